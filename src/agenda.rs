@@ -0,0 +1,170 @@
+use crate::todo::Item;
+use chrono::{Datelike, Days, NaiveDate};
+use std::collections::HashMap;
+
+/// A column of the agenda view. Columns appear in declaration order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bucket {
+    Overdue,
+    Today,
+    Tomorrow,
+    ThisWeek,
+    Later,
+    NoDate,
+}
+
+impl Bucket {
+    pub const ALL: &[Self] = &[
+        Self::Overdue,
+        Self::Today,
+        Self::Tomorrow,
+        Self::ThisWeek,
+        Self::Later,
+        Self::NoDate,
+    ];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Overdue => "Overdue",
+            Self::Today => "Today",
+            Self::Tomorrow => "Tomorrow",
+            Self::ThisWeek => "This week",
+            Self::Later => "Later",
+            Self::NoDate => "No date",
+        }
+    }
+
+    /// Where `item` belongs. The `due:` date decides; an item without one is
+    /// placed by its `t:` date, and one whose threshold has passed is due
+    /// today rather than overdue.
+    pub fn of(item: &Item, today: NaiveDate) -> Self {
+        let date = match (item.due_date(), item.threshold_date()) {
+            (Some(due), _) => due,
+            (None, Some(t)) => t.max(today),
+            (None, None) => return Self::NoDate,
+        };
+        let days_to_sunday = u64::from(6 - today.weekday().num_days_from_monday());
+        if date < today {
+            Self::Overdue
+        } else if date == today {
+            Self::Today
+        } else if Some(date) == today.succ_opt() {
+            Self::Tomorrow
+        } else if today
+            .checked_add_days(Days::new(days_to_sunday))
+            .is_some_and(|end_of_week| date <= end_of_week)
+        {
+            Self::ThisWeek
+        } else {
+            Self::Later
+        }
+    }
+
+    fn position(label: &str) -> usize {
+        Self::ALL
+            .iter()
+            .position(|b| b.label() == label)
+            .unwrap_or(Self::ALL.len())
+    }
+}
+
+/// The date an item is ordered by inside its bucket.
+fn sort_date(item: &Item) -> Option<NaiveDate> {
+    item.due_date().or_else(|| item.threshold_date())
+}
+
+/// Group todos into agenda buckets keyed by label, each sorted by date.
+/// Items keep their relative order on equal dates.
+pub fn group_by_date(todos: &[Item], today: NaiveDate) -> HashMap<String, Vec<Item>> {
+    let mut grouped: HashMap<String, Vec<Item>> = HashMap::new();
+    for todo in todos {
+        grouped
+            .entry(Bucket::of(todo, today).label().to_string())
+            .or_default()
+            .push(todo.clone());
+    }
+    for items in grouped.values_mut() {
+        items.sort_by_key(sort_date);
+    }
+    grouped
+}
+
+/// Order bucket labels chronologically instead of alphabetically.
+pub fn sort_columns(names: &mut [String]) {
+    names.sort_by_key(|name| Bucket::position(name));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    // 2026-10-16 is a Friday
+    const TODAY: &str = "2026-10-16";
+
+    fn bucket(line: &str) -> Bucket {
+        Bucket::of(&Item::parse(line, 1), date(TODAY))
+    }
+
+    #[test]
+    fn test_bucket_by_due_date() {
+        assert_eq!(bucket("Pay due:2026-10-15"), Bucket::Overdue);
+        assert_eq!(bucket("Pay due:2026-10-16"), Bucket::Today);
+        assert_eq!(bucket("Pay due:2026-10-17"), Bucket::Tomorrow);
+        assert_eq!(bucket("Pay due:2026-10-18"), Bucket::ThisWeek);
+        assert_eq!(bucket("Pay due:2026-10-19"), Bucket::Later);
+        assert_eq!(bucket("Pay"), Bucket::NoDate);
+        assert_eq!(bucket("Pay due:someday"), Bucket::NoDate);
+    }
+
+    #[test]
+    fn test_bucket_by_threshold_without_due() {
+        assert_eq!(bucket("Call t:2026-10-01"), Bucket::Today);
+        assert_eq!(bucket("Call t:2026-10-17"), Bucket::Tomorrow);
+        assert_eq!(bucket("Call t:2026-11-01"), Bucket::Later);
+        // due: wins over t:
+        assert_eq!(bucket("Call t:2026-10-01 due:2026-10-30"), Bucket::Later);
+    }
+
+    #[test]
+    fn test_bucket_this_week_is_empty_on_sunday() {
+        let sunday = date("2026-10-18");
+        let item = Item::parse("Pay due:2026-10-19", 1);
+        assert_eq!(Bucket::of(&item, sunday), Bucket::Tomorrow);
+        let item = Item::parse("Pay due:2026-10-20", 1);
+        assert_eq!(Bucket::of(&item, sunday), Bucket::Later);
+    }
+
+    #[test]
+    fn test_group_by_date_sorts_within_bucket() {
+        let todos = vec![
+            Item::parse("B due:2026-12-01", 1),
+            Item::parse("A due:2026-11-01", 2),
+            Item::parse("C", 3),
+            Item::parse("D t:2026-11-15", 4),
+        ];
+        let grouped = group_by_date(&todos, date(TODAY));
+        let later: Vec<&str> = grouped["Later"]
+            .iter()
+            .map(|t| t.description.as_str())
+            .collect();
+        assert_eq!(later, ["A", "D", "B"]);
+        assert_eq!(grouped["No date"].len(), 1);
+        assert!(!grouped.contains_key("Today"));
+    }
+
+    #[test]
+    fn test_sort_columns_in_bucket_order() {
+        let mut names = vec![
+            "No date".to_string(),
+            "Later".to_string(),
+            "Overdue".to_string(),
+            "Today".to_string(),
+        ];
+        sort_columns(&mut names);
+        assert_eq!(names, ["Overdue", "Today", "Later", "No date"]);
+    }
+}
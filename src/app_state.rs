@@ -1,16 +1,28 @@
+use crate::agenda;
+use crate::archive;
+use crate::columns::ColumnLayout;
 use crate::crmux::Plan;
+use crate::grouping::{self, GroupBy};
+use crate::line_editor::LineEditor;
 use crate::md_preview::{compute_meta, md_path};
+use crate::mouse::ClickMap;
+use crate::query::Query;
+use crate::search;
+use crate::store;
 use crate::templates::{TemplateEntry, insert_template, load_templates};
 use crate::todo::{
-    Item, add_missing_ids, append_todo, delete_todo, group_todos_by_project_owned,
-    has_todo_with_id, load_todos, mark_complete, move_to_file, set_priority,
+    Item, add_item, add_missing_ids, append_todo, delete_todo, find_line, has_todo_with_id,
+    load_todos, mark_complete, move_to_file, replace_line, set_priority, uncomplete,
 };
+use crate::todo_file::TodoFile;
+use crate::undo::{UndoStack, mark_ends, snapshot};
 use crate::url::{extract_urls, open_urls};
 use log::{debug, error};
 use std::{
-    collections::HashMap, fs, io::Write, os::unix::net::UnixStream, time::Duration,
+    collections::HashMap, error::Error, fs, io::Write, os::unix::net::UnixStream, time::Duration,
     time::SystemTime,
 };
+use uuid::Uuid;
 
 fn parse_frontmatter_cwd(content: &str) -> Option<String> {
     let content = content.trim();
@@ -72,6 +84,31 @@ pub struct TemplateState {
     pub focused: usize,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InputKind {
+    Add,
+    /// Editing the raw line of the item with this id
+    Edit(String),
+    Search,
+    Filter,
+}
+
+impl InputKind {
+    pub const fn prompt(&self) -> &'static str {
+        match self {
+            Self::Add => "Add: ",
+            Self::Edit(_) => "Edit: ",
+            Self::Search => "/",
+            Self::Filter => "Filter: ",
+        }
+    }
+}
+
+pub struct InputState {
+    pub kind: InputKind,
+    pub editor: LineEditor,
+}
+
 impl HintState {
     pub fn cell_label(&self, col: usize, row: usize) -> Option<&str> {
         self.labels
@@ -87,7 +124,7 @@ pub enum HintOutcome {
     Cancelled,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, clap::ValueEnum)]
 pub enum ViewMode {
     Todo,
     Ref,
@@ -106,14 +143,10 @@ impl ViewMode {
     ];
     pub const COUNT: usize = Self::ALL.len();
 
-    pub const fn filename(self) -> &'static str {
-        match self {
-            Self::Todo => "todo.txt",
-            Self::Ref => "ref.txt",
-            Self::Inbox => "inbox.txt",
-            Self::Someday => "someday.txt",
-            Self::Waiting => "waiting.txt",
-        }
+    /// File of this mode in the todotxt directory, as configured in
+    /// `[files]` (`todo.txt`, `inbox.txt`, ... by default).
+    pub fn filename(self) -> &'static str {
+        crate::config::get().files.for_mode(self)
     }
 
     pub const fn label(self) -> &'static str {
@@ -125,14 +158,37 @@ impl ViewMode {
             Self::Waiting => "Waiting",
         }
     }
+}
 
-    pub const fn shortcut_key(self) -> char {
+/// How cards are laid out into columns.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum View {
+    /// One column per project, showing the active mode file
+    #[default]
+    Board,
+    /// Columns by due date, across all mode files
+    Agenda,
+    /// Completed items from `done.txt` by completion date, read-only
+    Done,
+}
+
+impl View {
+    pub const fn label(self) -> &'static str {
         match self {
-            Self::Todo => 't',
-            Self::Ref => 'r',
-            Self::Inbox => 'i',
-            Self::Someday => 's',
-            Self::Waiting => 'w',
+            Self::Board => "Board",
+            Self::Agenda => "Agenda",
+            Self::Done => "Done",
         }
     }
 }
@@ -163,9 +219,15 @@ pub fn generate_hint_labels(n: usize) -> Vec<String> {
 }
 
 pub fn count_items_in_file(path: &str) -> usize {
-    fs::read_to_string(path)
-        .map(|c| c.lines().filter(|l| !l.trim().is_empty()).count())
-        .unwrap_or(0)
+    TodoFile::read(path).map_or(0, |doc| doc.items().count())
+}
+
+/// Something RPC subscribers are told about that cannot be seen by diffing
+/// the state between polls.
+#[derive(Clone, Debug)]
+pub enum AppEvent {
+    ItemCompleted(Box<Item>),
+    FileReloaded(String),
 }
 
 #[allow(clippy::struct_excessive_bools)]
@@ -185,11 +247,27 @@ pub struct AppState {
     pub show_help: bool,
     pub update_available: Option<String>,
     pub view_mode: ViewMode,
+    pub view: View,
+    /// Board grouping chosen for each mode; missing modes group by project.
+    pub groupings: HashMap<ViewMode, GroupBy>,
+    /// Pinned, hidden and sort settings for board columns.
+    pub columns: ColumnLayout,
+    /// Mode file of each item shown in the agenda, by id.
+    agenda_modes: HashMap<String, ViewMode>,
     pub mode_counts: [usize; ViewMode::COUNT],
     pub hint: Option<HintState>,
     pub pending_enter_hint: bool,
     pub template: Option<TemplateState>,
     pub pending_enter_template: bool,
+    pub input: Option<InputState>,
+    /// Active `/` search; only matching cards are grouped into columns.
+    pub search: Option<String>,
+    /// Active structured filter query (`F`), applied on top of the search.
+    pub filter: Option<Query>,
+    /// Events not yet delivered to RPC subscribers.
+    pub events: Vec<AppEvent>,
+    /// Where the last frame drew tabs, columns and cards, for mouse clicks.
+    pub click_map: ClickMap,
 }
 
 impl AppState {
@@ -233,6 +311,8 @@ impl AppState {
     pub fn new(todos: Vec<Item>, nvim_socket: String, todotxt_dir: String) -> Self {
         let crmux_version = crate::crmux::detect();
         let claude_available = crate::claude::detect();
+        let groupings = grouping::load(&todotxt_dir);
+        let columns = ColumnLayout::load(&todotxt_dir);
 
         let mut state = Self {
             todos,
@@ -250,11 +330,20 @@ impl AppState {
             show_help: false,
             update_available: None,
             view_mode: ViewMode::Todo,
+            view: View::Board,
+            groupings,
+            columns,
+            agenda_modes: HashMap::new(),
             mode_counts: [0; ViewMode::COUNT],
             hint: None,
             pending_enter_hint: false,
             template: None,
             pending_enter_template: false,
+            input: None,
+            search: None,
+            filter: None,
+            events: Vec::new(),
+            click_map: ClickMap::default(),
         };
         state.update_derived_state();
         state.refresh_mode_counts();
@@ -268,28 +357,108 @@ impl AppState {
         }
     }
 
+    /// Reload the shown items. On the board that is `todo_file`; the agenda
+    /// reloads every mode file.
     pub fn reload_todos(&mut self, todo_file: &str) {
-        if let Err(e) = add_missing_ids(todo_file) {
-            error!("Failed to add missing IDs on reload: {e}");
-        }
-        match load_todos(todo_file) {
+        let loaded = match self.view {
+            View::Agenda => self.load_agenda_todos(),
+            View::Done => self.load_done_todos(),
+            View::Board => {
+                if let Err(e) = add_missing_ids(todo_file) {
+                    error!("Failed to add missing IDs on reload: {e}");
+                }
+                load_todos(todo_file)
+            }
+        };
+        match loaded {
             Ok(new_todos) => {
                 debug!("Reloaded {} todos from file", new_todos.len());
                 self.todos = new_todos;
                 self.update_derived_state();
                 self.refresh_mode_counts();
+                self.events
+                    .push(AppEvent::FileReloaded(todo_file.to_string()));
             }
             Err(e) => error!("Failed to reload todos: {e}"),
         }
     }
 
+    fn load_agenda_todos(&mut self) -> Result<Vec<Item>, Box<dyn Error>> {
+        self.agenda_modes.clear();
+        let mut todos = Vec::new();
+        for mode in ViewMode::ALL {
+            let file = format!("{}/{}", self.todotxt_dir, mode.filename());
+            if !std::path::Path::new(&file).exists() {
+                continue;
+            }
+            if let Err(e) = add_missing_ids(&file) {
+                error!("Failed to add missing IDs to {file}: {e}");
+            }
+            for item in load_todos(&file)? {
+                if let Some(id) = item.id.clone() {
+                    self.agenda_modes.insert(id, *mode);
+                }
+                todos.push(item);
+            }
+        }
+        Ok(todos)
+    }
+
+    /// The archive is shown as it is: lines without an id are not given one,
+    /// so browsing never rewrites `done.txt`.
+    fn load_done_todos(&self) -> Result<Vec<Item>, Box<dyn Error>> {
+        let file = self.done_file();
+        if !std::path::Path::new(&file).exists() {
+            return Ok(Vec::new());
+        }
+        load_todos(&file)
+    }
+
+    /// Reload the active file after it was changed outside the key handlers
+    /// (undo/redo, RPC), keeping the cursor on the same item if it survived.
+    pub fn reload_keeping_selection(&mut self) {
+        let id = self.get_current_todo_id().map(str::to_string);
+        self.reload_todos(&self.active_file());
+        if let Some(id) = id {
+            self.select_todo_by_id(&id);
+        }
+    }
+
     pub fn refresh_md_previews(&mut self) {
         self.refresh_md_meta();
-        self.grouped_todos = group_todos_by_project_owned(&self.todos);
+        self.grouped_todos = self.group_visible_todos();
+    }
+
+    /// Group todos into columns, dropping hidden columns, cards that do
+    /// not match the active search and any column left empty by that.
+    fn group_visible_todos(&self) -> HashMap<String, Vec<Item>> {
+        let mut grouped = match self.view {
+            View::Board => {
+                let mut grouped = self.group_by().group(&self.todos);
+                grouped.retain(|name, _| !self.columns.is_hidden(name));
+                grouped
+            }
+            View::Agenda => agenda::group_by_date(&self.todos, chrono::Local::now().date_naive()),
+            View::Done => archive::group_by_completion(&self.todos),
+        };
+        if let Some(filter) = self.filter.as_ref() {
+            for todos in grouped.values_mut() {
+                todos.retain(|t| filter.matches(t, &self.todotxt_dir));
+            }
+            grouped.retain(|_, todos| !todos.is_empty());
+        }
+        if let Some(query) = self.search.as_deref() {
+            for todos in grouped.values_mut() {
+                todos.retain(|t| search::matches(t, query));
+            }
+            grouped.retain(|_, todos| !todos.is_empty());
+        }
+        grouped
     }
 
     fn refresh_md_meta(&mut self) {
-        if !matches!(self.view_mode, ViewMode::Todo | ViewMode::Waiting) {
+        if self.view == View::Board && !matches!(self.view_mode, ViewMode::Todo | ViewMode::Waiting)
+        {
             for t in &mut self.todos {
                 t.md_meta = None;
             }
@@ -303,9 +472,20 @@ impl AppState {
 
     fn update_derived_state(&mut self) {
         self.refresh_md_meta();
-        self.grouped_todos = group_todos_by_project_owned(&self.todos);
-        self.project_names = self.grouped_todos.keys().cloned().collect();
-        self.project_names.sort();
+        self.grouped_todos = self.group_visible_todos();
+        self.project_names = match self.view {
+            View::Board => self.columns.arrange(&self.grouped_todos, &self.todotxt_dir),
+            View::Agenda => {
+                let mut names: Vec<String> = self.grouped_todos.keys().cloned().collect();
+                agenda::sort_columns(&mut names);
+                names
+            }
+            View::Done => {
+                let mut names: Vec<String> = self.grouped_todos.keys().cloned().collect();
+                archive::sort_columns(&mut names);
+                names
+            }
+        };
 
         let visible = &self.project_names;
         if self.current_column >= visible.len() {
@@ -370,30 +550,26 @@ impl AppState {
                     }
                 }
             }
-            'h' => {
-                if !visible.is_empty() {
-                    let len = visible.len();
-                    let new_col = (self.current_column + len - 1) % len;
-                    if new_col != self.current_column {
-                        self.current_column = new_col;
-                        self.selected_in_column = 0;
-                        self.scroll_offset = 0;
-                        if let Some(todo_id) = self.get_current_todo_id() {
-                            self.send_vim_command(todo_id);
-                        }
+            'h' if !visible.is_empty() => {
+                let len = visible.len();
+                let new_col = (self.current_column + len - 1) % len;
+                if new_col != self.current_column {
+                    self.current_column = new_col;
+                    self.selected_in_column = 0;
+                    self.scroll_offset = 0;
+                    if let Some(todo_id) = self.get_current_todo_id() {
+                        self.send_vim_command(todo_id);
                     }
                 }
             }
-            'l' => {
-                if !visible.is_empty() {
-                    let new_col = (self.current_column + 1) % visible.len();
-                    if new_col != self.current_column {
-                        self.current_column = new_col;
-                        self.selected_in_column = 0;
-                        self.scroll_offset = 0;
-                        if let Some(todo_id) = self.get_current_todo_id() {
-                            self.send_vim_command(todo_id);
-                        }
+            'l' if !visible.is_empty() => {
+                let new_col = (self.current_column + 1) % visible.len();
+                if new_col != self.current_column {
+                    self.current_column = new_col;
+                    self.selected_in_column = 0;
+                    self.scroll_offset = 0;
+                    if let Some(todo_id) = self.get_current_todo_id() {
+                        self.send_vim_command(todo_id);
                     }
                 }
             }
@@ -401,47 +577,135 @@ impl AppState {
         }
     }
 
+    fn undo_stack(&self) -> UndoStack {
+        UndoStack::new(&self.todotxt_dir)
+    }
+
+    /// Run a file mutation, recording the before-images of `paths` so it can
+    /// be undone. The entry is recorded even if `op` fails midway.
+    fn with_undo<T>(
+        &self,
+        label: &str,
+        paths: &[String],
+        op: impl FnOnce() -> Result<T, Box<dyn Error>>,
+    ) -> Result<T, Box<dyn Error>> {
+        self.with_undo_appending(label, paths, &[], op)
+    }
+
+    /// Like `with_undo`, but `appended` files are only appended to, so just
+    /// the added lines are journaled.
+    fn with_undo_appending<T>(
+        &self,
+        label: &str,
+        paths: &[String],
+        appended: &[String],
+        op: impl FnOnce() -> Result<T, Box<dyn Error>>,
+    ) -> Result<T, Box<dyn Error>> {
+        let _lock = store::lock(std::path::Path::new(&self.todotxt_dir))?;
+        let before = snapshot(paths);
+        let ends = mark_ends(appended);
+        let result = op();
+        if let Err(e) = self.undo_stack().record_appending(label, before, ends) {
+            error!("Failed to record undo entry: {e}");
+        }
+        result
+    }
+
+    pub fn handle_undo(&mut self) {
+        let result = self.undo_stack().undo();
+        self.finish_undo_redo(result, "Undid", "Nothing to undo");
+    }
+
+    pub fn handle_redo(&mut self) {
+        let result = self.undo_stack().redo();
+        self.finish_undo_redo(result, "Redid", "Nothing to redo");
+    }
+
+    fn finish_undo_redo(
+        &mut self,
+        result: Result<Option<String>, Box<dyn Error>>,
+        verb: &str,
+        empty: &str,
+    ) {
+        match result {
+            Ok(Some(label)) => {
+                self.reload_keeping_selection();
+                self.status_message = Some(format!("{verb}: {label}"));
+            }
+            Ok(None) => self.status_message = Some(empty.to_string()),
+            Err(e) => {
+                error!("{verb} failed: {e}");
+                self.status_message = Some(format!("{verb} failed: {e}"));
+            }
+        }
+    }
+
     pub fn handle_complete_todo(&mut self, todo_file: &str) {
-        if let Some(todo_id) = self.get_current_todo_id() {
-            debug!("Attempting to mark todo as complete: {todo_id}");
-            match mark_complete(todo_file, todo_id) {
-                Ok(()) => {
-                    debug!("Successfully marked todo as complete: {todo_id}");
-                    self.reload_todos(todo_file);
+        let Some(todo_id) = self.get_current_todo_id().map(str::to_string) else {
+            return;
+        };
+        debug!("Attempting to mark todo as complete: {todo_id}");
+        let done_file = std::path::Path::new(todo_file)
+            .with_file_name(&crate::config::get().files.done)
+            .to_string_lossy()
+            .into_owned();
+        // A recurring item's next occurrence may get a copy of the md
+        let next_id = Uuid::new_v4().to_string();
+        let paths = [todo_file.to_string(), md_path(&self.todotxt_dir, &next_id)];
+        let result = self.with_undo_appending("Complete", &paths, &[done_file], || {
+            mark_complete(todo_file, &todo_id, &next_id)
+        });
+        match result {
+            Ok(None) => {}
+            Ok(Some(completion)) => {
+                debug!("Successfully marked todo as complete: {todo_id}");
+                self.events
+                    .push(AppEvent::ItemCompleted(Box::new(completion.archived)));
+                self.reload_todos(todo_file);
+                if let Some(next) = completion.next {
+                    self.status_message = Some(next.due_date().map_or_else(
+                        || format!("Next: {}", next.description),
+                        |due| format!("Next: {} (due {due})", next.description),
+                    ));
                 }
-                Err(e) => error!("Failed to mark todo as complete: {e}"),
             }
+            Err(e) => error!("Failed to mark todo as complete: {e}"),
         }
     }
 
     pub fn handle_delete_todo(&mut self) {
-        let file = self.active_file();
+        let file = self.current_file();
         let Some(todo_id) = self.get_current_todo_id().map(str::to_string) else {
             return;
         };
         debug!("Attempting to delete todo: {todo_id}");
-        match delete_todo(&file, &todo_id) {
-            Ok(true) => {
-                let path = md_path(&self.todotxt_dir, &todo_id);
-                if let Err(e) = fs::remove_file(&path)
-                    && e.kind() != std::io::ErrorKind::NotFound
-                {
-                    debug!("Failed to remove md file {path}: {e}");
-                }
-                self.reload_todos(&file);
+        let path = md_path(&self.todotxt_dir, &todo_id);
+        let paths = [file.clone(), path.clone()];
+        let result = self.with_undo("Delete", &paths, || {
+            let removed = delete_todo(&file, &todo_id)?;
+            if removed
+                && let Err(e) = fs::remove_file(&path)
+                && e.kind() != std::io::ErrorKind::NotFound
+            {
+                debug!("Failed to remove md file {path}: {e}");
             }
+            Ok(removed)
+        });
+        match result {
+            Ok(true) => self.reload_todos(&file),
             Ok(false) => {}
             Err(e) => error!("Failed to delete todo: {e}"),
         }
     }
 
     pub fn handle_set_priority(&mut self, priority: Option<char>) {
-        let file = self.active_file();
+        let file = self.current_file();
         let Some(id) = self.get_current_todo_id().map(str::to_string) else {
             return;
         };
         debug!("Setting priority {priority:?} on {id}");
-        match set_priority(&file, &id, priority) {
+        let paths = [file.clone()];
+        match self.with_undo("Priority", &paths, || set_priority(&file, &id, priority)) {
             Ok(()) => self.reload_todos(&file),
             Err(e) => error!("Failed to set priority: {e}"),
         }
@@ -467,6 +731,325 @@ impl AppState {
         }
     }
 
+    /// Open the add prompt. With `prefill_column`, the tag of the current
+    /// column (`+project`, `@context`, `key:value`, or the priority) is
+    /// prefilled so the new item lands in it.
+    pub fn start_add_item(&mut self, prefill_column: bool) {
+        // Agenda columns are dates, not tags
+        let prefill = self
+            .project_names
+            .get(self.current_column)
+            .filter(|_| prefill_column && self.view == View::Board)
+            .and_then(|column| self.group_by().prefill(column));
+        let editor = prefill.map_or_else(
+            || LineEditor::new(""),
+            |(text, cursor)| LineEditor::with_cursor(&text, cursor),
+        );
+        self.input = Some(InputState {
+            kind: InputKind::Add,
+            editor,
+        });
+        self.status_message = None;
+    }
+
+    pub fn cancel_input(&mut self) {
+        let input = self.input.take();
+        self.status_message = None;
+        if input.is_some_and(|i| i.kind == InputKind::Search) {
+            self.clear_search();
+        }
+    }
+
+    /// Called after every edit of the prompt text; drives the live search.
+    pub fn input_changed(&mut self) {
+        let Some(input) = self.input.as_ref() else {
+            return;
+        };
+        if input.kind == InputKind::Search {
+            let text = input.editor.text().to_string();
+            self.apply_search(&text);
+        }
+    }
+
+    pub fn start_search(&mut self) {
+        let current = self.search.clone().unwrap_or_default();
+        self.input = Some(InputState {
+            kind: InputKind::Search,
+            editor: LineEditor::new(&current),
+        });
+        self.status_message = None;
+    }
+
+    /// Filter the columns by `query`, keeping the selected card if it still
+    /// matches and jumping to the first match otherwise.
+    pub fn apply_search(&mut self, query: &str) {
+        let selected = self.get_current_todo_id().map(str::to_string);
+        let query = query.trim();
+        self.search = (!query.is_empty()).then(|| query.to_string());
+        self.update_derived_state();
+        if !selected.is_some_and(|id| self.select_todo_by_id(&id)) {
+            self.current_column = 0;
+            self.selected_in_column = 0;
+            self.scroll_offset = 0;
+        }
+    }
+
+    /// Drop the search filter, keeping the selection on the card that was
+    /// selected among the matches.
+    pub fn clear_search(&mut self) {
+        let selected = self.get_current_todo_id().map(str::to_string);
+        self.search = None;
+        self.update_derived_state();
+        if let Some(id) = selected {
+            self.select_todo_by_id(&id);
+        }
+        self.status_message = None;
+    }
+
+    pub fn start_filter(&mut self) {
+        let current = self
+            .filter
+            .as_ref()
+            .map_or_else(String::new, |q| q.source().to_string());
+        self.input = Some(InputState {
+            kind: InputKind::Filter,
+            editor: LineEditor::new(&current),
+        });
+        self.status_message = None;
+    }
+
+    /// Parse and apply a filter query. An empty query removes the filter.
+    pub fn apply_filter(&mut self, text: &str) -> Result<(), String> {
+        let query = Query::parse(text).map_err(|e| format!("Filter error: {e}"))?;
+        let selected = self.get_current_todo_id().map(str::to_string);
+        self.filter = (!text.trim().is_empty()).then_some(query);
+        self.update_derived_state();
+        if !selected.is_some_and(|id| self.select_todo_by_id(&id)) {
+            self.current_column = 0;
+            self.selected_in_column = 0;
+            self.scroll_offset = 0;
+        }
+        Ok(())
+    }
+
+    pub fn clear_filter(&mut self) {
+        let selected = self.get_current_todo_id().map(str::to_string);
+        self.filter = None;
+        self.update_derived_state();
+        if let Some(id) = selected {
+            self.select_todo_by_id(&id);
+        }
+        self.status_message = None;
+    }
+
+    fn visible_cells(&self) -> Vec<(usize, usize)> {
+        self.project_names
+            .iter()
+            .enumerate()
+            .flat_map(|(col, name)| {
+                let len = self.grouped_todos.get(name).map_or(0, Vec::len);
+                (0..len).map(move |row| (col, row))
+            })
+            .collect()
+    }
+
+    /// Move to the next (or previous) search match, column by column,
+    /// wrapping at either end.
+    pub fn cycle_match(&mut self, forward: bool) {
+        if self.search.is_none() {
+            return;
+        }
+        let cells = self.visible_cells();
+        if cells.is_empty() {
+            self.status_message = Some("No matches".to_string());
+            return;
+        }
+        let len = cells.len();
+        let current = cells
+            .iter()
+            .position(|&c| c == (self.current_column, self.selected_in_column))
+            .unwrap_or(0);
+        let next = if forward {
+            (current + 1) % len
+        } else {
+            (current + len - 1) % len
+        };
+        (self.current_column, self.selected_in_column) = cells[next];
+        self.scroll_offset = 0;
+        if let Some(id) = self.get_current_todo_id().map(str::to_string) {
+            self.send_vim_command(&id);
+        }
+        self.status_message = Some(format!("Match {}/{len}", next + 1));
+    }
+
+    /// Open the edit prompt on the selected item's raw line in its file.
+    pub fn start_edit_item(&mut self) {
+        let Some(id) = self.get_current_todo_id().map(str::to_string) else {
+            return;
+        };
+        match find_line(&self.file_of(&id), &id) {
+            Ok(Some(line)) => {
+                self.input = Some(InputState {
+                    kind: InputKind::Edit(id),
+                    editor: LineEditor::new(&line),
+                });
+                self.status_message = None;
+            }
+            Ok(None) => {
+                self.status_message = Some(format!("Item {id} not found in file"));
+            }
+            Err(e) => {
+                error!("Failed to read line for {id}: {e}");
+                self.status_message = Some(format!("Failed to read item: {e}"));
+            }
+        }
+    }
+
+    pub fn submit_input(&mut self) {
+        let Some(input) = self.input.take() else {
+            return;
+        };
+        let text = input.editor.text().trim().to_string();
+        match &input.kind {
+            InputKind::Add if text.is_empty() => self.status_message = None,
+            InputKind::Add => self.handle_add_item(&text),
+            InputKind::Search if text.is_empty() => self.clear_search(),
+            InputKind::Search => {
+                self.apply_search(&text);
+                let count = self.visible_cells().len();
+                self.status_message = Some(format!("/{text}: {count} match(es), n/N: cycle"));
+            }
+            InputKind::Filter => match self.apply_filter(&text) {
+                Ok(()) => {
+                    let count = self.visible_cells().len();
+                    self.status_message =
+                        (!text.is_empty()).then(|| format!("Filter: {count} card(s) match"));
+                }
+                Err(msg) => {
+                    self.status_message = Some(msg);
+                    self.input = Some(input);
+                }
+            },
+            InputKind::Edit(id) => {
+                let id = id.clone();
+                if let Err(msg) = self.handle_edit_item(&id, &text) {
+                    // Keep the prompt open so the line can be fixed
+                    self.status_message = Some(msg);
+                    self.input = Some(input);
+                }
+            }
+        }
+    }
+
+    fn handle_edit_item(&mut self, id: &str, text: &str) -> Result<(), String> {
+        let file = self.file_of(id);
+        debug!("Replacing line of {id} in {file}: {text}");
+        let paths = [file.clone()];
+        match self.with_undo("Edit", &paths, || replace_line(&file, id, text)) {
+            Ok(true) => {
+                self.reload_todos(&file);
+                self.select_todo_by_id(id);
+                self.status_message = Some("Updated item".to_string());
+                Ok(())
+            }
+            Ok(false) => {
+                self.status_message = Some(format!("Item {id} no longer exists"));
+                Ok(())
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn handle_add_item(&mut self, text: &str) {
+        let file = self.active_file();
+        debug!("Adding item to {file}: {text}");
+        let paths = [file.clone()];
+        match self.with_undo("Add", &paths, || add_item(&file, text)) {
+            Ok(item) => {
+                self.reload_todos(&file);
+                if let Some(id) = item.id.as_deref() {
+                    self.select_todo_by_id(id);
+                }
+                self.status_message = Some(format!("Added: {}", item.description));
+            }
+            Err(e) => {
+                error!("Failed to add item: {e}");
+                self.status_message = Some(format!("Failed to add item: {e}"));
+            }
+        }
+    }
+
+    /// Move the selection to the first card with `id`. Returns false when no
+    /// visible column contains it.
+    pub fn select_todo_by_id(&mut self, id: &str) -> bool {
+        let found = self
+            .project_names
+            .iter()
+            .enumerate()
+            .find_map(|(col, name)| {
+                self.grouped_todos
+                    .get(name)?
+                    .iter()
+                    .position(|t| t.id.as_deref() == Some(id))
+                    .map(|row| (col, row))
+            });
+        let Some((col, row)) = found else {
+            return false;
+        };
+        self.current_column = col;
+        self.selected_in_column = row;
+        self.scroll_offset = 0;
+        self.send_vim_command(id);
+        true
+    }
+
+    /// Select card `row` of column `col`, as a mouse click does. The column's
+    /// scroll position is kept so the clicked card stays under the pointer.
+    pub fn select_card(&mut self, col: usize, row: usize) {
+        let Some(len) = self
+            .project_names
+            .get(col)
+            .and_then(|name| self.grouped_todos.get(name))
+            .map(Vec::len)
+        else {
+            return;
+        };
+        let row = row.min(len.saturating_sub(1));
+        if col == self.current_column && row == self.selected_in_column {
+            return;
+        }
+        self.status_message = None;
+        if col != self.current_column {
+            self.current_column = col;
+            self.scroll_offset = 0;
+        }
+        self.selected_in_column = row;
+        if let Some(todo_id) = self.get_current_todo_id() {
+            self.send_vim_command(todo_id);
+        }
+    }
+
+    /// Move the selection one card down or up in column `col`, without
+    /// wrapping around, for the scroll wheel. Scrolling over another column
+    /// selects its first card.
+    pub fn scroll_column(&mut self, col: usize, down: bool) {
+        if col != self.current_column {
+            self.select_card(col, 0);
+        } else if down {
+            self.select_card(col, self.selected_in_column + 1);
+        } else {
+            self.select_card(col, self.selected_in_column.saturating_sub(1));
+        }
+    }
+
+    /// Open the selected item's detail md in nvim, even if it is already
+    /// showing there.
+    pub fn open_current_md(&self) {
+        if let Some(todo_id) = self.get_current_todo_id() {
+            self.send_vim_command(todo_id);
+        }
+    }
+
     pub const fn toggle_help(&mut self) {
         self.show_help = !self.show_help;
     }
@@ -493,7 +1076,7 @@ impl AppState {
     }
 
     fn templates_dir(&self) -> std::path::PathBuf {
-        std::path::PathBuf::from(&self.todotxt_dir).join("templates")
+        std::path::PathBuf::from(&self.todotxt_dir).join(&crate::config::get().templates.dir)
     }
 
     pub fn enter_template_mode(&mut self) {
@@ -568,7 +1151,14 @@ impl AppState {
 
         // Fallback: nvim unreachable, write directly to disk.
         let path = md_path(&self.todotxt_dir, &todo_id);
-        match insert_template(std::path::Path::new(&path), &entry.content) {
+        let paths = [path.clone()];
+        let result = self.with_undo("Insert template", &paths, || {
+            Ok(insert_template(
+                std::path::Path::new(&path),
+                &entry.content,
+            )?)
+        });
+        match result {
             Ok(()) => {
                 self.status_message = Some(format!("Inserted template: {}", entry.name));
             }
@@ -619,6 +1209,32 @@ impl AppState {
         format!("{}/{}", self.todotxt_dir, self.view_mode.filename())
     }
 
+    pub fn done_file(&self) -> String {
+        format!("{}/{}", self.todotxt_dir, crate::config::get().files.done)
+    }
+
+    /// Mode file holding the item with `id`. On the board that is always
+    /// the active mode; the agenda mixes items from every mode file.
+    pub fn mode_of(&self, id: &str) -> ViewMode {
+        match self.view {
+            View::Board | View::Done => self.view_mode,
+            View::Agenda => self.agenda_modes.get(id).copied().unwrap_or(self.view_mode),
+        }
+    }
+
+    pub fn file_of(&self, id: &str) -> String {
+        if self.view == View::Done {
+            return self.done_file();
+        }
+        format!("{}/{}", self.todotxt_dir, self.mode_of(id).filename())
+    }
+
+    /// File of the selected item, falling back to the active file.
+    pub fn current_file(&self) -> String {
+        self.get_current_todo_id()
+            .map_or_else(|| self.active_file(), |id| self.file_of(id))
+    }
+
     pub fn current_mode_index(&self) -> usize {
         ViewMode::ALL
             .iter()
@@ -636,15 +1252,16 @@ impl AppState {
         self.set_view_mode(ViewMode::ALL[prev_idx]);
     }
 
+    /// Show `mode` on the board, leaving the agenda if it was open.
     pub fn set_view_mode(&mut self, mode: ViewMode) {
-        if self.view_mode == mode {
+        if self.view_mode == mode && self.view == View::Board {
             return;
         }
         self.view_mode = mode;
+        self.view = View::Board;
         let file = self.active_file();
-        if !std::path::Path::new(&file).exists()
-            && let Err(e) = std::fs::write(&file, "")
-        {
+        // Never truncates: another process may create the file meanwhile
+        if let Err(e) = fs::OpenOptions::new().create(true).append(true).open(&file) {
             error!("Failed to create {file}: {e}");
             return;
         }
@@ -654,16 +1271,82 @@ impl AppState {
         self.scroll_offset = 0;
     }
 
-    pub fn handle_send_to(&mut self, target_mode: ViewMode) {
-        if target_mode == self.view_mode {
+    pub fn set_view(&mut self, view: View) {
+        if self.view == view {
             return;
         }
-        let source_file = self.active_file();
+        self.view = view;
+        self.reload_todos(&self.active_file());
+        self.current_column = 0;
+        self.selected_in_column = 0;
+        self.scroll_offset = 0;
+    }
+
+    pub fn toggle_agenda(&mut self) {
+        self.set_view(match self.view {
+            View::Board | View::Done => View::Agenda,
+            View::Agenda => View::Board,
+        });
+    }
+
+    pub fn toggle_done(&mut self) {
+        self.set_view(match self.view {
+            View::Board | View::Agenda => View::Done,
+            View::Done => View::Board,
+        });
+    }
+
+    /// Put the selected archived item back where it was completed from,
+    /// without its `x` and completion date.
+    pub fn handle_uncomplete(&mut self) {
+        if self.view != View::Done {
+            return;
+        }
+        let Some(item) = self.get_current_todo() else {
+            return;
+        };
+        let Some(todo_id) = item.id.clone() else {
+            self.status_message = Some("Only items with an id: can be restored".to_string());
+            return;
+        };
+        let mode = item.origin();
+        let done_file = self.done_file();
+        let dest_file = format!("{}/{}", self.todotxt_dir, mode.filename());
+        let paths = [done_file.clone(), dest_file.clone()];
+        match self.with_undo("Uncomplete", &paths, || {
+            uncomplete(&done_file, &dest_file, &todo_id)
+        }) {
+            Ok(Some(item)) => {
+                debug!("Restored {todo_id} to {dest_file}");
+                self.status_message = Some(format!(
+                    "Restored to {}: {}",
+                    mode.label(),
+                    item.description
+                ));
+                self.reload_todos(&done_file);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                error!("Failed to restore {todo_id}: {e}");
+                self.status_message = Some(format!("Failed to restore: {e}"));
+            }
+        }
+    }
+
+    pub fn handle_send_to(&mut self, target_mode: ViewMode) {
         if let Some(todo_id) = self.get_current_todo_id() {
+            if target_mode == self.mode_of(todo_id) {
+                return;
+            }
+            let source_file = self.file_of(todo_id);
             let target_name = target_mode.filename();
             let target_file = format!("{}/{target_name}", self.todotxt_dir);
             debug!("Attempting to move item to {target_name}: {todo_id}");
-            match move_to_file(&source_file, &target_file, todo_id) {
+            let paths = [source_file.clone(), target_file.clone()];
+            let label = format!("Send to {}", target_mode.label());
+            match self.with_undo(&label, &paths, || {
+                move_to_file(&source_file, &target_file, todo_id)
+            }) {
                 Ok(()) => {
                     debug!("Successfully moved item to {target_name}: {todo_id}");
                     self.reload_todos(&source_file);
@@ -674,15 +1357,135 @@ impl AppState {
     }
 
     pub fn send_initial_vim_command(&self) {
-        if let Some(todo_id) = self.get_current_todo_id() {
-            self.send_vim_command(todo_id);
-        }
+        self.open_current_md();
     }
 
+    /// Project of the selected item: its column when grouped by project,
+    /// otherwise the item's first `+project`.
     pub fn get_current_project_name(&self) -> Option<String> {
+        if self.view == View::Board && self.group_by() == GroupBy::Project {
+            return self.project_names.get(self.current_column).cloned();
+        }
+        self.get_current_todo()?.projects.first().cloned()
+    }
+
+    pub fn group_by(&self) -> GroupBy {
+        self.groupings
+            .get(&self.view_mode)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Apply a change to the column layout, save it and redraw the board
+    /// with the selection kept on the same item.
+    fn update_columns(&mut self, change: impl FnOnce(&mut ColumnLayout) -> String) {
+        if self.view != View::Board {
+            self.status_message = Some(format!("{} columns are fixed", self.view.label()));
+            return;
+        }
+        let id = self.get_current_todo_id().map(str::to_string);
+        let message = change(&mut self.columns);
+        self.status_message = Some(message);
+        if let Err(e) = self.columns.save(&self.todotxt_dir) {
+            error!("Failed to save column layout: {e}");
+            self.status_message = Some(format!("Failed to save column layout: {e}"));
+        }
+        self.update_derived_state();
+        if let Some(id) = id {
+            self.select_todo_by_id(&id);
+        }
+    }
+
+    /// Move the current column one place left or right.
+    pub fn move_current_column(&mut self, left: bool) {
+        if self.view != View::Board {
+            self.status_message = Some(format!("{} columns are fixed", self.view.label()));
+            return;
+        }
+        let names = self.project_names.clone();
+        match self.columns.move_column(&names, self.current_column, left) {
+            Ok(target) => {
+                let row = self.selected_in_column;
+                self.update_columns(|columns| format!("Column order: {}", columns.sort));
+                self.current_column = target;
+                self.selected_in_column = row;
+            }
+            Err(msg) => self.status_message = Some(msg.to_string()),
+        }
+    }
+
+    pub fn toggle_pin_current_column(&mut self) {
+        let Some(name) = self.get_current_column_name() else {
+            return;
+        };
+        self.update_columns(|columns| {
+            if columns.toggle_pin(&name) {
+                format!("Pinned {name}")
+            } else {
+                format!("Unpinned {name}")
+            }
+        });
+    }
+
+    pub fn hide_current_column(&mut self) {
+        let Some(name) = self.get_current_column_name() else {
+            return;
+        };
+        self.update_columns(|columns| {
+            columns.hide(&name);
+            format!("Hid {name} (zu: show all)")
+        });
+    }
+
+    pub fn unhide_all_columns(&mut self) {
+        self.update_columns(|columns| {
+            let count = columns.hidden.len();
+            columns.hidden.clear();
+            format!("Showing {count} hidden column(s)")
+        });
+    }
+
+    pub fn cycle_column_sort(&mut self) {
+        self.update_columns(|columns| {
+            columns.sort = columns.sort.next();
+            format!("Column order: {}", columns.sort)
+        });
+    }
+
+    fn get_current_column_name(&self) -> Option<String> {
         self.project_names.get(self.current_column).cloned()
     }
 
+    /// Switch the active mode's board to the next grouping and save it.
+    pub fn cycle_grouping(&mut self) {
+        match self.view {
+            View::Board => {}
+            View::Agenda => {
+                self.status_message = Some("The agenda is always grouped by date".to_string());
+                return;
+            }
+            View::Done => {
+                self.status_message = Some("Done is grouped by completion date".to_string());
+                return;
+            }
+        }
+        let id = self.get_current_todo_id().map(str::to_string);
+        let next = self.group_by().next(&self.todos);
+        self.status_message = Some(format!("Group by: {next}"));
+        self.groupings.insert(self.view_mode, next);
+        if let Err(e) = grouping::save(&self.todotxt_dir, &self.groupings) {
+            error!("Failed to save grouping: {e}");
+            self.status_message = Some(format!("Failed to save grouping: {e}"));
+        }
+        self.current_column = 0;
+        self.selected_in_column = 0;
+        self.scroll_offset = 0;
+        self.update_derived_state();
+        if let Some(id) = id {
+            self.select_todo_by_id(&id);
+        }
+    }
+
     fn get_current_todo_description(&self) -> Option<String> {
         let project_name = self.project_names.get(self.current_column)?;
         let todos = self.grouped_todos.get(project_name)?;
@@ -763,15 +1566,11 @@ impl AppState {
             return;
         };
         match key_char {
-            'j' => {
-                if modal.selected < modal.plans.len().saturating_sub(1) {
-                    modal.selected += 1;
-                }
+            'j' if modal.selected < modal.plans.len().saturating_sub(1) => {
+                modal.selected += 1;
             }
-            'k' => {
-                if modal.selected > 0 {
-                    modal.selected -= 1;
-                }
+            'k' if modal.selected > 0 => {
+                modal.selected -= 1;
             }
             ' ' => {
                 let idx = modal.selected;
@@ -796,6 +1595,19 @@ impl AppState {
             .and_then(|p| p.to_str())
             .unwrap_or(".");
 
+        let mut paths = vec![todo_file.to_string()];
+        paths.extend(
+            modal
+                .plans
+                .iter()
+                .zip(&modal.checked)
+                .filter(|(_, checked)| **checked)
+                .map(|(plan, _)| md_path(todotxt_dir, &plan.slug)),
+        );
+        let _lock = store::lock(std::path::Path::new(todotxt_dir))
+            .inspect_err(|e| error!("Failed to lock {todotxt_dir}: {e}"));
+        let before = snapshot(&paths);
+
         let mut imported = 0u32;
         let mut skipped = 0u32;
 
@@ -827,6 +1639,10 @@ impl AppState {
             imported += 1;
         }
 
+        if let Err(e) = self.undo_stack().record("Import plans", before) {
+            error!("Failed to record undo entry: {e}");
+        }
+
         self.status_message = Some(format!("Imported {imported} plans (skipped {skipped})"));
         self.reload_todos(todo_file);
     }
@@ -884,6 +1700,7 @@ impl AppState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::columns::ColumnSort;
     use crate::todo::Item;
     use std::fs;
 
@@ -1342,6 +2159,124 @@ mod tests {
         fs::remove_dir_all(&temp_dir).ok();
     }
 
+    fn agenda_state(dir: &tempfile::TempDir) -> AppState {
+        fs::write(
+            dir.path().join("todo.txt"),
+            "Pay rent +home due:2000-01-01 id:a-pay\nRead book +fun id:a-read\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("waiting.txt"),
+            "Parcel +home due:2000-01-02 id:a-parcel\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("inbox.txt"), "Idea\n").unwrap();
+        let mut state = create_test_state(vec![]);
+        state.todotxt_dir = dir.path().to_str().unwrap().to_string();
+        state.set_view(View::Agenda);
+        state
+    }
+
+    #[test]
+    fn test_agenda_groups_all_modes_by_date() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = agenda_state(&dir);
+        assert_eq!(state.project_names, ["Overdue", "No date"]);
+        let overdue: Vec<_> = state.grouped_todos["Overdue"]
+            .iter()
+            .filter_map(|t| t.id.as_deref())
+            .collect();
+        assert_eq!(overdue, ["a-pay", "a-parcel"]);
+        assert_eq!(state.grouped_todos["No date"].len(), 2);
+        // Items without ids get one, like on the board
+        let inbox = fs::read_to_string(dir.path().join("inbox.txt")).unwrap();
+        assert!(inbox.contains("id:"));
+    }
+
+    #[test]
+    fn test_agenda_mutations_use_the_item_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = agenda_state(&dir);
+        assert!(state.select_todo_by_id("a-parcel"));
+        assert_eq!(state.mode_of("a-parcel"), ViewMode::Waiting);
+        let file = state.current_file();
+        assert!(file.ends_with("waiting.txt"));
+
+        state.handle_complete_todo(&file);
+        let waiting = fs::read_to_string(dir.path().join("waiting.txt")).unwrap();
+        assert!(!waiting.contains("a-parcel"));
+        let done = fs::read_to_string(dir.path().join("done.txt")).unwrap();
+        assert!(done.contains("a-parcel"));
+        assert_eq!(state.view, View::Agenda);
+
+        assert!(state.select_todo_by_id("a-pay"));
+        state.handle_send_to(ViewMode::Someday);
+        let someday = fs::read_to_string(dir.path().join("someday.txt")).unwrap();
+        assert!(someday.contains("a-pay"));
+        assert_eq!(state.mode_of("a-pay"), ViewMode::Someday);
+    }
+
+    #[test]
+    fn test_set_view_mode_leaves_agenda() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = agenda_state(&dir);
+        state.set_view_mode(ViewMode::Todo);
+        assert_eq!(state.view, View::Board);
+        assert_eq!(state.project_names, ["fun", "home"]);
+    }
+
+    #[test]
+    fn test_done_archive_groups_by_completion_and_restores() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = agenda_state(&dir);
+        assert!(state.select_todo_by_id("a-parcel"));
+        let file = state.current_file();
+        state.handle_complete_todo(&file);
+        fs::write(
+            dir.path().join("done.txt"),
+            format!(
+                "x 2026-01-02 Old report +work id:d-old\nx Undated\n{}",
+                fs::read_to_string(dir.path().join("done.txt")).unwrap()
+            ),
+        )
+        .unwrap();
+
+        state.toggle_done();
+        assert_eq!(state.view, View::Done);
+        let today = chrono::Local::now().date_naive().to_string();
+        assert_eq!(
+            state.project_names,
+            [today.as_str(), "2026-01-02", "No date"]
+        );
+        // Browsing leaves the archive alone
+        let done = fs::read_to_string(dir.path().join("done.txt")).unwrap();
+        assert!(done.contains("x Undated\n"));
+
+        state.search = Some("parcel".to_string());
+        state.refresh_md_previews();
+        assert_eq!(state.project_names.len(), 3);
+        assert_eq!(state.grouped_todos.len(), 1);
+        state.search = None;
+        state.refresh_md_previews();
+
+        // Completed from Waiting, so it goes back there without its date
+        assert!(state.select_todo_by_id("a-parcel"));
+        state.handle_uncomplete();
+        let waiting = fs::read_to_string(dir.path().join("waiting.txt")).unwrap();
+        assert_eq!(waiting, "Parcel +home due:2000-01-02 id:a-parcel\n");
+        assert!(state.select_todo_by_id("d-old"));
+        state.handle_uncomplete();
+        let todo = fs::read_to_string(dir.path().join("todo.txt")).unwrap();
+        assert!(todo.ends_with("\nOld report +work id:d-old\n"), "{todo}");
+        assert_eq!(state.view, View::Done);
+        assert!(!state.select_todo_by_id("d-old"));
+
+        state.handle_undo();
+        let todo = fs::read_to_string(dir.path().join("todo.txt")).unwrap();
+        assert!(!todo.contains("d-old"));
+        assert!(state.select_todo_by_id("d-old"));
+    }
+
     #[test]
     fn test_handle_set_priority_sets_priority() {
         let temp_dir = std::env::temp_dir().join("torudo_test_set_priority_app");
@@ -2180,6 +3115,10 @@ mod tests {
         .unwrap();
         assert_eq!(count_items_in_file(file.to_str().unwrap()), 2);
 
+        let commented = temp_dir.join("commented.txt");
+        fs::write(&commented, "# Someday\nItem one id:1\n  # parked\n").unwrap();
+        assert_eq!(count_items_in_file(commented.to_str().unwrap()), 1);
+
         let empty_file = temp_dir.join("empty.txt");
         fs::write(&empty_file, "").unwrap();
         assert_eq!(count_items_in_file(empty_file.to_str().unwrap()), 0);
@@ -2556,4 +3495,398 @@ mod tests {
 
         assert_eq!(std::fs::read_to_string(&md).unwrap(), "body\n");
     }
+
+    #[test]
+    fn test_start_add_item_prefills_current_project() {
+        let mut state = create_test_state(create_test_todos());
+        state.current_column = state
+            .project_names
+            .iter()
+            .position(|p| p == "work")
+            .unwrap();
+        state.start_add_item(true);
+        let input = state.input.as_ref().expect("input should be open");
+        assert_eq!(input.kind, InputKind::Add);
+        assert_eq!(input.editor.text(), " +work");
+        assert_eq!(input.editor.cursor(), 0);
+    }
+
+    #[test]
+    fn test_start_add_item_blank_and_no_project_column() {
+        let mut state = create_test_state(create_test_todos());
+        state.start_add_item(false);
+        assert_eq!(state.input.as_ref().unwrap().editor.text(), "");
+
+        state.current_column = state
+            .project_names
+            .iter()
+            .position(|p| p == "No Project")
+            .unwrap();
+        state.start_add_item(true);
+        assert_eq!(state.input.as_ref().unwrap().editor.text(), "");
+    }
+
+    #[test]
+    fn test_cycle_grouping_regroups_and_persists_per_mode() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(
+            tmp.path().join("todo.txt"),
+            "(A) Call @phone +work owner:alice id:g1\nEmail +work id:g2\n",
+        )
+        .unwrap();
+        let mut state = make_state_with_todotxt_dir(tmp.path());
+        state.reload_todos(&state.active_file());
+        assert_eq!(state.project_names, ["work"]);
+        assert!(state.select_todo_by_id("g2"));
+
+        state.cycle_grouping();
+        assert_eq!(state.group_by(), GroupBy::Context);
+        assert_eq!(state.project_names, ["No Context", "phone"]);
+        assert_eq!(state.get_current_todo_id(), Some("g2"));
+        assert_eq!(state.status_message.as_deref(), Some("Group by: context"));
+        // Claude prompts still go to the item's project
+        assert_eq!(state.get_current_project_name(), Some("work".to_string()));
+
+        state.cycle_grouping();
+        state.cycle_grouping();
+        assert_eq!(state.group_by(), GroupBy::Tag("owner".to_string()));
+        assert_eq!(state.project_names, ["No owner", "alice"]);
+        state.current_column = 1;
+        state.start_add_item(true);
+        assert_eq!(state.input.as_ref().unwrap().editor.text(), " owner:alice");
+
+        // Other modes keep their own grouping, and the choice survives a restart
+        state.set_view_mode(ViewMode::Inbox);
+        assert_eq!(state.group_by(), GroupBy::Project);
+        let restarted = AppState::new(
+            vec![],
+            String::new(),
+            tmp.path().to_string_lossy().into_owned(),
+        );
+        assert_eq!(
+            restarted.groupings.get(&ViewMode::Todo),
+            Some(&GroupBy::Tag("owner".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_column_layout_keys_reorder_and_persist() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(
+            tmp.path().join("todo.txt"),
+            "A1 +alpha id:c1\nB1 +beta id:c2\nB2 +beta id:c3\nG1 +gamma id:c4\n",
+        )
+        .unwrap();
+        let mut state = make_state_with_todotxt_dir(tmp.path());
+        state.reload_todos(&state.active_file());
+        assert_eq!(state.project_names, ["alpha", "beta", "gamma"]);
+
+        assert!(state.select_todo_by_id("c4"));
+        state.move_current_column(true);
+        assert_eq!(state.project_names, ["alpha", "gamma", "beta"]);
+        assert_eq!(state.current_column, 1);
+        assert_eq!(state.get_current_todo_id(), Some("c4"));
+        assert_eq!(state.columns.sort, ColumnSort::Manual);
+
+        state.current_column = 2;
+        state.toggle_pin_current_column();
+        assert_eq!(state.project_names, ["beta", "alpha", "gamma"]);
+        assert_eq!(state.status_message.as_deref(), Some("Pinned beta"));
+
+        assert!(state.select_todo_by_id("c1"));
+        state.hide_current_column();
+        assert_eq!(state.project_names, ["beta", "gamma"]);
+
+        state.cycle_column_sort();
+        assert_eq!(state.columns.sort, ColumnSort::Name);
+
+        let restarted = AppState::new(
+            load_todos(&state.active_file()).unwrap(),
+            String::new(),
+            tmp.path().to_string_lossy().into_owned(),
+        );
+        assert_eq!(restarted.project_names, ["beta", "gamma"]);
+
+        state.unhide_all_columns();
+        assert_eq!(state.project_names, ["beta", "alpha", "gamma"]);
+    }
+
+    #[test]
+    fn test_submit_add_item_writes_active_file_and_selects_it() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_state_with_todotxt_dir(tmp.path());
+        state.view_mode = ViewMode::Inbox;
+        fs::write(tmp.path().join("inbox.txt"), "Existing +alpha id:e1\n").unwrap();
+        state.reload_todos(&state.active_file());
+
+        state.start_add_item(true);
+        for c in "New task".chars() {
+            state.input.as_mut().unwrap().editor.insert_char(c);
+        }
+        state.submit_input();
+
+        assert!(state.input.is_none());
+        let content = fs::read_to_string(tmp.path().join("inbox.txt")).unwrap();
+        let last = content.lines().last().unwrap();
+        assert!(last.contains("New task +alpha"), "got: {last}");
+        assert!(last.contains(" id:"), "id should be generated: {last}");
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        assert!(last.starts_with(&today), "creation date expected: {last}");
+        let selected = state.get_current_todo().unwrap();
+        assert_eq!(selected.description, "New task");
+        assert_eq!(selected.projects, vec!["alpha".to_string()]);
+        assert_eq!(state.status_message.as_deref(), Some("Added: New task"));
+    }
+
+    #[test]
+    fn test_submit_blank_input_writes_nothing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_state_with_todotxt_dir(tmp.path());
+        state.start_add_item(false);
+        state.input.as_mut().unwrap().editor.insert_str("   ");
+        state.submit_input();
+        assert!(state.input.is_none());
+        assert!(!tmp.path().join("todo.txt").exists());
+    }
+
+    fn make_state_with_file(dir: &std::path::Path, content: &str) -> AppState {
+        fs::write(dir.join("todo.txt"), content).unwrap();
+        let mut state = make_state_with_todotxt_dir(dir);
+        state.reload_todos(&state.active_file());
+        state
+    }
+
+    #[test]
+    fn test_start_edit_item_loads_raw_line() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_state_with_file(
+            tmp.path(),
+            "(B) 2024-01-10 Fix tpyo +work due:2024-02-01 id:e1\n",
+        );
+        state.start_edit_item();
+        let input = state.input.as_ref().expect("edit prompt should open");
+        assert_eq!(input.kind, InputKind::Edit("e1".to_string()));
+        assert_eq!(
+            input.editor.text(),
+            "(B) 2024-01-10 Fix tpyo +work due:2024-02-01 id:e1"
+        );
+    }
+
+    #[test]
+    fn test_submit_edit_rewrites_line_in_place() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_state_with_file(tmp.path(), "First +work id:e1\nSecond +work id:e2\n");
+        state.select_todo_by_id("e1");
+        state.start_edit_item();
+        let editor = &mut state.input.as_mut().unwrap().editor;
+        editor.move_home();
+        editor.insert_str("(A) ");
+        state.submit_input();
+
+        assert!(state.input.is_none());
+        let content = fs::read_to_string(tmp.path().join("todo.txt")).unwrap();
+        assert_eq!(content, "(A) First +work id:e1\nSecond +work id:e2\n");
+        assert_eq!(state.get_current_todo_id(), Some("e1"));
+    }
+
+    #[test]
+    fn test_submit_edit_without_id_keeps_prompt_open() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_state_with_file(tmp.path(), "First id:e1\n");
+        state.start_edit_item();
+        let editor = &mut state.input.as_mut().unwrap().editor;
+        for _ in 0.."id:e1".len() {
+            editor.backspace();
+        }
+        state.submit_input();
+
+        assert!(state.input.is_some(), "prompt stays open on rejection");
+        assert!(state.status_message.as_deref().unwrap().contains("id:e1"));
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("todo.txt")).unwrap(),
+            "First id:e1\n"
+        );
+    }
+
+    #[test]
+    fn test_undo_delete_restores_line_and_md_then_redo() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("todos")).unwrap();
+        let initial = "Task one +work id:u-a\nTask two +work id:u-b\n";
+        let mut state = make_state_with_file(tmp.path(), initial);
+        let md = tmp.path().join("todos/u-a.md");
+        fs::write(&md, "# detail\n").unwrap();
+        state.select_todo_by_id("u-a");
+
+        state.handle_delete_todo();
+        assert!(!md.exists());
+
+        state.handle_undo();
+        assert_eq!(state.status_message.as_deref(), Some("Undid: Delete"));
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("todo.txt")).unwrap(),
+            initial
+        );
+        assert_eq!(fs::read_to_string(&md).unwrap(), "# detail\n");
+        assert_eq!(state.todos.len(), 2);
+
+        state.handle_redo();
+        assert_eq!(state.status_message.as_deref(), Some("Redid: Delete"));
+        assert!(!md.exists());
+        assert_eq!(state.todos.len(), 1);
+    }
+
+    #[test]
+    fn test_undo_is_multi_level() {
+        let tmp = tempfile::tempdir().unwrap();
+        let initial = "Task one +work id:m-a\n";
+        let mut state = make_state_with_file(tmp.path(), initial);
+        state.select_todo_by_id("m-a");
+
+        state.handle_set_priority(Some('A'));
+        state.handle_set_priority(Some('B'));
+        state.handle_undo();
+        assert_eq!(state.get_current_todo().unwrap().priority, Some('A'));
+        state.handle_undo();
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("todo.txt")).unwrap(),
+            initial
+        );
+        state.handle_undo();
+        assert_eq!(state.status_message.as_deref(), Some("Nothing to undo"));
+    }
+
+    #[test]
+    fn test_undo_complete_restores_todo_and_done() {
+        let tmp = tempfile::tempdir().unwrap();
+        let initial = "Task one +work id:c-a\n";
+        let mut state = make_state_with_file(tmp.path(), initial);
+        state.select_todo_by_id("c-a");
+
+        state.handle_complete_todo(&state.active_file());
+        assert!(tmp.path().join("done.txt").exists());
+
+        state.handle_undo();
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("todo.txt")).unwrap(),
+            initial
+        );
+        assert!(!tmp.path().join("done.txt").exists());
+    }
+
+    #[test]
+    fn test_undo_recurring_complete_removes_copied_md() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("todos")).unwrap();
+        let initial = "Water plants rec:1w due:2026-10-10 id:r-a\n";
+        let mut state = make_state_with_file(tmp.path(), initial);
+        fs::write(tmp.path().join("todos/r-a.md"), "# Ferns first\n").unwrap();
+        state.select_todo_by_id("r-a");
+
+        state.handle_complete_todo(&state.active_file());
+        let next = state.todos[0].id.clone().unwrap();
+        let next_md = tmp.path().join(format!("todos/{next}.md"));
+        assert_eq!(fs::read_to_string(&next_md).unwrap(), "# Ferns first\n");
+
+        state.handle_undo();
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("todo.txt")).unwrap(),
+            initial
+        );
+        assert!(!next_md.exists(), "copied md left behind");
+        assert!(tmp.path().join("todos/r-a.md").exists());
+
+        state.handle_redo();
+        assert_eq!(fs::read_to_string(&next_md).unwrap(), "# Ferns first\n");
+    }
+
+    #[test]
+    fn test_apply_search_filters_cards_and_hides_empty_columns() {
+        let mut state = create_test_state(create_test_todos());
+        state.apply_search("@home");
+        assert_eq!(state.project_names, vec!["personal".to_string()]);
+        assert_eq!(state.get_current_todo_id(), Some("task-2"));
+
+        // Task 4 is tagged +work +urgent, so it shows up in both columns
+        state.apply_search("task +work");
+        assert_eq!(
+            state.project_names,
+            vec!["urgent".to_string(), "work".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_apply_search_keeps_selection_when_still_matching() {
+        let mut state = create_test_state(create_test_todos());
+        state.select_todo_by_id("task-2");
+        state.apply_search("task");
+        assert_eq!(state.get_current_todo_id(), Some("task-2"));
+    }
+
+    #[test]
+    fn test_clear_search_restores_columns_and_keeps_selection() {
+        let mut state = create_test_state(create_test_todos());
+        let all_columns = state.project_names.clone();
+        state.apply_search("@home");
+        state.clear_search();
+        assert!(state.search.is_none());
+        assert_eq!(state.project_names, all_columns);
+        assert_eq!(state.get_current_todo_id(), Some("task-2"));
+    }
+
+    #[test]
+    fn test_cycle_match_wraps_across_columns() {
+        let mut state = create_test_state(create_test_todos());
+        state.apply_search("task");
+        let cells = state.visible_cells();
+        assert!(cells.len() > 1);
+        let first = state.get_current_todo_id().map(str::to_string);
+
+        state.cycle_match(false);
+        assert_eq!(
+            (state.current_column, state.selected_in_column),
+            *cells.last().unwrap()
+        );
+        state.cycle_match(true);
+        assert_eq!(state.get_current_todo_id().map(str::to_string), first);
+    }
+
+    #[test]
+    fn test_cancel_search_input_clears_filter() {
+        let mut state = create_test_state(create_test_todos());
+        state.start_search();
+        state.input.as_mut().unwrap().editor.insert_str("@home");
+        state.input_changed();
+        assert_eq!(state.search.as_deref(), Some("@home"));
+        state.cancel_input();
+        assert!(state.search.is_none());
+        assert_eq!(state.get_current_todo_id(), Some("task-2"));
+    }
+
+    #[test]
+    fn test_apply_filter_query_hides_non_matching_columns() {
+        let mut state = create_test_state(create_test_todos());
+        state.apply_filter("pri:A-B -@home").unwrap();
+        assert_eq!(state.project_names, vec!["work".to_string()]);
+        assert_eq!(state.get_current_todo_id(), Some("task-1"));
+
+        state.clear_filter();
+        assert!(state.filter.is_none());
+        assert!(state.project_names.len() > 1);
+        assert_eq!(state.get_current_todo_id(), Some("task-1"));
+    }
+
+    #[test]
+    fn test_submit_invalid_filter_shows_error_and_keeps_prompt() {
+        let mut state = create_test_state(create_test_todos());
+        state.start_filter();
+        state.input.as_mut().unwrap().editor.insert_str("pri:Q-");
+        state.submit_input();
+        assert!(state.input.is_some());
+        assert!(state.filter.is_none());
+        assert_eq!(
+            state.status_message.as_deref(),
+            Some("Filter error: invalid priority 'Q-' (expected A-Z or a range like A-C)")
+        );
+    }
 }
@@ -0,0 +1,234 @@
+use crate::store;
+use crate::todo::Item;
+use crate::todo_file::TodoFile;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::io;
+use std::path::Path;
+
+/// Column for completed items without a completion date.
+pub const NO_DATE: &str = "No date";
+
+/// Group completed items into one column per completion date
+/// (`YYYY-MM-DD`). Inside a column the most recently archived item, the
+/// one furthest down `done.txt`, comes first.
+pub fn group_by_completion(todos: &[Item]) -> HashMap<String, Vec<Item>> {
+    let mut grouped: HashMap<String, Vec<Item>> = HashMap::new();
+    for todo in todos {
+        let label = todo
+            .completion_date
+            .map_or_else(|| NO_DATE.to_string(), |d| d.to_string());
+        grouped.entry(label).or_default().push(todo.clone());
+    }
+    for items in grouped.values_mut() {
+        items.sort_by_key(|t| std::cmp::Reverse(t.line_number));
+    }
+    grouped
+}
+
+/// Newest date first, undated items last.
+pub fn sort_columns(names: &mut [String]) {
+    names.sort_by(|a, b| (a == NO_DATE).cmp(&(b == NO_DATE)).then_with(|| b.cmp(a)));
+}
+
+/// How `done.txt` is split into per-period archive files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rotation {
+    /// Keep every completed item in done.txt
+    #[default]
+    Off,
+    /// One `done-YYYY.txt` per completion year
+    Year,
+    /// One `done-YYYY-MM.txt` per completion month
+    Month,
+}
+
+/// Archive file for items completed on `date`, named after the done file:
+/// `done.txt` gives `done-2026.txt` or `done-2026-10.txt`.
+pub fn rotated_name(done: &str, rotation: Rotation, date: NaiveDate) -> Option<String> {
+    let period = match rotation {
+        Rotation::Off => return None,
+        Rotation::Year => date.format("%Y"),
+        Rotation::Month => date.format("%Y-%m"),
+    };
+    Some(match done.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{stem}-{period}.{ext}"),
+        _ => format!("{done}-{period}"),
+    })
+}
+
+/// Move every item completed before the current year or month out of
+/// `done.txt` into its period's archive file. Undated items, blank lines
+/// and comments stay. A line already in its archive is not added again, so
+/// an interrupted rotation can simply be run again. Returns each archive
+/// written with the number of items moved into it.
+pub fn rotate(
+    todotxt_dir: &str,
+    rotation: Rotation,
+    today: NaiveDate,
+) -> Result<Vec<(String, usize)>, Box<dyn Error>> {
+    let done_name = &crate::config::get().files.done;
+    let Some(current) = rotated_name(done_name, rotation, today) else {
+        return Ok(Vec::new());
+    };
+    let _lock = store::lock(Path::new(todotxt_dir))?;
+    let done_file = format!("{todotxt_dir}/{done_name}");
+    let mut done = match TodoFile::read(&done_file) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        result => result?,
+    };
+
+    // Same stem and zero-padded dates, so names sort chronologically
+    let mut targets: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (index, line) in done.items() {
+        let Some(date) = Item::parse(line, index + 1).completion_date else {
+            continue;
+        };
+        if let Some(name) = rotated_name(done_name, rotation, date)
+            && name < current
+        {
+            targets.entry(name).or_default().push(index);
+        }
+    }
+
+    let mut moved = Vec::with_capacity(targets.len());
+    for (name, indices) in &targets {
+        let path = format!("{todotxt_dir}/{name}");
+        let mut archive = TodoFile::read_or_empty(&path)?;
+        let existing: HashSet<String> = archive.items().map(|(_, l)| l.to_string()).collect();
+        for &index in indices {
+            let line = done.line(index);
+            if !existing.contains(line) {
+                archive.push_line(line.to_string());
+            }
+        }
+        store::write_atomic(&path, &archive.to_string())?;
+        moved.push((name.clone(), indices.len()));
+    }
+    if !moved.is_empty() {
+        let mut indices: Vec<usize> = targets.into_values().flatten().collect();
+        indices.sort_unstable();
+        for index in indices.into_iter().rev() {
+            done.remove_line(index);
+        }
+        store::write_atomic(&done_file, &done.to_string())?;
+    }
+    Ok(moved)
+}
+
+/// `torudo archive rotate`: rotate by `by`, or by `[archive] rotate`.
+pub fn run_rotate(todotxt_dir: &str, by: Option<Rotation>) -> Result<(), Box<dyn Error>> {
+    let rotation = by.unwrap_or_else(|| crate::config::get().archive.rotate);
+    if rotation == Rotation::Off {
+        return Err("archive rotation is off: pass --by year|month or set [archive] rotate".into());
+    }
+    let moved = rotate(todotxt_dir, rotation, chrono::Local::now().date_naive())?;
+    if moved.is_empty() {
+        println!("Nothing to rotate");
+    }
+    for (name, count) in moved {
+        println!("Moved {count} item(s) to {name}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_by_completion_date_newest_entry_first() {
+        let todos = vec![
+            Item::parse("x 2026-10-01 2026-09-20 Old id:a", 1),
+            Item::parse("x 2026-10-02 Newer id:b", 2),
+            Item::parse("x 2026-10-01 Later same day id:c", 3),
+            Item::parse("x Undated id:d", 4),
+        ];
+        let grouped = group_by_completion(&todos);
+        let first: Vec<&str> = grouped["2026-10-01"]
+            .iter()
+            .map(|t| t.description.as_str())
+            .collect();
+        assert_eq!(first, ["Later same day", "Old"]);
+        assert_eq!(grouped["2026-10-02"].len(), 1);
+        assert_eq!(grouped[NO_DATE].len(), 1);
+    }
+
+    #[test]
+    fn test_sort_columns_newest_first() {
+        let mut names = vec![
+            NO_DATE.to_string(),
+            "2026-09-30".to_string(),
+            "2026-10-02".to_string(),
+            "2026-10-01".to_string(),
+        ];
+        sort_columns(&mut names);
+        assert_eq!(names, ["2026-10-02", "2026-10-01", "2026-09-30", NO_DATE]);
+    }
+
+    #[test]
+    fn test_rotated_name_follows_done_file_name() {
+        let date = NaiveDate::from_ymd_opt(2025, 3, 9).unwrap();
+        assert_eq!(
+            rotated_name("done.txt", Rotation::Year, date).as_deref(),
+            Some("done-2025.txt")
+        );
+        assert_eq!(
+            rotated_name("archive.todo.txt", Rotation::Month, date).as_deref(),
+            Some("archive.todo-2025-03.txt")
+        );
+        assert_eq!(
+            rotated_name("done", Rotation::Month, date).as_deref(),
+            Some("done-2025-03")
+        );
+        assert_eq!(rotated_name("done.txt", Rotation::Off, date), None);
+    }
+
+    #[test]
+    fn test_rotate_moves_past_periods_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir_str = dir.path().to_str().unwrap();
+        let done = dir.path().join("done.txt");
+        std::fs::write(
+            &done,
+            "# archive\r\nx 2025-12-31 Old year id:a\r\nx Undated id:b\r\n\r\n\
+             x 2026-09-30 Last month id:c\r\nx 2026-10-01 This month id:d\r\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("done-2026-09.txt"),
+            "x 2026-09-30 Last month id:c\n",
+        )
+        .unwrap();
+        let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+
+        let moved = rotate(dir_str, Rotation::Month, today).unwrap();
+        assert_eq!(
+            moved,
+            [
+                ("done-2025-12.txt".to_string(), 1),
+                ("done-2026-09.txt".to_string(), 1)
+            ]
+        );
+        assert_eq!(
+            std::fs::read_to_string(&done).unwrap(),
+            "# archive\r\nx Undated id:b\r\n\r\nx 2026-10-01 This month id:d\r\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("done-2025-12.txt")).unwrap(),
+            "x 2025-12-31 Old year id:a\n"
+        );
+        // Already archived by an interrupted run: not duplicated
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("done-2026-09.txt")).unwrap(),
+            "x 2026-09-30 Last month id:c\n"
+        );
+
+        assert!(rotate(dir_str, Rotation::Month, today).unwrap().is_empty());
+        let moved = rotate(dir_str, Rotation::Year, today).unwrap();
+        assert!(moved.is_empty(), "{moved:?}");
+    }
+}
@@ -0,0 +1,365 @@
+use crate::md_preview::md_path;
+use crate::store;
+use crate::todo::Item;
+use chrono::{Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How columns that are neither pinned nor manually placed are ordered.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnSort {
+    /// Alphabetically by column name
+    #[default]
+    Name,
+    /// Most items first
+    Count,
+    /// Most recently created or detail-edited item first
+    Recent,
+    /// The saved `order`, as arranged with the move keys
+    Manual,
+}
+
+impl ColumnSort {
+    const CYCLE: &[Self] = &[Self::Name, Self::Count, Self::Recent, Self::Manual];
+
+    pub fn next(self) -> Self {
+        let i = Self::CYCLE.iter().position(|s| *s == self).unwrap_or(0);
+        Self::CYCLE[(i + 1) % Self::CYCLE.len()]
+    }
+}
+
+impl fmt::Display for ColumnSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Name => "name",
+            Self::Count => "count",
+            Self::Recent => "recent",
+            Self::Manual => "manual",
+        })
+    }
+}
+
+/// Board column arrangement, stored in `{todotxt_dir}/.torudo/columns.json`
+/// and editable by hand. Columns are matched by name, so the same settings
+/// apply to a project whichever mode or grouping shows it.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ColumnLayout {
+    pub sort: ColumnSort,
+    /// Always shown first, in this order
+    pub pinned: Vec<String>,
+    /// Never shown
+    pub hidden: Vec<String>,
+    /// Order used by `ColumnSort::Manual`; unlisted columns follow by name
+    pub order: Vec<String>,
+}
+
+fn layout_path(todotxt_dir: &str) -> PathBuf {
+    Path::new(todotxt_dir).join(".torudo").join("columns.json")
+}
+
+/// Latest moment an item was touched: its creation date or the last edit
+/// of its detail md, whichever is newer.
+fn touched_at(item: &Item, todotxt_dir: &str) -> Option<NaiveDateTime> {
+    let created = item.creation_date.and_then(|d| d.and_hms_opt(0, 0, 0));
+    let edited = item
+        .id
+        .as_deref()
+        .and_then(|id| fs::metadata(md_path(todotxt_dir, id)).ok())
+        .and_then(|meta| meta.modified().ok())
+        .map(|mtime| chrono::DateTime::<Local>::from(mtime).naive_local());
+    created.max(edited)
+}
+
+impl ColumnLayout {
+    /// Settings from disk; defaults when the file is missing or unreadable.
+    pub fn load(todotxt_dir: &str) -> Self {
+        fs::read_to_string(layout_path(todotxt_dir))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, todotxt_dir: &str) -> Result<(), Box<dyn Error>> {
+        let path = layout_path(todotxt_dir);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        store::write_atomic(&path, &serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn is_hidden(&self, name: &str) -> bool {
+        self.hidden.iter().any(|h| h == name)
+    }
+
+    pub fn is_pinned(&self, name: &str) -> bool {
+        self.pinned.iter().any(|p| p == name)
+    }
+
+    /// Column names of `grouped` in display order: pinned columns first,
+    /// then the rest by `sort`. Hidden columns are left out.
+    pub fn arrange(&self, grouped: &HashMap<String, Vec<Item>>, todotxt_dir: &str) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .pinned
+            .iter()
+            .filter(|p| grouped.contains_key(*p) && !self.is_hidden(p))
+            .cloned()
+            .collect();
+        let mut rest: Vec<String> = grouped
+            .keys()
+            .filter(|name| !self.is_hidden(name) && !self.is_pinned(name))
+            .cloned()
+            .collect();
+        rest.sort();
+        match self.sort {
+            ColumnSort::Name => {}
+            ColumnSort::Count => rest.sort_by_key(|name| std::cmp::Reverse(grouped[name].len())),
+            ColumnSort::Recent => rest.sort_by_cached_key(|name| {
+                std::cmp::Reverse(
+                    grouped[name]
+                        .iter()
+                        .filter_map(|t| touched_at(t, todotxt_dir))
+                        .max(),
+                )
+            }),
+            ColumnSort::Manual => rest.sort_by_key(|name| {
+                self.order
+                    .iter()
+                    .position(|o| o == name)
+                    .unwrap_or(usize::MAX)
+            }),
+        }
+        names.extend(rest);
+        names
+    }
+
+    /// Swap column `index` of the displayed `names` with its left or right
+    /// neighbour. Moving unpinned columns switches to manual sorting so the
+    /// new order sticks. Returns the column's new index.
+    pub fn move_column(
+        &mut self,
+        names: &[String],
+        index: usize,
+        left: bool,
+    ) -> Result<usize, &'static str> {
+        let target = if left {
+            index.checked_sub(1)
+        } else {
+            Some(index + 1).filter(|&i| i < names.len())
+        }
+        .ok_or("Already at the edge")?;
+        if self.is_pinned(&names[index]) != self.is_pinned(&names[target]) {
+            return Err("Pinned columns stay in front");
+        }
+        let mut moved = names.to_vec();
+        moved.swap(index, target);
+        if self.is_pinned(&moved[target]) {
+            let displayed: Vec<String> = moved
+                .iter()
+                .filter(|n| self.is_pinned(n))
+                .cloned()
+                .collect();
+            // Pinned columns not on this board keep their relative place
+            let others = self
+                .pinned
+                .iter()
+                .filter(|p| !displayed.contains(p))
+                .cloned();
+            self.pinned = displayed.iter().cloned().chain(others).collect();
+        } else {
+            let displayed: Vec<String> = moved
+                .iter()
+                .filter(|n| !self.is_pinned(n))
+                .cloned()
+                .collect();
+            let others = self
+                .order
+                .iter()
+                .filter(|o| !displayed.contains(o))
+                .cloned();
+            self.order = displayed.iter().cloned().chain(others).collect();
+            self.sort = ColumnSort::Manual;
+        }
+        Ok(target)
+    }
+
+    /// Pin or unpin `name`. Returns whether it is pinned now.
+    pub fn toggle_pin(&mut self, name: &str) -> bool {
+        if self.is_pinned(name) {
+            self.pinned.retain(|p| p != name);
+            false
+        } else {
+            self.pinned.push(name.to_string());
+            true
+        }
+    }
+
+    pub fn hide(&mut self, name: &str) {
+        if !self.is_hidden(name) {
+            self.hidden.push(name.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grouped(columns: &[(&str, usize)]) -> HashMap<String, Vec<Item>> {
+        columns
+            .iter()
+            .map(|(name, count)| {
+                let items = (0..*count)
+                    .map(|i| Item::parse(&format!("Task {i} +{name}"), i + 1))
+                    .collect();
+                ((*name).to_string(), items)
+            })
+            .collect()
+    }
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| (*s).to_string()).collect()
+    }
+
+    #[test]
+    fn test_arrange_defaults_to_name_order() {
+        let layout = ColumnLayout::default();
+        let grouped = grouped(&[("work", 1), ("No Project", 1), ("home", 1)]);
+        assert_eq!(
+            layout.arrange(&grouped, "/nonexistent"),
+            ["No Project", "home", "work"]
+        );
+    }
+
+    #[test]
+    fn test_arrange_pinned_hidden_and_count() {
+        let layout = ColumnLayout {
+            sort: ColumnSort::Count,
+            pinned: names(&["work", "gone"]),
+            hidden: names(&["No Project"]),
+            order: Vec::new(),
+        };
+        let grouped = grouped(&[
+            ("work", 1),
+            ("No Project", 5),
+            ("home", 2),
+            ("blog", 3),
+            ("art", 2),
+        ]);
+        assert_eq!(
+            layout.arrange(&grouped, "/nonexistent"),
+            ["work", "blog", "art", "home"]
+        );
+    }
+
+    #[test]
+    fn test_arrange_recent_uses_creation_date_and_md_mtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir_path = dir.path().to_str().unwrap();
+        let mut grouped = HashMap::new();
+        grouped.insert(
+            "old".to_string(),
+            vec![Item::parse("2020-01-01 Old +old id:o1", 1)],
+        );
+        grouped.insert(
+            "new".to_string(),
+            vec![Item::parse("2024-01-01 New +new id:n1", 2)],
+        );
+        grouped.insert("undated".to_string(), vec![Item::parse("Undated", 3)]);
+        let layout = ColumnLayout {
+            sort: ColumnSort::Recent,
+            ..ColumnLayout::default()
+        };
+        assert_eq!(
+            layout.arrange(&grouped, dir_path),
+            ["new", "old", "undated"]
+        );
+
+        // Editing the detail md counts as touching the item
+        fs::create_dir_all(dir.path().join("todos")).unwrap();
+        fs::write(md_path(dir_path, "o1"), "notes").unwrap();
+        assert_eq!(
+            layout.arrange(&grouped, dir_path),
+            ["old", "new", "undated"]
+        );
+    }
+
+    #[test]
+    fn test_move_column_switches_to_manual_order() {
+        let mut layout = ColumnLayout {
+            order: names(&["elsewhere"]),
+            ..ColumnLayout::default()
+        };
+        let grouped = grouped(&[("a", 1), ("b", 1), ("c", 1)]);
+        let shown = layout.arrange(&grouped, "/nonexistent");
+        assert_eq!(layout.move_column(&shown, 2, true), Ok(1));
+        assert_eq!(layout.sort, ColumnSort::Manual);
+        assert_eq!(layout.order, ["a", "c", "b", "elsewhere"]);
+        assert_eq!(layout.arrange(&grouped, "/nonexistent"), ["a", "c", "b"]);
+
+        assert_eq!(
+            layout.move_column(&shown, 0, true),
+            Err("Already at the edge")
+        );
+        assert_eq!(
+            layout.move_column(&shown, 2, false),
+            Err("Already at the edge")
+        );
+    }
+
+    #[test]
+    fn test_move_column_keeps_pinned_in_front() {
+        let mut layout = ColumnLayout {
+            pinned: names(&["x", "elsewhere", "y"]),
+            ..ColumnLayout::default()
+        };
+        let grouped = grouped(&[("x", 1), ("y", 1), ("a", 1)]);
+        let shown = layout.arrange(&grouped, "/nonexistent");
+        assert_eq!(shown, ["x", "y", "a"]);
+        assert_eq!(
+            layout.move_column(&shown, 2, true),
+            Err("Pinned columns stay in front")
+        );
+        assert_eq!(layout.move_column(&shown, 1, true), Ok(0));
+        assert_eq!(layout.pinned, ["y", "x", "elsewhere"]);
+        assert_eq!(layout.sort, ColumnSort::Name);
+    }
+
+    #[test]
+    fn test_toggle_pin_hide_and_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir_path = dir.path().to_str().unwrap();
+        assert_eq!(ColumnLayout::load(dir_path), ColumnLayout::default());
+
+        let mut layout = ColumnLayout::default();
+        assert!(layout.toggle_pin("work"));
+        assert!(!layout.toggle_pin("work"));
+        assert!(layout.toggle_pin("home"));
+        layout.hide("misc");
+        layout.hide("misc");
+        layout.sort = ColumnSort::Count;
+        layout.save(dir_path).unwrap();
+
+        let loaded = ColumnLayout::load(dir_path);
+        assert_eq!(loaded, layout);
+        assert_eq!(loaded.hidden, ["misc"]);
+
+        // Hand-written files may leave fields out
+        fs::write(layout_path(dir_path), r#"{"hidden": ["x"]}"#).unwrap();
+        let loaded = ColumnLayout::load(dir_path);
+        assert_eq!(loaded.sort, ColumnSort::Name);
+        assert_eq!(loaded.hidden, ["x"]);
+    }
+
+    #[test]
+    fn test_sort_cycles() {
+        assert_eq!(ColumnSort::Name.next(), ColumnSort::Count);
+        assert_eq!(ColumnSort::Manual.next(), ColumnSort::Name);
+    }
+}
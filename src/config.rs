@@ -0,0 +1,562 @@
+use crate::app_state::{View, ViewMode};
+use crate::archive::Rotation;
+use crate::keymap::Keymap;
+use crate::theme::{self, Theme, ThemeSpec};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct NvimConfig {
+    /// Socket of the Neovim instance that shows detail files
+    pub listen: String,
+}
+
+impl Default for NvimConfig {
+    fn default() -> Self {
+        Self {
+            listen: "/tmp/nvim.sock".to_string(),
+        }
+    }
+}
+
+/// File names inside the todotxt directory.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilesConfig {
+    pub inbox: String,
+    pub todo: String,
+    pub waiting: String,
+    #[serde(rename = "ref")]
+    pub reference: String,
+    pub someday: String,
+    pub done: String,
+}
+
+impl Default for FilesConfig {
+    fn default() -> Self {
+        Self {
+            inbox: "inbox.txt".to_string(),
+            todo: "todo.txt".to_string(),
+            waiting: "waiting.txt".to_string(),
+            reference: "ref.txt".to_string(),
+            someday: "someday.txt".to_string(),
+            done: "done.txt".to_string(),
+        }
+    }
+}
+
+impl FilesConfig {
+    pub fn for_mode(&self, mode: ViewMode) -> &str {
+        match mode {
+            ViewMode::Inbox => &self.inbox,
+            ViewMode::Todo => &self.todo,
+            ViewMode::Waiting => &self.waiting,
+            ViewMode::Ref => &self.reference,
+            ViewMode::Someday => &self.someday,
+        }
+    }
+
+    fn all(&self) -> [(&'static str, &str); 6] {
+        [
+            ("inbox", &self.inbox),
+            ("todo", &self.todo),
+            ("waiting", &self.waiting),
+            ("ref", &self.reference),
+            ("someday", &self.someday),
+            ("done", &self.done),
+        ]
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct PreviewConfig {
+    /// Unchecked `- [ ]` lines of the detail md shown on a card
+    pub max_items: usize,
+}
+
+impl Default for PreviewConfig {
+    fn default() -> Self {
+        Self { max_items: 3 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchConfig {
+    /// Minimum time between reloads triggered by file changes
+    pub debounce_ms: u64,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self { debounce_ms: 200 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct TemplatesConfig {
+    /// Template directory, relative to the todotxt directory unless absolute
+    pub dir: String,
+}
+
+impl Default for TemplatesConfig {
+    fn default() -> Self {
+        Self {
+            dir: "templates".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArchiveConfig {
+    /// Split done.txt by completion year or month when the TUI starts
+    pub rotate: Rotation,
+}
+
+/// Settings from `$XDG_CONFIG_HOME/torudo/config.toml`, overridden by
+/// `{todotxt_dir}/.torudo/config.toml` and then by command-line flags.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub todotxt_dir: Option<String>,
+    pub view: View,
+    /// Built-in theme (`dark`, `light`, `high-contrast`) or one of `themes`
+    pub theme: Option<String>,
+    pub nvim: NvimConfig,
+    pub files: FilesConfig,
+    pub preview: PreviewConfig,
+    pub watch: WatchConfig,
+    pub templates: TemplatesConfig,
+    pub archive: ArchiveConfig,
+    /// Custom themes by name
+    pub themes: BTreeMap<String, ThemeSpec>,
+    /// Action name to keys, for the actions in `keymap::ACTIONS`. Dotted
+    /// names may be written as nested tables (`[keys.send_to]`).
+    pub keys: toml::Table,
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// The configuration in effect. Defaults until `init` is called, which is
+/// what unit tests run with.
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+pub fn init(config: Config) {
+    if CONFIG.set(config).is_err() {
+        log::debug!("Configuration was already initialized");
+    }
+}
+
+/// `$XDG_CONFIG_HOME/torudo/config.toml`, or `~/.config/torudo/config.toml`.
+pub fn global_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(base.join("torudo").join("config.toml"))
+}
+
+pub fn dir_path(todotxt_dir: &str) -> PathBuf {
+    Path::new(todotxt_dir).join(".torudo").join("config.toml")
+}
+
+/// Read and validate one config file. A missing file is `None`; one that
+/// cannot be read (permissions, not UTF-8), syntax errors, and unknown or
+/// mistyped settings are reported with the file's path.
+fn read_table(path: &Path) -> Result<Option<toml::Table>, String> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("{}: {e}", path.display())),
+    };
+    let table: toml::Table =
+        toml::from_str(&content).map_err(|e| format!("{}: {e}", path.display()))?;
+    Config::deserialize(toml::Value::Table(table.clone()))
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(Some(table))
+}
+
+/// Merge `overlay` into `base`, recursing into tables so an override file
+/// only needs the settings it changes.
+fn merge(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(overlay_table)) => {
+                merge(base_table, overlay_table);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// The merged configuration and the files it was read from.
+pub struct Loaded {
+    pub config: Config,
+    pub sources: Vec<PathBuf>,
+}
+
+/// Load the global config and the override in the todotxt directory.
+/// `cli_dir` (from `--todotxt-dir` or `TODOTXT_DIR`) wins over the global
+/// file's `todotxt_dir`, which wins over `~/todotxt`.
+pub fn load(cli_dir: Option<String>) -> Result<Loaded, String> {
+    load_from(global_path(), cli_dir)
+}
+
+fn load_from(global: Option<PathBuf>, cli_dir: Option<String>) -> Result<Loaded, String> {
+    let mut merged = toml::Table::new();
+    let mut sources = Vec::new();
+    if let Some(path) = global
+        && let Some(table) = read_table(&path)?
+    {
+        merge(&mut merged, table);
+        sources.push(path);
+    }
+    let todotxt_dir = cli_dir
+        .or_else(|| {
+            merged
+                .get("todotxt_dir")
+                .and_then(toml::Value::as_str)
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| {
+            let home_dir = env::var("HOME").unwrap();
+            format!("{home_dir}/todotxt")
+        });
+    let path = dir_path(&todotxt_dir);
+    if let Some(table) = read_table(&path)? {
+        if table.contains_key("todotxt_dir") {
+            return Err(format!(
+                "{}: todotxt_dir cannot be set in a per-directory config",
+                path.display()
+            ));
+        }
+        merge(&mut merged, table);
+        sources.push(path);
+    }
+    let mut config = Config::deserialize(toml::Value::Table(merged)).map_err(|e| e.to_string())?;
+    config.todotxt_dir = Some(todotxt_dir);
+    config.validate().map_err(|e| {
+        let files: Vec<String> = sources.iter().map(|p| p.display().to_string()).collect();
+        format!("invalid configuration ({}): {e}", files.join(", "))
+    })?;
+    Ok(Loaded { config, sources })
+}
+
+impl Config {
+    pub fn todotxt_dir(&self) -> &str {
+        self.todotxt_dir.as_deref().unwrap_or_default()
+    }
+
+    /// Overlay command-line flags, which take precedence over every file.
+    pub fn apply_flags(&mut self, nvim_listen: Option<String>, view: Option<View>) {
+        if let Some(listen) = nvim_listen {
+            self.nvim.listen = listen;
+        }
+        if let Some(view) = view {
+            self.view = view;
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let files = self.files.all();
+        for (i, (name, file)) in files.iter().enumerate() {
+            if file.is_empty() || file.contains('/') {
+                return Err(format!(
+                    "files.{name} must be a plain file name, got {file:?}"
+                ));
+            }
+            if let Some((other, _)) = files[..i].iter().find(|(_, f)| f == file) {
+                return Err(format!("files.{other} and files.{name} are both {file:?}"));
+            }
+        }
+        if self.templates.dir.is_empty() {
+            return Err("templates.dir must not be empty".to_string());
+        }
+        if self.nvim.listen.is_empty() {
+            return Err("nvim.listen must not be empty".to_string());
+        }
+        self.theme()?;
+        self.keymap().map(|_| ())
+    }
+
+    pub fn theme_name(&self) -> &str {
+        self.theme.as_deref().unwrap_or("dark")
+    }
+
+    /// The selected theme with custom styles applied.
+    pub fn theme(&self) -> Result<Theme, String> {
+        theme::resolve(self.theme_name(), &self.themes)
+    }
+
+    /// The key bindings with `[keys]` applied.
+    pub fn keymap(&self) -> Result<Keymap, String> {
+        let mut overrides = BTreeMap::new();
+        flatten_keys(&self.keys, "", &mut overrides)?;
+        Keymap::new(&overrides)
+    }
+
+    /// Everything `torudo config show` prints: the settings in effect with
+    /// the full key map spelled out.
+    pub fn to_toml(&self) -> Result<String, String> {
+        let mut shown = self.clone();
+        shown.theme = Some(self.theme_name().to_string());
+        shown.keys = toml::Table::new();
+        for (name, keys) in self.keymap()?.to_names() {
+            let mut parts: Vec<&str> = name.split('.').collect();
+            let last = parts.pop().unwrap_or_default();
+            let mut table = &mut shown.keys;
+            for part in parts {
+                table = table
+                    .entry(part)
+                    .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                    .as_table_mut()
+                    .expect("action groups are tables");
+            }
+            table.insert(last.to_string(), toml::Value::String(keys));
+        }
+        toml::to_string_pretty(&shown).map_err(|e| e.to_string())
+    }
+}
+
+/// Collect `[keys]` into dotted action names.
+fn flatten_keys(
+    table: &toml::Table,
+    prefix: &str,
+    out: &mut BTreeMap<String, String>,
+) -> Result<(), String> {
+    for (key, value) in table {
+        let name = format!("{prefix}{key}");
+        match value {
+            toml::Value::String(keys) => {
+                out.insert(name, keys.clone());
+            }
+            toml::Value::Table(inner) => flatten_keys(inner, &format!("{name}."), out)?,
+            _ => {
+                return Err(format!(
+                    "keys.{name} must be a string such as \"x\" or \"dd\""
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `torudo config show`: the effective configuration, preceded by the files
+/// that were considered.
+pub fn run_show(loaded: &Loaded, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let candidates = global_path()
+        .into_iter()
+        .chain(std::iter::once(dir_path(config.todotxt_dir())));
+    for path in candidates {
+        let status = if loaded.sources.contains(&path) {
+            "loaded"
+        } else {
+            "not found"
+        };
+        println!("# {} ({status})", path.display());
+    }
+    print!("{}", config.to_toml()?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Config {
+        toml::from_str(content).unwrap()
+    }
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_defaults_match_built_in_behaviour() {
+        let config = Config::default();
+        assert_eq!(config.files.for_mode(ViewMode::Ref), "ref.txt");
+        assert_eq!(config.files.done, "done.txt");
+        assert_eq!(config.preview.max_items, 3);
+        assert_eq!(config.watch.debounce_ms, 200);
+        assert_eq!(config.nvim.listen, "/tmp/nvim.sock");
+        assert_eq!(config.view, View::Board);
+        assert!(config.validate().is_ok());
+        assert_eq!(config.keymap(), Ok(Keymap::default()));
+    }
+
+    #[test]
+    fn test_merge_keeps_unchanged_settings() {
+        let mut base: toml::Table =
+            toml::from_str("[files]\ntodo = \"next.txt\"\ninbox = \"in.txt\"\n").unwrap();
+        let overlay: toml::Table = toml::from_str("[files]\ninbox = \"box.txt\"\n").unwrap();
+        merge(&mut base, overlay);
+        let config = Config::deserialize(toml::Value::Table(base)).unwrap();
+        assert_eq!(config.files.todo, "next.txt");
+        assert_eq!(config.files.inbox, "box.txt");
+        assert_eq!(config.files.waiting, "waiting.txt");
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_files_and_keys() {
+        let err = parse("[files]\ntodo = \"a/b.txt\"").validate().unwrap_err();
+        assert!(
+            err.contains("files.todo must be a plain file name"),
+            "{err}"
+        );
+        let err = parse("[files]\ndone = \"todo.txt\"")
+            .validate()
+            .unwrap_err();
+        assert_eq!(err, r#"files.todo and files.done are both "todo.txt""#);
+        let err = parse("[keys]\nfly = \"y\"").validate().unwrap_err();
+        assert!(err.starts_with("keys.fly is not an action"), "{err}");
+        let err = parse("[keys]\nquit = 1").validate().unwrap_err();
+        assert_eq!(err, r#"keys.quit must be a string such as "x" or "dd""#);
+        let err = parse("[keys]\nquit = \"x\"").validate().unwrap_err();
+        assert_eq!(err, "keys.complete and keys.quit are both x");
+    }
+
+    #[test]
+    fn test_theme_selection_and_custom_themes() {
+        assert_eq!(Config::default().theme(), Ok(Theme::dark()));
+        assert_eq!(parse("theme = \"light\"").theme(), Ok(Theme::light()));
+
+        let config = parse(
+            "theme = \"solar\"\n[themes.solar]\nbase = \"light\"\noverdue = { fg = \"#dc322f\", bold = true }\n",
+        );
+        let theme = config.theme().unwrap();
+        assert_eq!(theme.selection, Theme::light().selection);
+        assert_ne!(theme.overdue, Theme::light().overdue);
+        assert!(config.validate().is_ok());
+
+        let err = parse("theme = \"solar\"").validate().unwrap_err();
+        assert!(err.starts_with("theme \"solar\" is not defined"), "{err}");
+        let err = parse("theme = \"x\"\n[themes.x]\nborder = { fg = \"nope\" }\n")
+            .validate()
+            .unwrap_err();
+        assert!(err.starts_with("themes.x.border:"), "{err}");
+    }
+
+    #[test]
+    fn test_keys_accept_dotted_and_nested_names() {
+        use crate::keymap::{Action, Lookup, parse_keys};
+
+        let dotted = parse("[keys]\n\"send_to.waiting\" = \"W\"\n")
+            .keymap()
+            .unwrap();
+        let nested = parse("[keys.send_to]\nwaiting = \"W\"\n").keymap().unwrap();
+        assert_eq!(dotted, nested);
+        assert_eq!(
+            nested.lookup(&parse_keys("W").unwrap()),
+            Lookup::Action(Action::SendTo(ViewMode::Waiting))
+        );
+    }
+
+    #[test]
+    fn test_load_layers_global_dir_and_cli() {
+        let tmp = tempfile::tempdir().unwrap();
+        let global = tmp.path().join("config").join("torudo").join("config.toml");
+        let dir = tmp.path().join("todo");
+        let dir_str = dir.to_str().unwrap().to_string();
+        write(
+            &global,
+            &format!(
+                "todotxt_dir = {dir_str:?}\n[preview]\nmax_items = 5\n[watch]\ndebounce_ms = 50\n"
+            ),
+        );
+        write(&dir_path(&dir_str), "[preview]\nmax_items = 1\n");
+
+        let loaded = load_from(Some(global.clone()), None).unwrap();
+        assert_eq!(loaded.config.todotxt_dir(), dir_str);
+        assert_eq!(loaded.config.preview.max_items, 1);
+        assert_eq!(loaded.config.watch.debounce_ms, 50);
+        assert_eq!(loaded.sources, [global.clone(), dir_path(&dir_str)]);
+
+        // The command line picks another directory, without its override
+        let other = tmp.path().join("other").to_str().unwrap().to_string();
+        let loaded = load_from(Some(global), Some(other.clone())).unwrap();
+        assert_eq!(loaded.config.todotxt_dir(), other);
+        assert_eq!(loaded.config.preview.max_items, 5);
+
+        let mut config = loaded.config;
+        config.apply_flags(Some("/tmp/other.sock".to_string()), Some(View::Agenda));
+        assert_eq!(config.nvim.listen, "/tmp/other.sock");
+        assert_eq!(config.view, View::Agenda);
+    }
+
+    #[test]
+    fn test_load_reports_errors_with_path() {
+        let tmp = tempfile::tempdir().unwrap();
+        let global = tmp.path().join("config.toml");
+        let dir = tmp.path().to_str().unwrap().to_string();
+
+        write(&global, "[preview]\nmax_itmes = 5\n");
+        let err = load_from(Some(global.clone()), Some(dir.clone()))
+            .err()
+            .unwrap();
+        assert!(err.starts_with(&global.display().to_string()), "{err}");
+        assert!(err.contains("max_itmes"), "{err}");
+
+        write(&global, "view = \"calendar\"\n");
+        assert!(load_from(Some(global.clone()), Some(dir.clone())).is_err());
+
+        write(&global, "[keys]\nquit = \"x\"\n");
+        let err = load_from(Some(global.clone()), Some(dir.clone()))
+            .err()
+            .unwrap();
+        assert!(err.starts_with("invalid configuration ("), "{err}");
+
+        fs::remove_file(&global).unwrap();
+        write(&dir_path(&dir), "todotxt_dir = \"/elsewhere\"\n");
+        let err = load_from(Some(global), Some(dir)).err().unwrap();
+        assert!(
+            err.contains("cannot be set in a per-directory config"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_load_reports_unreadable_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let global = tmp.path().join("config.toml");
+        let dir = tmp.path().to_str().unwrap().to_string();
+
+        fs::write(&global, b"view = \"\xff\"\n").unwrap();
+        let err = load_from(Some(global.clone()), Some(dir.clone()))
+            .err()
+            .unwrap();
+        assert!(err.starts_with(&global.display().to_string()), "{err}");
+
+        fs::remove_file(&global).unwrap();
+        fs::create_dir_all(dir_path(&dir)).unwrap();
+        let err = load_from(Some(global), Some(dir.clone())).err().unwrap();
+        assert!(
+            err.starts_with(&dir_path(&dir).display().to_string()),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_to_toml_spells_out_keys() {
+        let config = parse("[keys]\nquit = \"Q\"");
+        let shown = config.to_toml().unwrap();
+        assert!(shown.contains("quit = \"Q\""), "{shown}");
+        assert!(shown.contains("complete = \"x\""), "{shown}");
+        assert!(shown.contains("[keys.claude.send]"), "{shown}");
+        assert!(shown.contains("theme = \"dark\""), "{shown}");
+        let reparsed: Config = toml::from_str(&shown).unwrap();
+        assert_eq!(reparsed.keymap(), config.keymap());
+    }
+}
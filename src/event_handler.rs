@@ -1,24 +1,38 @@
-use crate::app_state::{AppState, ViewMode};
-use crossterm::event::{Event, KeyCode};
+use crate::app_state::{AppState, View, ViewMode};
+use crate::keymap::{self, Action, Key, Keymap, Lookup, display_keys, group_label};
+use crate::mouse::{Hit, TabTarget};
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 use log::debug;
 use notify::{Event as NotifyEvent, EventKind};
 use std::sync::mpsc;
 use std::time::{Duration, Instant};
 
+/// Longest gap between two clicks on the same card that counts as a
+/// double-click.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
 pub struct EventHandler {
     last_reload_time: Option<Instant>,
     last_md_refresh_time: Option<Instant>,
     debounce_duration: Duration,
-    pending_keys: Vec<char>,
+    pending_keys: Vec<Key>,
+    keymap: Keymap,
+    /// Time and card of the last left click, to detect double-clicks
+    last_click: Option<(Instant, usize, usize)>,
 }
 
 impl EventHandler {
-    pub const fn new() -> Self {
+    pub fn new() -> Self {
+        let config = crate::config::get();
         Self {
             last_reload_time: None,
             last_md_refresh_time: None,
-            debounce_duration: Duration::from_millis(200),
+            debounce_duration: Duration::from_millis(config.watch.debounce_ms),
             pending_keys: Vec::new(),
+            keymap: keymap::active().clone(),
+            last_click: None,
         }
     }
 
@@ -52,8 +66,20 @@ impl EventHandler {
                         .and_then(|n| n.to_str())
                         == Some("todos")
             });
+            let is_done_file_event = event.paths.iter().any(|path| {
+                path.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|name| name == crate::config::get().files.done)
+            });
 
-            if is_active_file_event {
+            // The agenda shows every mode file, so any of them changing is a
+            // reload; the done archive only follows done.txt
+            let shown = match state.view {
+                View::Board => is_active_file_event,
+                View::Agenda => is_mode_file_event,
+                View::Done => is_done_file_event,
+            };
+            if shown {
                 if debug_mode {
                     debug!("Active file event detected: {:?}", event.kind);
                 }
@@ -121,14 +147,34 @@ impl EventHandler {
         todo_file: &str,
         debug_mode: bool,
     ) -> bool {
+        if let Event::Paste(text) = event {
+            if let Some(input) = state.input.as_mut() {
+                input.editor.insert_str(text);
+                state.input_changed();
+            }
+            return false;
+        }
+        if let Event::Mouse(mouse) = *event {
+            self.handle_mouse(mouse, state);
+            return false;
+        }
         if let Event::Key(key) = *event {
+            // The inline prompt captures every key until it is submitted or cancelled
+            if state.input.is_some() {
+                Self::handle_input_key(key, state);
+                return false;
+            }
+
             // Handle help overlay keys when help is shown
             if state.show_help {
-                match key.code {
-                    KeyCode::Char('?' | 'q') | KeyCode::Esc => {
-                        state.show_help = false;
-                    }
-                    _ => {}
+                let closes = Key::from_event(&key).is_some_and(|k| {
+                    matches!(
+                        self.keymap.lookup(&[k]),
+                        Lookup::Action(Action::Help | Action::Quit)
+                    )
+                });
+                if closes || key.code == KeyCode::Esc {
+                    state.show_help = false;
                 }
                 return false;
             }
@@ -169,280 +215,297 @@ impl EventHandler {
                 return false;
             }
 
-            // Handle multi-stroke sequences
-            if !self.pending_keys.is_empty() {
-                self.handle_pending_sequence(key.code, state, todo_file, debug_mode);
+            let Some(pressed) = Key::from_event(&key) else {
+                if !self.pending_keys.is_empty() {
+                    // Any other key cancels a sequence
+                    self.pending_keys.clear();
+                    state.status_message = None;
+                } else if key.code == KeyCode::Esc {
+                    if state.search.is_some() {
+                        state.clear_search();
+                    } else if state.filter.is_some() {
+                        state.clear_filter();
+                    }
+                }
                 return false;
-            }
-
-            return self.handle_initial_key(key.code, state, debug_mode);
+            };
+            return self.handle_key(pressed, state, todo_file, debug_mode);
         }
         false // Continue running
     }
 
-    #[allow(clippy::too_many_lines)]
-    fn handle_pending_sequence(
-        &mut self,
-        code: KeyCode,
-        state: &mut AppState,
-        todo_file: &str,
-        debug_mode: bool,
-    ) {
-        let KeyCode::Char(c) = code else {
-            // Non-char key pressed during sequence - cancel
-            self.pending_keys.clear();
-            state.status_message = None;
+    /// Clicks select cards and switch tabs, a double-click opens the card's
+    /// detail md in nvim, and the wheel moves through the column under the
+    /// pointer. Overlays and the prompt are keyboard-only.
+    fn handle_mouse(&mut self, mouse: MouseEvent, state: &mut AppState) {
+        if state.input.is_some()
+            || state.show_help
+            || state.plan_modal.is_some()
+            || state.hint.is_some()
+            || state.template.is_some()
+        {
+            return;
+        }
+        let Some(hit) = state.click_map.hit(mouse.column, mouse.row) else {
             return;
         };
-
-        self.pending_keys.push(c);
-        let todotxt_dir = std::path::Path::new(todo_file)
-            .parent()
-            .and_then(|p| p.to_str())
-            .unwrap_or(".");
-
-        match self.pending_keys.as_slice() {
-            ['c', 's'] => {
-                // Intermediate state - wait for third key
-                state.status_message = Some("cs → p: Plan | i: Impl | Esc: Cancel".to_string());
-            }
-            ['c', 's', 'p'] => {
-                if debug_mode {
-                    debug!("Send plan prompt requested (csp)");
-                }
-                state.handle_send_plan(todotxt_dir);
-                self.pending_keys.clear();
-            }
-            ['c', 's', 'i'] => {
-                if debug_mode {
-                    debug!("Send implement prompt requested (csi)");
-                }
-                state.handle_send_implement(todotxt_dir);
-                self.pending_keys.clear();
-            }
-            ['c', 'g'] => {
-                // Intermediate state - wait for third key
-                state.status_message = Some("cg → p: Plans | Esc: Cancel".to_string());
-            }
-            ['c', 'g', 'p'] if state.crmux_supports_get_plans() => {
-                if debug_mode {
-                    debug!("Get plans requested (cgp)");
-                }
-                state.handle_open_plan_modal();
-                self.pending_keys.clear();
-            }
-            ['c', 'l'] => {
-                // Intermediate state - wait for third key
-                state.status_message = Some("cl → p: Plan | i: Impl | Esc: Cancel".to_string());
-            }
-            ['c', 'l', 'p'] => {
-                if debug_mode {
-                    debug!("Launch plan requested (clp)");
-                }
-                state.handle_launch_plan(todotxt_dir);
-                self.pending_keys.clear();
-            }
-            ['c', 'l', 'i'] => {
-                if debug_mode {
-                    debug!("Launch implement requested (cli)");
-                }
-                state.handle_launch_implement(todotxt_dir);
-                self.pending_keys.clear();
-            }
-            ['s', 't'] => {
-                if debug_mode {
-                    debug!("Send to todo (st)");
-                }
-                state.handle_send_to(ViewMode::Todo);
-                self.pending_keys.clear();
-                state.status_message = None;
-            }
-            ['s', 'r'] => {
-                if debug_mode {
-                    debug!("Send to ref (sr)");
-                }
-                state.handle_send_to(ViewMode::Ref);
-                self.pending_keys.clear();
-                state.status_message = None;
-            }
-            ['s', 'i'] => {
-                if debug_mode {
-                    debug!("Send to inbox (si)");
-                }
-                state.handle_send_to(ViewMode::Inbox);
-                self.pending_keys.clear();
-                state.status_message = None;
+        if !self.pending_keys.is_empty() {
+            self.pending_keys.clear();
+            state.status_message = None;
+        }
+        match (mouse.kind, hit) {
+            (MouseEventKind::Down(MouseButton::Left), Hit::Tab(TabTarget::Mode(mode))) => {
+                state.set_view_mode(mode);
             }
-            ['s', 's'] => {
-                if debug_mode {
-                    debug!("Send to someday (ss)");
-                }
-                state.handle_send_to(ViewMode::Someday);
-                self.pending_keys.clear();
-                state.status_message = None;
+            (MouseEventKind::Down(MouseButton::Left), Hit::Tab(TabTarget::Agenda)) => {
+                state.set_view(View::Agenda);
             }
-            ['s', 'w'] => {
-                if debug_mode {
-                    debug!("Send to waiting (sw)");
-                }
-                state.handle_send_to(ViewMode::Waiting);
-                self.pending_keys.clear();
-                state.status_message = None;
+            (MouseEventKind::Down(MouseButton::Left), Hit::Tab(TabTarget::Done)) => {
+                state.set_view(View::Done);
             }
-            ['p', c @ ('a'..='e')] => {
-                let priority = c.to_ascii_uppercase();
-                if debug_mode {
-                    debug!("Set priority ({priority}) via p{c}");
+            (MouseEventKind::Down(MouseButton::Left), Hit::Card { column, row }) => {
+                let now = Instant::now();
+                let is_double = self.last_click.is_some_and(|(at, c, r)| {
+                    (c, r) == (column, row) && now.duration_since(at) <= DOUBLE_CLICK
+                });
+                if is_double {
+                    self.last_click = None;
+                    state.open_current_md();
+                } else {
+                    self.last_click = Some((now, column, row));
+                    state.select_card(column, row);
                 }
-                state.handle_set_priority(Some(priority));
-                self.pending_keys.clear();
-                state.status_message = None;
             }
-            ['p', 'x'] => {
-                if debug_mode {
-                    debug!("Clear priority (px)");
-                }
-                state.handle_set_priority(None);
-                self.pending_keys.clear();
-                state.status_message = None;
+            (MouseEventKind::Down(MouseButton::Left), Hit::Column(column))
+                if column != state.current_column =>
+            {
+                state.select_card(column, 0);
             }
-            ['d', 'd'] => {
-                if debug_mode {
-                    debug!("Delete todo requested (dd)");
-                }
-                state.handle_delete_todo();
-                self.pending_keys.clear();
-                state.status_message = None;
+            (
+                MouseEventKind::ScrollDown | MouseEventKind::ScrollUp,
+                Hit::Card { column, .. } | Hit::Column(column),
+            ) => {
+                state.scroll_column(column, mouse.kind == MouseEventKind::ScrollDown);
             }
-            _ => {
-                if debug_mode {
-                    debug!("Unknown key sequence: {:?}", self.pending_keys);
+            _ => {}
+        }
+    }
+
+    fn handle_input_key(key: KeyEvent, state: &mut AppState) {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => state.cancel_input(),
+            KeyCode::Enter => state.submit_input(),
+            code => {
+                let Some(input) = state.input.as_mut() else {
+                    return;
+                };
+                let editor = &mut input.editor;
+                match code {
+                    KeyCode::Backspace => editor.backspace(),
+                    KeyCode::Delete => editor.delete(),
+                    KeyCode::Left => editor.move_left(),
+                    KeyCode::Right => editor.move_right(),
+                    KeyCode::Home => editor.move_home(),
+                    KeyCode::End => editor.move_end(),
+                    KeyCode::Char('a') if ctrl => editor.move_home(),
+                    KeyCode::Char('e') if ctrl => editor.move_end(),
+                    KeyCode::Char('u') if ctrl => editor.delete_to_start(),
+                    KeyCode::Char('w') if ctrl => editor.delete_word_before(),
+                    KeyCode::Char(c) if !ctrl => editor.insert_char(c),
+                    _ => {}
                 }
-                self.pending_keys.clear();
-                state.status_message = None;
+                state.input_changed();
             }
         }
     }
 
-    fn handle_initial_key(
+    /// Add `key` to the typed sequence and run the action it completes,
+    /// or show the submenu of what can follow. Returns true to quit.
+    fn handle_key(
         &mut self,
-        code: KeyCode,
+        key: Key,
         state: &mut AppState,
+        todo_file: &str,
         debug_mode: bool,
     ) -> bool {
-        match code {
-            KeyCode::Char('q') => {
-                if debug_mode {
-                    debug!("Quit command received");
+        self.pending_keys.push(key);
+        match self.keymap.lookup(&self.pending_keys) {
+            Lookup::Action(action) => {
+                if self.pending_keys.len() > 1 {
+                    state.status_message = None;
                 }
-                return true; // Signal to quit
-            }
-            KeyCode::Char(c @ ('k' | 'j' | 'h' | 'l')) => {
-                if debug_mode {
-                    debug!("Navigation key pressed: {c}");
-                }
-                state.handle_navigation_key(c);
-            }
-            KeyCode::Char('x') if matches!(state.view_mode, ViewMode::Todo | ViewMode::Waiting) => {
-                if debug_mode {
-                    debug!("Complete todo command received");
-                }
-                let file = state.active_file();
-                state.handle_complete_todo(&file);
-            }
-            KeyCode::Char('d') => {
-                self.pending_keys.push('d');
-                state.status_message = Some(build_d_submenu());
-            }
-            KeyCode::Char('s') => {
-                self.pending_keys.push('s');
-                state.status_message = Some(build_s_submenu(state));
-            }
-            KeyCode::Char('p') => {
-                self.pending_keys.push('p');
-                state.status_message = Some(build_p_submenu());
-            }
-            KeyCode::Tab => {
-                if debug_mode {
-                    debug!("Tab: next mode");
+                self.pending_keys.clear();
+                if !is_enabled(action, state) {
+                    return false;
                 }
-                state.next_view_mode();
-            }
-            KeyCode::BackTab => {
                 if debug_mode {
-                    debug!("Shift+Tab: previous mode");
+                    debug!("Action requested: {}", action.name());
                 }
-                state.prev_view_mode();
-            }
-            KeyCode::Char('c')
-                if state.view_mode == ViewMode::Todo
-                    && (state.crmux_available() || state.claude_available()) =>
-            {
-                self.pending_keys.push('c');
-                state.status_message = Some(build_c_submenu(state));
+                return run_action(action, state, todo_file);
             }
-            KeyCode::Char('o') => {
-                if debug_mode {
-                    debug!("Open URLs command received");
-                }
-                state.handle_open_urls();
-            }
-            KeyCode::Char('f') => {
-                if debug_mode {
-                    debug!("Hint mode requested");
+            Lookup::Prefix => {
+                if let Some(menu) = build_submenu(&self.keymap, &self.pending_keys, state) {
+                    state.status_message = Some(menu);
+                } else {
+                    // Nothing behind this prefix applies here
+                    self.pending_keys.clear();
                 }
-                state.pending_enter_hint = true;
             }
-            KeyCode::Char('t')
-                if matches!(state.view_mode, ViewMode::Todo | ViewMode::Waiting)
-                    && state.get_current_todo_id().is_some() =>
-            {
-                if debug_mode {
-                    debug!("Template mode requested");
+            Lookup::None => {
+                if self.pending_keys.len() > 1 {
+                    if debug_mode {
+                        debug!("Unknown key sequence: {:?}", self.pending_keys);
+                    }
+                    state.status_message = None;
                 }
-                state.pending_enter_template = true;
-            }
-            KeyCode::Char('?') => {
-                state.toggle_help();
+                self.pending_keys.clear();
             }
-            _ => {}
         }
         false
     }
 }
 
-fn build_p_submenu() -> String {
-    "p → a/b/c/d/e: Set (A-E) | x: Clear | Esc: Cancel".to_string()
+/// Whether `action` does anything in the current mode and view.
+fn is_enabled(action: Action, state: &AppState) -> bool {
+    if state.view == View::Done {
+        // The archive is read-only apart from taking items back out
+        return !matches!(
+            action,
+            Action::Complete
+                | Action::Delete
+                | Action::SendTo(_)
+                | Action::Priority(_)
+                | Action::ClearPriority
+                | Action::Add
+                | Action::AddBlank
+                | Action::Edit
+                | Action::Templates
+                | Action::ClaudeSendPlan
+                | Action::ClaudeSendImplement
+                | Action::ClaudeGetPlans
+                | Action::ClaudeLaunchPlan
+                | Action::ClaudeLaunchImplement
+        );
+    }
+    match action {
+        Action::Uncomplete => false,
+        Action::Templates => {
+            matches!(state.view_mode, ViewMode::Todo | ViewMode::Waiting)
+                && state.get_current_todo_id().is_some()
+        }
+        Action::ClaudeSendPlan
+        | Action::ClaudeSendImplement
+        | Action::ClaudeGetPlans
+        | Action::ClaudeLaunchPlan
+        | Action::ClaudeLaunchImplement => {
+            state.view_mode == ViewMode::Todo
+                && state.view == View::Board
+                && (state.crmux_available() || state.claude_available())
+                && (action != Action::ClaudeGetPlans || state.crmux_supports_get_plans())
+        }
+        _ => true,
+    }
 }
 
-fn build_d_submenu() -> String {
-    "d → d: Delete | Esc: Cancel".to_string()
+/// Whether a submenu offers `action`. Stricter than `is_enabled`: sending an
+/// item to its own mode and tools that are not installed are left out.
+fn in_menu(action: Action, state: &AppState) -> bool {
+    let offered = match action {
+        Action::SendTo(mode) => {
+            let current = state
+                .get_current_todo_id()
+                .map_or(state.view_mode, |id| state.mode_of(id));
+            mode != current
+        }
+        Action::ClaudeSendPlan | Action::ClaudeSendImplement => state.crmux_available(),
+        Action::ClaudeLaunchPlan | Action::ClaudeLaunchImplement => state.claude_available(),
+        _ => true,
+    };
+    offered && is_enabled(action, state)
 }
 
-fn build_s_submenu(state: &AppState) -> String {
-    let mut parts = vec!["s →".to_string()];
-    for mode in ViewMode::ALL {
-        if *mode != state.view_mode {
-            parts.push(format!("{}: {}", mode.shortcut_key(), mode.label()));
+/// Status line listing the keys that can follow `typed`, e.g.
+/// `c → | s: Send… | l: Launch… | Esc: Cancel`. `None` when no action
+/// behind `typed` is enabled; enabled ones left out of the menu can still
+/// be typed.
+fn build_submenu(keymap: &Keymap, typed: &[Key], state: &AppState) -> Option<String> {
+    let mut parts = vec![format!("{} →", display_keys(typed))];
+    let mut enabled = false;
+    for (next, actions) in keymap.next_keys(typed) {
+        enabled |= actions.iter().any(|a| is_enabled(*a, state));
+        let offered: Vec<Action> = actions.into_iter().filter(|a| in_menu(*a, state)).collect();
+        match offered.as_slice() {
+            [] => {}
+            [action]
+                if keymap
+                    .keys(*action)
+                    .is_some_and(|k| k.len() == typed.len() + 1) =>
+            {
+                parts.push(format!("{next}: {}", action.label()));
+            }
+            _ => parts.push(format!("{next}: {}…", group_label(&offered))),
         }
     }
+    if !enabled {
+        return None;
+    }
     parts.push("Esc: Cancel".to_string());
-    parts.join(" | ")
+    Some(parts.join(" | "))
 }
 
-fn build_c_submenu(state: &AppState) -> String {
-    let mut parts = vec!["c →".to_string()];
-    if state.crmux_available() {
-        parts.push("s: Send…".to_string());
-    }
-    if state.crmux_supports_get_plans() {
-        parts.push("g: Get…".to_string());
-    }
-    if state.claude_available() {
-        parts.push("l: Launch…".to_string());
+/// Carry out `action`. Returns true to quit.
+fn run_action(action: Action, state: &mut AppState, todo_file: &str) -> bool {
+    let todotxt_dir = std::path::Path::new(todo_file)
+        .parent()
+        .and_then(|p| p.to_str())
+        .unwrap_or(".");
+    match action {
+        Action::Left => state.handle_navigation_key('h'),
+        Action::Down => state.handle_navigation_key('j'),
+        Action::Up => state.handle_navigation_key('k'),
+        Action::Right => state.handle_navigation_key('l'),
+        Action::NextMode => state.next_view_mode(),
+        Action::PrevMode => state.prev_view_mode(),
+        Action::Undo => state.handle_undo(),
+        Action::Redo => state.handle_redo(),
+        Action::Complete => {
+            let file = state.current_file();
+            state.handle_complete_todo(&file);
+        }
+        Action::Delete => state.handle_delete_todo(),
+        Action::SendTo(mode) => state.handle_send_to(mode),
+        Action::Priority(priority) => state.handle_set_priority(Some(priority)),
+        Action::ClearPriority => state.handle_set_priority(None),
+        Action::HideColumn => state.hide_current_column(),
+        Action::PinColumn => state.toggle_pin_current_column(),
+        Action::UnhideColumns => state.unhide_all_columns(),
+        Action::CycleColumnSort => state.cycle_column_sort(),
+        Action::MoveColumnLeft => state.move_current_column(true),
+        Action::MoveColumnRight => state.move_current_column(false),
+        Action::CycleGrouping => state.cycle_grouping(),
+        Action::ToggleAgenda => state.toggle_agenda(),
+        Action::ToggleDone => state.toggle_done(),
+        Action::Uncomplete => state.handle_uncomplete(),
+        Action::Add => state.start_add_item(true),
+        Action::AddBlank => state.start_add_item(false),
+        Action::Edit => state.start_edit_item(),
+        Action::OpenUrls => state.handle_open_urls(),
+        Action::Hint => state.pending_enter_hint = true,
+        Action::Search => state.start_search(),
+        Action::NextMatch => state.cycle_match(true),
+        Action::PrevMatch => state.cycle_match(false),
+        Action::Filter => state.start_filter(),
+        Action::Templates => state.pending_enter_template = true,
+        Action::ClaudeSendPlan => state.handle_send_plan(todotxt_dir),
+        Action::ClaudeSendImplement => state.handle_send_implement(todotxt_dir),
+        Action::ClaudeGetPlans => state.handle_open_plan_modal(),
+        Action::ClaudeLaunchPlan => state.handle_launch_plan(todotxt_dir),
+        Action::ClaudeLaunchImplement => state.handle_launch_implement(todotxt_dir),
+        Action::Help => state.toggle_help(),
+        Action::Quit => return true,
     }
-    parts.push("Esc: Cancel".to_string());
-    parts.join(" | ")
+    false
 }
 
 #[cfg(test)]
@@ -519,7 +582,7 @@ mod tests {
         assert!(state.status_message.is_some());
 
         handler.handle_keyboard_event(&make_key_event('s'), &mut state, todo_file, false);
-        assert_eq!(handler.pending_keys.as_slice(), &['c', 's']);
+        assert_eq!(display_keys(&handler.pending_keys), "cs");
     }
 
     #[test]
@@ -554,7 +617,7 @@ mod tests {
 
         handler.handle_keyboard_event(&make_key_event('c'), &mut state, todo_file, false);
         handler.handle_keyboard_event(&make_key_event('g'), &mut state, todo_file, false);
-        assert_eq!(handler.pending_keys.as_slice(), &['c', 'g']);
+        assert_eq!(display_keys(&handler.pending_keys), "cg");
     }
 
     #[test]
@@ -580,7 +643,7 @@ mod tests {
         assert!(state.status_message.is_some());
 
         handler.handle_keyboard_event(&make_key_event('l'), &mut state, todo_file, false);
-        assert_eq!(handler.pending_keys.as_slice(), &['c', 'l']);
+        assert_eq!(display_keys(&handler.pending_keys), "cl");
     }
 
     #[test]
@@ -626,7 +689,7 @@ mod tests {
         let todo_file = "/tmp/dummy.txt";
 
         handler.handle_keyboard_event(&make_key_event('d'), &mut state, todo_file, false);
-        assert_eq!(handler.pending_keys.as_slice(), &['d']);
+        assert_eq!(display_keys(&handler.pending_keys), "d");
         let msg = state.status_message.as_deref().unwrap();
         assert!(msg.contains("Delete"));
     }
@@ -669,6 +732,38 @@ mod tests {
         fs::remove_dir_all(&temp_dir).ok();
     }
 
+    #[test]
+    fn test_remapped_sequence_deletes_todo() {
+        use std::fs;
+        let dir = tempfile::tempdir().unwrap();
+        let todo_file = dir.path().join("todo.txt");
+        fs::write(&todo_file, "Zap me +proj id:zap-2\n").unwrap();
+        let todo_file = todo_file.to_str().unwrap();
+        let todos = crate::todo::load_todos(todo_file).unwrap();
+        let mut state = crate::app_state::AppState::new(
+            todos,
+            String::new(),
+            dir.path().to_str().unwrap().to_string(),
+        );
+
+        let mut handler = EventHandler::new();
+        let overrides = [("delete".to_string(), "<Space>d".to_string())].into();
+        handler.keymap = Keymap::new(&overrides).unwrap();
+
+        // The old binding is gone
+        handler.handle_keyboard_event(&make_key_event('d'), &mut state, todo_file, false);
+        assert!(handler.pending_keys.is_empty());
+
+        handler.handle_keyboard_event(&make_key_event(' '), &mut state, todo_file, false);
+        assert_eq!(
+            state.status_message.as_deref(),
+            Some("Space → | d: Delete | Esc: Cancel")
+        );
+        handler.handle_keyboard_event(&make_key_event('d'), &mut state, todo_file, false);
+        assert!(handler.pending_keys.is_empty());
+        assert!(!fs::read_to_string(todo_file).unwrap().contains("zap-2"));
+    }
+
     #[test]
     fn test_d_then_other_cancels() {
         let mut handler = EventHandler::new();
@@ -708,7 +803,7 @@ mod tests {
         assert!(msg.contains("c →"));
         assert!(msg.contains("s: Send…"));
         assert!(msg.contains("g: Get…"));
-        assert_eq!(handler.pending_keys.as_slice(), &['c']);
+        assert_eq!(display_keys(&handler.pending_keys), "c");
     }
 
     #[test]
@@ -721,7 +816,7 @@ mod tests {
         let msg = state.status_message.as_deref().unwrap();
         assert!(msg.contains("c →"));
         assert!(msg.contains("l: Launch…"));
-        assert_eq!(handler.pending_keys.as_slice(), &['c']);
+        assert_eq!(display_keys(&handler.pending_keys), "c");
     }
 
     #[test]
@@ -891,10 +986,11 @@ mod tests {
 
         handler.handle_keyboard_event(&make_key_event('p'), &mut state, todo_file, false);
 
-        assert_eq!(handler.pending_keys.as_slice(), &['p']);
+        assert_eq!(display_keys(&handler.pending_keys), "p");
         let msg = state.status_message.as_deref().unwrap();
         assert!(msg.contains("p →"));
-        assert!(msg.contains("a/b/c/d/e"));
+        assert!(msg.contains("a: (A) | b: (B)"));
+        assert!(msg.contains("e: (E)"));
         assert!(msg.contains("x: Clear"));
     }
 
@@ -1000,6 +1096,69 @@ mod tests {
         assert!(state.status_message.is_none());
     }
 
+    #[test]
+    fn test_v_toggles_agenda_and_x_completes_inbox_item() {
+        use crate::app_state::View;
+        use std::fs;
+        let dir = tempfile::tempdir().unwrap();
+        let dir_path = dir.path().to_str().unwrap().to_string();
+        fs::write(format!("{dir_path}/todo.txt"), "").unwrap();
+        let inbox_path = format!("{dir_path}/inbox.txt");
+        fs::write(&inbox_path, "Call Bob due:2000-01-01 id:in-x\n").unwrap();
+
+        let mut state = crate::app_state::AppState::new(vec![], String::new(), dir_path.clone());
+        let mut handler = EventHandler::new();
+        let todo_file = format!("{dir_path}/todo.txt");
+        handler.handle_keyboard_event(&make_key_event('v'), &mut state, &todo_file, false);
+        assert_eq!(state.view, View::Agenda);
+        assert_eq!(state.get_current_todo_id(), Some("in-x"));
+
+        handler.handle_keyboard_event(&make_key_event('x'), &mut state, &todo_file, false);
+        assert!(!fs::read_to_string(&inbox_path).unwrap().contains("in-x"));
+
+        handler.handle_keyboard_event(&make_key_event('v'), &mut state, &todo_file, false);
+        assert_eq!(state.view, View::Board);
+    }
+
+    #[test]
+    fn test_done_archive_is_read_only_except_uncomplete() {
+        use crate::app_state::View;
+        use std::fs;
+        let dir = tempfile::tempdir().unwrap();
+        let dir_path = dir.path().to_str().unwrap().to_string();
+        let todo_file = format!("{dir_path}/todo.txt");
+        let done_path = format!("{dir_path}/done.txt");
+        fs::write(&todo_file, "").unwrap();
+        fs::write(&done_path, "x 2026-10-01 Shipped it +work id:d-1\n").unwrap();
+
+        let mut state = crate::app_state::AppState::new(vec![], String::new(), dir_path);
+        let mut handler = EventHandler::new();
+        handler.handle_keyboard_event(&make_key_event('D'), &mut state, &todo_file, false);
+        assert_eq!(state.view, View::Done);
+        assert_eq!(state.get_current_todo_id(), Some("d-1"));
+
+        for c in ['x', 'd', 'd', 'p', 'a', 'e'] {
+            handler.handle_keyboard_event(&make_key_event(c), &mut state, &todo_file, false);
+        }
+        assert!(state.input.is_none());
+        assert_eq!(
+            fs::read_to_string(&done_path).unwrap(),
+            "x 2026-10-01 Shipped it +work id:d-1\n"
+        );
+
+        handler.handle_keyboard_event(&make_key_event('U'), &mut state, &todo_file, false);
+        assert_eq!(fs::read_to_string(&done_path).unwrap(), "");
+        assert_eq!(
+            fs::read_to_string(&todo_file).unwrap(),
+            "Shipped it +work id:d-1\n"
+        );
+        assert!(state.todos.is_empty());
+
+        handler.handle_keyboard_event(&make_key_event('D'), &mut state, &todo_file, false);
+        assert_eq!(state.view, View::Board);
+        assert_eq!(state.get_current_todo_id(), Some("d-1"));
+    }
+
     #[test]
     fn test_x_completes_in_waiting_mode() {
         use std::fs;
@@ -1061,7 +1220,7 @@ mod tests {
     }
 
     #[test]
-    fn test_x_ignored_in_inbox_mode() {
+    fn test_x_completes_in_inbox_mode_and_records_it() {
         use std::fs;
         let dir = tempfile::tempdir().unwrap();
         let dir_path = dir.path().to_str().unwrap().to_string();
@@ -1076,14 +1235,11 @@ mod tests {
         handler.handle_keyboard_event(&make_key_event('x'), &mut state, &inbox_path, false);
 
         let content = fs::read_to_string(&inbox_path).unwrap();
+        assert!(!content.contains("inb-x"), "{content}");
+        let done = fs::read_to_string(format!("{dir_path}/done.txt")).unwrap();
         assert!(
-            content.contains("inb-x"),
-            "x in Inbox should be no-op: {content}"
-        );
-        let done_path = format!("{dir_path}/done.txt");
-        assert!(
-            !std::path::Path::new(&done_path).exists(),
-            "done.txt should not be created from Inbox"
+            done.ends_with(" Inbox idea +proj id:inb-x from:inbox\n"),
+            "{done}"
         );
     }
 
@@ -1276,4 +1432,211 @@ mod tests {
         let got = std::fs::read_to_string(&md).unwrap();
         assert_eq!(got, "## Design\n");
     }
+
+    fn make_special_key_event(code: KeyCode, modifiers: KeyModifiers) -> Event {
+        Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        })
+    }
+
+    #[test]
+    fn test_a_opens_add_prompt_and_captures_keys() {
+        let mut handler = EventHandler::new();
+        let mut state = create_test_state_with_crmux();
+        let todo_file = "/tmp/dummy.txt";
+
+        handler.handle_keyboard_event(&make_key_event('a'), &mut state, todo_file, false);
+        assert_eq!(state.input.as_ref().unwrap().editor.text(), " +proj");
+
+        // Keys that are normally commands are typed into the prompt instead
+        let quit =
+            handler.handle_keyboard_event(&make_key_event('q'), &mut state, todo_file, false);
+        assert!(!quit, "q must not quit while the prompt is open");
+        handler.handle_keyboard_event(&make_key_event('?'), &mut state, todo_file, false);
+        assert!(!state.show_help);
+        assert_eq!(state.input.as_ref().unwrap().editor.text(), "q? +proj");
+
+        handler.handle_keyboard_event(
+            &make_special_key_event(KeyCode::Esc, KeyModifiers::NONE),
+            &mut state,
+            todo_file,
+            false,
+        );
+        assert!(state.input.is_none());
+    }
+
+    #[test]
+    fn test_add_prompt_editing_keys_and_paste() {
+        let mut handler = EventHandler::new();
+        let mut state = create_test_state_with_crmux();
+        let todo_file = "/tmp/dummy.txt";
+
+        handler.handle_keyboard_event(&make_key_event('A'), &mut state, todo_file, false);
+        handler.handle_keyboard_event(
+            &Event::Paste("Buy\nmilk".to_string()),
+            &mut state,
+            todo_file,
+            false,
+        );
+        handler.handle_keyboard_event(
+            &make_special_key_event(KeyCode::Char('a'), KeyModifiers::CONTROL),
+            &mut state,
+            todo_file,
+            false,
+        );
+        handler.handle_keyboard_event(
+            &make_special_key_event(KeyCode::Delete, KeyModifiers::NONE),
+            &mut state,
+            todo_file,
+            false,
+        );
+        handler.handle_keyboard_event(&make_key_event('b'), &mut state, todo_file, false);
+        let editor = &state.input.as_ref().unwrap().editor;
+        assert_eq!(editor.text(), "buy milk");
+        assert_eq!(editor.cursor(), 1);
+    }
+
+    #[test]
+    fn test_paste_ignored_without_prompt() {
+        let mut handler = EventHandler::new();
+        let mut state = create_test_state_with_crmux();
+        handler.handle_keyboard_event(
+            &Event::Paste("q".to_string()),
+            &mut state,
+            "/tmp/dummy.txt",
+            false,
+        );
+        assert!(state.input.is_none());
+    }
+
+    #[test]
+    fn test_e_opens_edit_prompt_with_raw_line() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            tmp.path().join("todo.txt"),
+            "(A) Test task +proj id:test-id\n",
+        )
+        .unwrap();
+        let mut handler = EventHandler::new();
+        let mut state = create_test_state_with_crmux();
+        state.todotxt_dir = tmp.path().to_string_lossy().into_owned();
+
+        handler.handle_keyboard_event(&make_key_event('e'), &mut state, "/tmp/dummy.txt", false);
+
+        let input = state.input.as_ref().expect("edit prompt should open");
+        assert_eq!(input.editor.text(), "(A) Test task +proj id:test-id");
+    }
+
+    #[test]
+    fn test_slash_search_filters_live_and_enter_keeps_filter() {
+        let mut handler = EventHandler::new();
+        let mut state = create_test_state_with_crmux();
+        let todo_file = "/tmp/dummy.txt";
+
+        handler.handle_keyboard_event(&make_key_event('/'), &mut state, todo_file, false);
+        handler.handle_keyboard_event(&make_key_event('z'), &mut state, todo_file, false);
+        assert!(state.project_names.is_empty(), "no card matches 'z'");
+        handler.handle_keyboard_event(
+            &make_special_key_event(KeyCode::Backspace, KeyModifiers::NONE),
+            &mut state,
+            todo_file,
+            false,
+        );
+        for c in "tst".chars() {
+            handler.handle_keyboard_event(&make_key_event(c), &mut state, todo_file, false);
+        }
+        handler.handle_keyboard_event(
+            &make_special_key_event(KeyCode::Enter, KeyModifiers::NONE),
+            &mut state,
+            todo_file,
+            false,
+        );
+        assert!(state.input.is_none());
+        assert_eq!(state.search.as_deref(), Some("tst"));
+        assert_eq!(state.get_current_todo_id(), Some("test-id"));
+
+        handler.handle_keyboard_event(
+            &make_special_key_event(KeyCode::Esc, KeyModifiers::NONE),
+            &mut state,
+            todo_file,
+            false,
+        );
+        assert!(state.search.is_none());
+    }
+
+    fn mouse_event(kind: MouseEventKind, (column, row): (u16, u16)) -> Event {
+        Event::Mouse(MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        })
+    }
+
+    /// Draw `state` and return a cell where `target` was drawn.
+    fn draw_and_find(state: &mut crate::app_state::AppState, target: Hit) -> (u16, u16) {
+        let backend = ratatui::backend::TestBackend::new(80, 24);
+        let mut terminal = ratatui::Terminal::new(backend).unwrap();
+        terminal.draw(|f| crate::ui::draw_ui(f, state)).unwrap();
+        (0..24)
+            .flat_map(|y| (0..80).map(move |x| (x, y)))
+            .find(|&(x, y)| state.click_map.hit(x, y) == Some(target))
+            .expect("target drawn")
+    }
+
+    #[test]
+    fn test_mouse_selects_cards_scrolls_and_switches_tabs() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir_path = dir.path().to_str().unwrap().to_string();
+        let todo_file = format!("{dir_path}/todo.txt");
+        std::fs::write(
+            &todo_file,
+            "A1 +alpha id:a1\nA2 +alpha id:a2\nB1 +beta id:b1\n",
+        )
+        .unwrap();
+        let mut state = crate::app_state::AppState::new(vec![], String::new(), dir_path);
+        state.reload_todos(&todo_file);
+        let mut handler = EventHandler::new();
+        let click = MouseEventKind::Down(MouseButton::Left);
+
+        let b1 = draw_and_find(&mut state, Hit::Card { column: 1, row: 0 });
+        handler.handle_keyboard_event(&mouse_event(click, b1), &mut state, &todo_file, false);
+        assert_eq!(state.get_current_todo_id(), Some("b1"));
+        assert!(handler.last_click.is_some());
+
+        // A second click on the same card opens it instead
+        handler.handle_keyboard_event(&mouse_event(click, b1), &mut state, &todo_file, false);
+        assert_eq!(state.get_current_todo_id(), Some("b1"));
+        assert!(handler.last_click.is_none());
+
+        let alpha = draw_and_find(&mut state, Hit::Column(0));
+        let scroll_down = mouse_event(MouseEventKind::ScrollDown, alpha);
+        handler.handle_keyboard_event(&scroll_down, &mut state, &todo_file, false);
+        assert_eq!(state.get_current_todo_id(), Some("a1"));
+        handler.handle_keyboard_event(&scroll_down, &mut state, &todo_file, false);
+        handler.handle_keyboard_event(&scroll_down, &mut state, &todo_file, false);
+        assert_eq!(state.get_current_todo_id(), Some("a2"));
+        let scroll_up = mouse_event(MouseEventKind::ScrollUp, alpha);
+        handler.handle_keyboard_event(&scroll_up, &mut state, &todo_file, false);
+        assert_eq!(state.get_current_todo_id(), Some("a1"));
+
+        let inbox = draw_and_find(&mut state, Hit::Tab(TabTarget::Mode(ViewMode::Inbox)));
+        handler.handle_keyboard_event(&mouse_event(click, inbox), &mut state, &todo_file, false);
+        assert_eq!(state.view_mode, ViewMode::Inbox);
+    }
+
+    #[test]
+    fn test_mouse_ignored_while_help_is_open() {
+        let mut state = create_test_state_with_crmux();
+        let mut handler = EventHandler::new();
+        let tab = draw_and_find(&mut state, Hit::Tab(TabTarget::Mode(ViewMode::Inbox)));
+        state.show_help = true;
+        let click = mouse_event(MouseEventKind::Down(MouseButton::Left), tab);
+        handler.handle_keyboard_event(&click, &mut state, "todo.txt", false);
+        assert_eq!(state.view_mode, ViewMode::Todo);
+        assert!(state.show_help);
+    }
 }
@@ -0,0 +1,316 @@
+use crate::app_state::ViewMode;
+use crate::store;
+use crate::todo::Item;
+use log::debug;
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Tags that are dates or schedules rather than categories, so cycling
+/// skips them.
+const SKIPPED_TAGS: &[&str] = &["due", "t", "rec"];
+
+/// What the board's columns are made of.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum GroupBy {
+    #[default]
+    Project,
+    Context,
+    Priority,
+    /// The value of a `key:value` tag, e.g. `owner` for `owner:alice`
+    Tag(String),
+    /// A single column holding every item
+    None,
+}
+
+impl GroupBy {
+    /// Column names an item is listed under. Items with several projects or
+    /// contexts appear in each of their columns.
+    fn columns(&self, item: &Item) -> Vec<String> {
+        match self {
+            Self::Project if item.projects.is_empty() => vec!["No Project".to_string()],
+            Self::Project => item.projects.clone(),
+            Self::Context if item.contexts.is_empty() => vec!["No Context".to_string()],
+            Self::Context => item.contexts.clone(),
+            Self::Priority => vec![
+                item.priority
+                    .map_or_else(|| "No Priority".to_string(), |p| format!("({p})")),
+            ],
+            Self::Tag(key) => vec![
+                item.key_values
+                    .get(key)
+                    .map_or_else(|| format!("No {key}"), Clone::clone),
+            ],
+            Self::None => vec!["All".to_string()],
+        }
+    }
+
+    pub fn group(&self, todos: &[Item]) -> HashMap<String, Vec<Item>> {
+        let mut grouped: HashMap<String, Vec<Item>> = HashMap::new();
+        for todo in todos {
+            for column in self.columns(todo) {
+                grouped.entry(column).or_default().push(todo.clone());
+            }
+        }
+        grouped
+    }
+
+    /// Text for the add prompt that files a new item under `column`, with
+    /// the cursor position (in chars). `None` for fallback columns.
+    pub fn prefill(&self, column: &str) -> Option<(String, usize)> {
+        match self {
+            Self::Project if column != "No Project" => Some((format!(" +{column}"), 0)),
+            Self::Context if column != "No Context" => Some((format!(" @{column}"), 0)),
+            Self::Priority if column.starts_with('(') => {
+                let text = format!("{column} ");
+                let len = text.chars().count();
+                Some((text, len))
+            }
+            Self::Tag(key) if *column != format!("No {key}") => {
+                Some((format!(" {key}:{column}"), 0))
+            }
+            _ => None,
+        }
+    }
+
+    /// The grouping after this one: project, context, priority, every tag
+    /// key found in `todos` (alphabetically), then none, and around again.
+    pub fn next(&self, todos: &[Item]) -> Self {
+        let keys: BTreeSet<&String> = todos
+            .iter()
+            .flat_map(|t| t.key_values.keys())
+            .filter(|k| !SKIPPED_TAGS.contains(&k.as_str()))
+            .collect();
+        let mut order = vec![Self::Project, Self::Context, Self::Priority];
+        order.extend(keys.into_iter().map(|k| Self::Tag(k.clone())));
+        order.push(Self::None);
+        order
+            .iter()
+            .position(|g| g == self)
+            .map_or(Self::None, |i| order[(i + 1) % order.len()].clone())
+    }
+}
+
+impl fmt::Display for GroupBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Project => f.write_str("project"),
+            Self::Context => f.write_str("context"),
+            Self::Priority => f.write_str("priority"),
+            Self::Tag(key) => write!(f, "tag:{key}"),
+            Self::None => f.write_str("none"),
+        }
+    }
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "project" => Ok(Self::Project),
+            "context" => Ok(Self::Context),
+            "priority" => Ok(Self::Priority),
+            "none" => Ok(Self::None),
+            _ => s
+                .strip_prefix("tag:")
+                .filter(|key| !key.is_empty())
+                .map(|key| Self::Tag(key.to_string()))
+                .ok_or_else(|| format!("unknown grouping: {s}")),
+        }
+    }
+}
+
+fn settings_path(todotxt_dir: &str) -> PathBuf {
+    Path::new(todotxt_dir).join(".torudo").join("grouping.json")
+}
+
+fn mode_key(mode: ViewMode) -> String {
+    mode.label().to_ascii_lowercase()
+}
+
+/// Per-mode groupings saved in `{todotxt_dir}/.torudo/grouping.json`.
+/// Modes missing from the file, or with an unreadable value, are omitted.
+pub fn load(todotxt_dir: &str) -> HashMap<ViewMode, GroupBy> {
+    let saved: HashMap<String, String> = fs::read_to_string(settings_path(todotxt_dir))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    ViewMode::ALL
+        .iter()
+        .filter_map(|mode| {
+            let value = saved.get(&mode_key(*mode))?;
+            match value.parse() {
+                Ok(group_by) => Some((*mode, group_by)),
+                Err(e) => {
+                    debug!("Ignoring saved grouping for {}: {e}", mode.label());
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+pub fn save(
+    todotxt_dir: &str,
+    groupings: &HashMap<ViewMode, GroupBy>,
+) -> Result<(), Box<dyn Error>> {
+    let path = settings_path(todotxt_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let saved: HashMap<String, String> = groupings
+        .iter()
+        .map(|(mode, group_by)| (mode_key(*mode), group_by.to_string()))
+        .collect();
+    store::write_atomic(&path, &serde_json::to_string_pretty(&saved)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(lines: &[&str]) -> Vec<Item> {
+        lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| Item::parse(line, i + 1))
+            .collect()
+    }
+
+    #[test]
+    fn test_group_by_project() {
+        let todos = items(&[
+            "Task 1 +work",
+            "Task 2 +personal",
+            "Task 3",
+            "Task 4 +work +urgent",
+        ]);
+        let grouped = GroupBy::Project.group(&todos);
+        assert_eq!(grouped.len(), 4);
+        assert_eq!(grouped["work"].len(), 2);
+        assert_eq!(grouped["personal"].len(), 1);
+        assert_eq!(grouped["No Project"].len(), 1);
+        assert_eq!(grouped["urgent"].len(), 1);
+    }
+
+    #[test]
+    fn test_group_by_context_priority_tag_and_none() {
+        let todos = items(&[
+            "(A) Call @phone @office owner:alice",
+            "(B) Email @office",
+            "Think owner:bob",
+        ]);
+
+        let grouped = GroupBy::Context.group(&todos);
+        assert_eq!(grouped["office"].len(), 2);
+        assert_eq!(grouped["phone"].len(), 1);
+        assert_eq!(grouped["No Context"].len(), 1);
+
+        let grouped = GroupBy::Priority.group(&todos);
+        assert_eq!(grouped["(A)"].len(), 1);
+        assert_eq!(grouped["(B)"].len(), 1);
+        assert_eq!(grouped["No Priority"].len(), 1);
+
+        let grouped = GroupBy::Tag("owner".to_string()).group(&todos);
+        assert_eq!(grouped["alice"].len(), 1);
+        assert_eq!(grouped["bob"].len(), 1);
+        assert_eq!(grouped["No owner"].len(), 1);
+
+        let grouped = GroupBy::None.group(&todos);
+        assert_eq!(grouped.len(), 1);
+        assert_eq!(grouped["All"].len(), 3);
+    }
+
+    #[test]
+    fn test_prefill() {
+        assert_eq!(
+            GroupBy::Project.prefill("work"),
+            Some((" +work".to_string(), 0))
+        );
+        assert_eq!(GroupBy::Project.prefill("No Project"), None);
+        assert_eq!(
+            GroupBy::Context.prefill("phone"),
+            Some((" @phone".to_string(), 0))
+        );
+        assert_eq!(
+            GroupBy::Priority.prefill("(A)"),
+            Some(("(A) ".to_string(), 4))
+        );
+        assert_eq!(GroupBy::Priority.prefill("No Priority"), None);
+        let owner = GroupBy::Tag("owner".to_string());
+        assert_eq!(
+            owner.prefill("alice"),
+            Some((" owner:alice".to_string(), 0))
+        );
+        assert_eq!(owner.prefill("No owner"), None);
+        assert_eq!(GroupBy::None.prefill("All"), None);
+    }
+
+    #[test]
+    fn test_next_cycles_through_tag_keys() {
+        let todos = items(&[
+            "A owner:alice due:2026-11-01 rec:1w",
+            "B area:ops t:2026-10-01",
+        ]);
+        let mut seen = vec![GroupBy::Project];
+        for _ in 0..6 {
+            seen.push(seen.last().unwrap().next(&todos));
+        }
+        assert_eq!(
+            seen,
+            [
+                GroupBy::Project,
+                GroupBy::Context,
+                GroupBy::Priority,
+                GroupBy::Tag("area".to_string()),
+                GroupBy::Tag("owner".to_string()),
+                GroupBy::None,
+                GroupBy::Project,
+            ]
+        );
+        // A tag no longer present moves on to the flat list
+        assert_eq!(GroupBy::Tag("gone".to_string()).next(&todos), GroupBy::None);
+    }
+
+    #[test]
+    fn test_parse_and_display_roundtrip() {
+        for group_by in [
+            GroupBy::Project,
+            GroupBy::Context,
+            GroupBy::Priority,
+            GroupBy::Tag("owner".to_string()),
+            GroupBy::None,
+        ] {
+            assert_eq!(group_by.to_string().parse::<GroupBy>(), Ok(group_by));
+        }
+        assert!("tag:".parse::<GroupBy>().is_err());
+        assert!("size".parse::<GroupBy>().is_err());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir_path = dir.path().to_str().unwrap();
+        assert!(load(dir_path).is_empty());
+
+        let mut groupings = HashMap::new();
+        groupings.insert(ViewMode::Todo, GroupBy::Context);
+        groupings.insert(ViewMode::Waiting, GroupBy::Tag("owner".to_string()));
+        save(dir_path, &groupings).unwrap();
+        assert_eq!(load(dir_path), groupings);
+
+        fs::write(
+            settings_path(dir_path),
+            r#"{"todo": "bogus", "inbox": "priority"}"#,
+        )
+        .unwrap();
+        let loaded = load(dir_path);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[&ViewMode::Inbox], GroupBy::Priority);
+    }
+}
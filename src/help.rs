@@ -1,13 +1,83 @@
+use crate::app_state::ViewMode;
+use crate::keymap::{Action, Keymap, display_keys};
+
+/// Which keys a help row shows, looked up in the active keymap.
+pub enum HelpKeys {
+    /// The actions' keys run together (`hjkl`)
+    Concat(&'static [Action]),
+    /// The actions' keys separated by `/` (`H/L`)
+    Each(&'static [Action]),
+    /// The keys the actions' sequences start with (`s` for `st`, `sr`, …)
+    Prefix(&'static [Action]),
+}
+
+impl HelpKeys {
+    /// The keys as shown, empty when none of the actions is bound.
+    fn render(&self, keymap: &Keymap) -> String {
+        match self {
+            Self::Concat(actions) => actions.iter().map(|a| keymap.display(*a)).collect(),
+            Self::Each(actions) => actions
+                .iter()
+                .map(|a| keymap.display(*a))
+                .filter(|k| !k.is_empty())
+                .collect::<Vec<_>>()
+                .join("/"),
+            Self::Prefix(actions) => {
+                let bound: Vec<_> = actions.iter().filter_map(|a| keymap.keys(*a)).collect();
+                let Some(first) = bound.first() else {
+                    return String::new();
+                };
+                let shared = (0..first.len())
+                    .take_while(|i| bound.iter().all(|k| k.len() > i + 1 && k[*i] == first[*i]))
+                    .count();
+                display_keys(&first[..shared])
+            }
+        }
+    }
+}
+
+const SEND_ACTIONS: &[Action] = &[
+    Action::SendTo(ViewMode::Todo),
+    Action::SendTo(ViewMode::Ref),
+    Action::SendTo(ViewMode::Inbox),
+    Action::SendTo(ViewMode::Someday),
+    Action::SendTo(ViewMode::Waiting),
+];
+
+const PRIORITY_ACTIONS: &[Action] = &[
+    Action::Priority('A'),
+    Action::Priority('B'),
+    Action::Priority('C'),
+    Action::Priority('D'),
+    Action::Priority('E'),
+    Action::ClearPriority,
+];
+
+const COLUMN_ACTIONS: &[Action] = &[
+    Action::HideColumn,
+    Action::PinColumn,
+    Action::UnhideColumns,
+    Action::CycleColumnSort,
+];
+
+const CLAUDE_ACTIONS: &[Action] = &[
+    Action::ClaudeSendPlan,
+    Action::ClaudeSendImplement,
+    Action::ClaudeGetPlans,
+    Action::ClaudeLaunchPlan,
+    Action::ClaudeLaunchImplement,
+];
+
 #[allow(clippy::struct_excessive_bools)]
 pub struct HelpEntry {
-    pub key: &'static str,
+    pub keys: HelpKeys,
     pub desc: &'static str,
     pub indent: bool,
     pub todo_only: bool,
     pub waiting_too: bool,
     pub requires_claude: bool,
     pub footer: Option<&'static str>,
-    pub footer_key: Option<&'static str>,
+    pub footer_key: Option<HelpKeys>,
 }
 
 impl HelpEntry {
@@ -17,9 +87,16 @@ impl HelpEntry {
     }
 }
 
+/// A row of the help overlay with its keys filled in.
+pub struct HelpLine {
+    pub key: String,
+    pub desc: &'static str,
+    pub indent: bool,
+}
+
 pub const HELP_ENTRIES: &[HelpEntry] = &[
     HelpEntry {
-        key: "hjkl",
+        keys: HelpKeys::Concat(&[Action::Left, Action::Down, Action::Up, Action::Right]),
         desc: "Navigate between columns and todos",
         indent: false,
         todo_only: false,
@@ -29,17 +106,17 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "Tab",
+        keys: HelpKeys::Each(&[Action::NextMode]),
         desc: "Next mode",
         indent: false,
         todo_only: false,
         waiting_too: false,
         requires_claude: false,
         footer: Some("Mode"),
-        footer_key: Some("Tab/S-Tab"),
+        footer_key: Some(HelpKeys::Each(&[Action::NextMode, Action::PrevMode])),
     },
     HelpEntry {
-        key: "S-Tab",
+        keys: HelpKeys::Each(&[Action::PrevMode]),
         desc: "Previous mode",
         indent: false,
         todo_only: false,
@@ -49,17 +126,147 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "x",
-        desc: "Complete selected todo",
+        keys: HelpKeys::Each(&[Action::MoveColumnLeft, Action::MoveColumnRight]),
+        desc: "Move current column left/right (saved; switches to manual order)",
         indent: false,
-        todo_only: true,
-        waiting_too: true,
+        todo_only: false,
+        waiting_too: false,
+        requires_claude: false,
+        footer: None,
+        footer_key: None,
+    },
+    HelpEntry {
+        keys: HelpKeys::Prefix(COLUMN_ACTIONS),
+        desc: "Column submenu",
+        indent: false,
+        todo_only: false,
+        waiting_too: false,
+        requires_claude: false,
+        footer: None,
+        footer_key: None,
+    },
+    HelpEntry {
+        keys: HelpKeys::Each(&[Action::HideColumn]),
+        desc: "Hide current column",
+        indent: true,
+        todo_only: false,
+        waiting_too: false,
+        requires_claude: false,
+        footer: None,
+        footer_key: None,
+    },
+    HelpEntry {
+        keys: HelpKeys::Each(&[Action::PinColumn]),
+        desc: "Pin/unpin current column (pinned columns come first)",
+        indent: true,
+        todo_only: false,
+        waiting_too: false,
+        requires_claude: false,
+        footer: None,
+        footer_key: None,
+    },
+    HelpEntry {
+        keys: HelpKeys::Each(&[Action::UnhideColumns]),
+        desc: "Show all hidden columns",
+        indent: true,
+        todo_only: false,
+        waiting_too: false,
+        requires_claude: false,
+        footer: None,
+        footer_key: None,
+    },
+    HelpEntry {
+        keys: HelpKeys::Each(&[Action::CycleColumnSort]),
+        desc: "Cycle column order: name, item count, recently touched, manual",
+        indent: true,
+        todo_only: false,
+        waiting_too: false,
+        requires_claude: false,
+        footer: None,
+        footer_key: None,
+    },
+    HelpEntry {
+        keys: HelpKeys::Each(&[Action::CycleGrouping]),
+        desc: "Cycle column grouping: project, context, priority, each tag key, none",
+        indent: false,
+        todo_only: false,
+        waiting_too: false,
+        requires_claude: false,
+        footer: None,
+        footer_key: None,
+    },
+    HelpEntry {
+        keys: HelpKeys::Each(&[Action::ToggleAgenda]),
+        desc: "Toggle agenda view (columns by due date across all modes)",
+        indent: false,
+        todo_only: false,
+        waiting_too: false,
+        requires_claude: false,
+        footer: None,
+        footer_key: None,
+    },
+    HelpEntry {
+        keys: HelpKeys::Each(&[Action::ToggleDone]),
+        desc: "Toggle done archive (done.txt by completion date, read-only)",
+        indent: false,
+        todo_only: false,
+        waiting_too: false,
+        requires_claude: false,
+        footer: None,
+        footer_key: None,
+    },
+    HelpEntry {
+        keys: HelpKeys::Each(&[Action::Uncomplete]),
+        desc: "In the done archive: restore item to the mode it was completed in",
+        indent: true,
+        todo_only: false,
+        waiting_too: false,
+        requires_claude: false,
+        footer: None,
+        footer_key: None,
+    },
+    HelpEntry {
+        keys: HelpKeys::Each(&[Action::Add]),
+        desc: "Add item to current mode (prefilled with column's +project)",
+        indent: false,
+        todo_only: false,
+        waiting_too: false,
+        requires_claude: false,
+        footer: Some("Add"),
+        footer_key: Some(HelpKeys::Each(&[Action::Add, Action::AddBlank])),
+    },
+    HelpEntry {
+        keys: HelpKeys::Each(&[Action::AddBlank]),
+        desc: "Add item to current mode (blank)",
+        indent: false,
+        todo_only: false,
+        waiting_too: false,
+        requires_claude: false,
+        footer: None,
+        footer_key: None,
+    },
+    HelpEntry {
+        keys: HelpKeys::Each(&[Action::Edit]),
+        desc: "Edit raw todo.txt line of selected todo (id: must be kept)",
+        indent: false,
+        todo_only: false,
+        waiting_too: false,
+        requires_claude: false,
+        footer: Some("Edit"),
+        footer_key: None,
+    },
+    HelpEntry {
+        keys: HelpKeys::Each(&[Action::Complete]),
+        desc: "Complete selected todo (archived to done.txt with from:<mode>)",
+        indent: false,
+        todo_only: false,
+        waiting_too: false,
         requires_claude: false,
         footer: Some("Done"),
         footer_key: None,
     },
     HelpEntry {
-        key: "dd",
+        keys: HelpKeys::Each(&[Action::Delete]),
         desc: "Delete selected todo (and its detail .md file)",
         indent: false,
         todo_only: false,
@@ -69,7 +276,27 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "o",
+        keys: HelpKeys::Each(&[Action::Undo]),
+        desc: "Undo last file change (also `torudo undo`)",
+        indent: false,
+        todo_only: false,
+        waiting_too: false,
+        requires_claude: false,
+        footer: Some("Undo"),
+        footer_key: Some(HelpKeys::Each(&[Action::Undo, Action::Redo])),
+    },
+    HelpEntry {
+        keys: HelpKeys::Each(&[Action::Redo]),
+        desc: "Redo last undone change",
+        indent: false,
+        todo_only: false,
+        waiting_too: false,
+        requires_claude: false,
+        footer: None,
+        footer_key: None,
+    },
+    HelpEntry {
+        keys: HelpKeys::Each(&[Action::OpenUrls]),
         desc: "Open URLs in selected todo",
         indent: false,
         todo_only: false,
@@ -79,7 +306,7 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "f",
+        keys: HelpKeys::Each(&[Action::Hint]),
         desc: "Jump to visible todo by hint label (a-z, aa-zz)",
         indent: false,
         todo_only: false,
@@ -89,7 +316,37 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "s",
+        keys: HelpKeys::Each(&[Action::Search]),
+        desc: "Fuzzy search cards (description, +project, @context, key:value)",
+        indent: false,
+        todo_only: false,
+        waiting_too: false,
+        requires_claude: false,
+        footer: Some("Search"),
+        footer_key: None,
+    },
+    HelpEntry {
+        keys: HelpKeys::Each(&[Action::NextMatch, Action::PrevMatch]),
+        desc: "Next/previous search match (Esc clears the search)",
+        indent: true,
+        todo_only: false,
+        waiting_too: false,
+        requires_claude: false,
+        footer: None,
+        footer_key: None,
+    },
+    HelpEntry {
+        keys: HelpKeys::Each(&[Action::Filter]),
+        desc: "Filter query, e.g. @phone pri:A-B due<2026-11-01 -+someday has:md (Esc clears)",
+        indent: false,
+        todo_only: false,
+        waiting_too: false,
+        requires_claude: false,
+        footer: None,
+        footer_key: None,
+    },
+    HelpEntry {
+        keys: HelpKeys::Prefix(SEND_ACTIONS),
         desc: "Send to... submenu",
         indent: false,
         todo_only: false,
@@ -99,8 +356,8 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "st",
-        desc: "Send to todo.txt",
+        keys: HelpKeys::Each(&[Action::SendTo(ViewMode::Todo)]),
+        desc: "Send to Todo",
         indent: true,
         todo_only: false,
         waiting_too: false,
@@ -109,8 +366,8 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "sr",
-        desc: "Send to ref.txt",
+        keys: HelpKeys::Each(&[Action::SendTo(ViewMode::Ref)]),
+        desc: "Send to Ref",
         indent: true,
         todo_only: false,
         waiting_too: false,
@@ -119,8 +376,8 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "si",
-        desc: "Send to inbox.txt",
+        keys: HelpKeys::Each(&[Action::SendTo(ViewMode::Inbox)]),
+        desc: "Send to Inbox",
         indent: true,
         todo_only: false,
         waiting_too: false,
@@ -129,8 +386,8 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "ss",
-        desc: "Send to someday.txt",
+        keys: HelpKeys::Each(&[Action::SendTo(ViewMode::Someday)]),
+        desc: "Send to Someday",
         indent: true,
         todo_only: false,
         waiting_too: false,
@@ -139,8 +396,8 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "sw",
-        desc: "Send to waiting.txt",
+        keys: HelpKeys::Each(&[Action::SendTo(ViewMode::Waiting)]),
+        desc: "Send to Waiting",
         indent: true,
         todo_only: false,
         waiting_too: false,
@@ -149,7 +406,7 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "p",
+        keys: HelpKeys::Prefix(PRIORITY_ACTIONS),
         desc: "Set/clear priority submenu",
         indent: false,
         todo_only: false,
@@ -159,7 +416,7 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "pa",
+        keys: HelpKeys::Each(&[Action::Priority('A')]),
         desc: "Set priority (A)",
         indent: true,
         todo_only: false,
@@ -169,7 +426,7 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "pb",
+        keys: HelpKeys::Each(&[Action::Priority('B')]),
         desc: "Set priority (B)",
         indent: true,
         todo_only: false,
@@ -179,7 +436,7 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "pc",
+        keys: HelpKeys::Each(&[Action::Priority('C')]),
         desc: "Set priority (C)",
         indent: true,
         todo_only: false,
@@ -189,7 +446,7 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "pd",
+        keys: HelpKeys::Each(&[Action::Priority('D')]),
         desc: "Set priority (D)",
         indent: true,
         todo_only: false,
@@ -199,7 +456,7 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "pe",
+        keys: HelpKeys::Each(&[Action::Priority('E')]),
         desc: "Set priority (E)",
         indent: true,
         todo_only: false,
@@ -209,7 +466,7 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "px",
+        keys: HelpKeys::Each(&[Action::ClearPriority]),
         desc: "Clear priority",
         indent: true,
         todo_only: false,
@@ -219,7 +476,7 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "c",
+        keys: HelpKeys::Prefix(CLAUDE_ACTIONS),
         desc: "Claude submenu (requires crmux or claude CLI)",
         indent: false,
         todo_only: true,
@@ -229,7 +486,7 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "csp",
+        keys: HelpKeys::Each(&[Action::ClaudeSendPlan]),
         desc: "Send plan prompt to project's idle crmux session (>= 0.10.0)",
         indent: true,
         todo_only: true,
@@ -239,7 +496,7 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "csi",
+        keys: HelpKeys::Each(&[Action::ClaudeSendImplement]),
         desc: "Send implement prompt to project's idle crmux session (>= 0.10.0)",
         indent: true,
         todo_only: true,
@@ -249,7 +506,7 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "cgp",
+        keys: HelpKeys::Each(&[Action::ClaudeGetPlans]),
         desc: "Get plans and import via crmux (>= 0.11.0)",
         indent: true,
         todo_only: true,
@@ -259,7 +516,7 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "clp",
+        keys: HelpKeys::Each(&[Action::ClaudeLaunchPlan]),
         desc: "Launch claude plan in tmux window (requires cwd in frontmatter)",
         indent: true,
         todo_only: true,
@@ -269,7 +526,7 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "cli",
+        keys: HelpKeys::Each(&[Action::ClaudeLaunchImplement]),
         desc: "Launch claude implement in tmux window (requires cwd in frontmatter)",
         indent: true,
         todo_only: true,
@@ -279,7 +536,7 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "t",
+        keys: HelpKeys::Each(&[Action::Templates]),
         desc: "Insert template from templates/*.md (j/k move, Enter insert, Esc/q cancel)",
         indent: false,
         todo_only: true,
@@ -289,7 +546,7 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "?",
+        keys: HelpKeys::Each(&[Action::Help]),
         desc: "Toggle help",
         indent: false,
         todo_only: false,
@@ -299,7 +556,7 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
         footer_key: None,
     },
     HelpEntry {
-        key: "q",
+        keys: HelpKeys::Each(&[Action::Quit]),
         desc: "Quit",
         indent: false,
         todo_only: false,
@@ -310,38 +567,59 @@ pub const HELP_ENTRIES: &[HelpEntry] = &[
     },
 ];
 
+/// Help rows for the current mode, skipping actions that are unbound.
 pub fn visible_entries(
+    keymap: &Keymap,
     is_todo: bool,
     is_waiting: bool,
     has_claude: bool,
-) -> Vec<&'static HelpEntry> {
-    HELP_ENTRIES
-        .iter()
-        .filter(|e| e.is_visible(is_todo, is_waiting, has_claude))
+) -> Vec<HelpLine> {
+    help_lines(keymap)
+        .into_iter()
+        .zip(HELP_ENTRIES)
+        .filter(|(line, e)| !line.key.is_empty() && e.is_visible(is_todo, is_waiting, has_claude))
+        .map(|(line, _)| line)
         .collect()
 }
 
 pub fn footer_entries(
+    keymap: &Keymap,
     is_todo: bool,
     is_waiting: bool,
     has_claude: bool,
-) -> Vec<(&'static str, &'static str)> {
+) -> Vec<(String, &'static str)> {
+    HELP_ENTRIES
+        .iter()
+        .filter(|e| e.is_visible(is_todo, is_waiting, has_claude))
+        .filter_map(|e| {
+            let key = e.footer_key.as_ref().unwrap_or(&e.keys).render(keymap);
+            Some((key, e.footer?)).filter(|(key, _)| !key.is_empty())
+        })
+        .collect()
+}
+
+fn help_lines(keymap: &Keymap) -> Vec<HelpLine> {
     HELP_ENTRIES
         .iter()
-        .filter(|e| e.footer.is_some() && e.is_visible(is_todo, is_waiting, has_claude))
-        .map(|e| (e.footer_key.unwrap_or(e.key), e.footer.unwrap()))
+        .map(|e| HelpLine {
+            key: e.keys.render(keymap),
+            desc: e.desc,
+            indent: e.indent,
+        })
         .collect()
 }
 
-pub fn cli_help_text() -> String {
-    let max_key_width = HELP_ENTRIES
+pub fn cli_help_text(keymap: &Keymap) -> String {
+    let lines = help_lines(keymap);
+    let max_key_width = lines
         .iter()
         .map(|e| e.key.len() + if e.indent { 2 } else { 0 })
         .max()
         .unwrap_or(0);
 
-    let lines: Vec<String> = HELP_ENTRIES
+    let lines: Vec<String> = lines
         .iter()
+        .filter(|e| !e.key.is_empty())
         .map(|e| {
             let prefix = if e.indent { "  " } else { "" };
             let padded_key = format!("{prefix}{}", e.key);
@@ -358,10 +636,10 @@ mod tests {
 
     #[test]
     fn test_cli_help_text_contains_all_keys() {
-        let text = cli_help_text();
-        for entry in HELP_ENTRIES {
+        let text = cli_help_text(&Keymap::default());
+        for entry in help_lines(&Keymap::default()) {
             assert!(
-                text.contains(entry.key),
+                text.contains(&entry.key),
                 "Help text should contain key: {}",
                 entry.key
             );
@@ -370,20 +648,21 @@ mod tests {
 
     #[test]
     fn test_footer_entries_contains_short_labels() {
-        let entries = footer_entries(true, false, true);
-        assert!(entries.contains(&("hjkl", "Nav")));
-        assert!(entries.contains(&("x", "Done")));
-        assert!(entries.contains(&("o", "URL")));
-        assert!(entries.contains(&("s", "Send")));
-        assert!(entries.contains(&("Tab/S-Tab", "Mode")));
-        assert!(entries.contains(&("c", "Claude")));
-        assert!(entries.contains(&("?", "Help")));
-        assert!(entries.contains(&("q", "Quit")));
+        let entries = footer_entries(&Keymap::default(), true, false, true);
+        assert!(entries.contains(&("hjkl".to_string(), "Nav")));
+        assert!(entries.contains(&("x".to_string(), "Done")));
+        assert!(entries.contains(&("o".to_string(), "URL")));
+        assert!(entries.contains(&("a/A".to_string(), "Add")));
+        assert!(entries.contains(&("s".to_string(), "Send")));
+        assert!(entries.contains(&("Tab/S-Tab".to_string(), "Mode")));
+        assert!(entries.contains(&("c".to_string(), "Claude")));
+        assert!(entries.contains(&("?".to_string(), "Help")));
+        assert!(entries.contains(&("q".to_string(), "Quit")));
     }
 
     #[test]
     fn test_footer_entries_excludes_stab_as_separate_entry() {
-        let entries = footer_entries(true, false, true);
+        let entries = footer_entries(&Keymap::default(), true, false, true);
         // S-Tab should not appear as its own footer entry (merged into Tab/S-Tab)
         assert!(!entries.iter().any(|(k, _)| *k == "S-Tab"));
         // Tab also should not appear as a standalone key since it's merged
@@ -393,15 +672,16 @@ mod tests {
     #[test]
     fn test_help_entries_real_keys_preserved() {
         // Ensure real key names for Tab and S-Tab remain intact for the help overlay
-        assert!(HELP_ENTRIES.iter().any(|e| e.key == "Tab"));
-        assert!(HELP_ENTRIES.iter().any(|e| e.key == "S-Tab"));
+        let lines = help_lines(&Keymap::default());
+        assert!(lines.iter().any(|e| e.key == "Tab"));
+        assert!(lines.iter().any(|e| e.key == "S-Tab"));
     }
 
     #[test]
     fn test_footer_entries_tab_mode_placed_right_after_hjkl() {
         // Tab/S-Tab is also a navigation key, so it should sit adjacent to hjkl
-        let entries = footer_entries(true, false, true);
-        let keys: Vec<&str> = entries.iter().map(|(k, _)| *k).collect();
+        let entries = footer_entries(&Keymap::default(), true, false, true);
+        let keys: Vec<&str> = entries.iter().map(|(k, _)| k.as_str()).collect();
         let hjkl_pos = keys.iter().position(|k| *k == "hjkl").unwrap();
         let tab_pos = keys.iter().position(|k| *k == "Tab/S-Tab").unwrap();
         assert_eq!(
@@ -414,7 +694,7 @@ mod tests {
     #[test]
     fn test_x_entry_visible_in_waiting() {
         // is_todo=false, is_waiting=true, has_claude=false
-        let entries = visible_entries(false, true, false);
+        let entries = visible_entries(&Keymap::default(), false, true, false);
         assert!(
             entries.iter().any(|e| e.key == "x"),
             "x entry should be visible in Waiting mode"
@@ -422,19 +702,19 @@ mod tests {
     }
 
     #[test]
-    fn test_x_entry_hidden_in_inbox() {
+    fn test_x_entry_visible_in_inbox() {
         // is_todo=false, is_waiting=false (e.g., Inbox/Ref/Someday)
-        let entries = visible_entries(false, false, false);
+        let entries = visible_entries(&Keymap::default(), false, false, false);
         assert!(
-            !entries.iter().any(|e| e.key == "x"),
-            "x entry should be hidden in non-Todo/Waiting modes"
+            entries.iter().any(|e| e.key == "x"),
+            "x entry should be visible in every mode"
         );
     }
 
     #[test]
     fn test_c_entry_still_todo_only() {
         // c must NOT widen into Waiting even with has_claude=true
-        let entries = visible_entries(false, true, true);
+        let entries = visible_entries(&Keymap::default(), false, true, true);
         assert!(
             !entries.iter().any(|e| e.key == "c"),
             "c entry must stay Todo-only"
@@ -444,7 +724,7 @@ mod tests {
     #[test]
     fn test_f_entry_visible_in_all_modes() {
         for (is_todo, is_waiting) in [(true, false), (false, true), (false, false)] {
-            let entries = visible_entries(is_todo, is_waiting, false);
+            let entries = visible_entries(&Keymap::default(), is_todo, is_waiting, false);
             assert!(
                 entries.iter().any(|e| e.key == "f"),
                 "f (hint jump) should be visible in mode is_todo={is_todo} is_waiting={is_waiting}"
@@ -454,10 +734,42 @@ mod tests {
 
     #[test]
     fn test_f_entry_in_footer() {
-        let entries = footer_entries(false, false, false);
+        let entries = footer_entries(&Keymap::default(), false, false, false);
         assert!(
             entries.iter().any(|(k, desc)| *k == "f" && *desc == "Jump"),
             "f:Jump should appear in footer"
         );
     }
+
+    #[test]
+    fn test_help_follows_remapped_keys() {
+        let overrides = [
+            ("complete", "X"),
+            ("send_to.todo", "mt"),
+            ("send_to.ref", "mr"),
+            ("send_to.inbox", "mi"),
+            ("send_to.someday", "ms"),
+            ("send_to.waiting", "mw"),
+            ("redo", ""),
+        ]
+        .iter()
+        .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+        .collect();
+        let keymap = Keymap::new(&overrides).unwrap();
+        let keys: Vec<String> = visible_entries(&keymap, true, false, false)
+            .into_iter()
+            .map(|e| e.key)
+            .collect();
+        assert!(keys.contains(&"X".to_string()));
+        assert!(keys.contains(&"m".to_string()));
+        assert!(keys.contains(&"mw".to_string()));
+        assert!(!keys.contains(&"x".to_string()));
+        // Unbound actions have no row
+        assert!(!keys.contains(&"C-r".to_string()));
+
+        let footer = footer_entries(&keymap, true, false, false);
+        assert!(footer.contains(&("X".to_string(), "Done")));
+        assert!(footer.contains(&("m".to_string(), "Send")));
+        assert!(footer.contains(&("u".to_string(), "Undo")));
+    }
 }
@@ -0,0 +1,536 @@
+use crate::app_state::ViewMode;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// A key that can be part of a binding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    Char(char),
+    Ctrl(char),
+    Tab,
+    BackTab,
+}
+
+impl Key {
+    /// The bindable key of a terminal event, if it has one.
+    pub const fn from_event(key: &KeyEvent) -> Option<Self> {
+        match key.code {
+            KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Self::Ctrl(c.to_ascii_lowercase()))
+            }
+            KeyCode::Char(c) => Some(Self::Char(c)),
+            KeyCode::Tab => Some(Self::Tab),
+            KeyCode::BackTab => Some(Self::BackTab),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Char(' ') => f.write_str("Space"),
+            Self::Char(c) => write!(f, "{c}"),
+            Self::Ctrl(c) => write!(f, "C-{c}"),
+            Self::Tab => f.write_str("Tab"),
+            Self::BackTab => f.write_str("S-Tab"),
+        }
+    }
+}
+
+/// Parse a binding such as `x`, `dd`, `<C-r>` or `<S-Tab>`. `<lt>` is a
+/// literal `<` and `<Space>` the space bar.
+pub fn parse_keys(s: &str) -> Result<Vec<Key>, String> {
+    let mut keys = Vec::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        let key = if c == '<' {
+            let name: String = chars.by_ref().take_while(|c| *c != '>').collect();
+            match name.as_str() {
+                "Tab" => Key::Tab,
+                "S-Tab" => Key::BackTab,
+                "Space" => Key::Char(' '),
+                "lt" => Key::Char('<'),
+                _ => {
+                    let mut rest = name.strip_prefix("C-").unwrap_or_default().chars();
+                    match (rest.next(), rest.next()) {
+                        (Some(c), None) if c.is_ascii_alphabetic() => {
+                            Key::Ctrl(c.to_ascii_lowercase())
+                        }
+                        _ => return Err(format!("unknown key <{name}>")),
+                    }
+                }
+            }
+        } else if c.is_whitespace() {
+            return Err("spaces must be written as <Space>".to_string());
+        } else {
+            Key::Char(c)
+        };
+        keys.push(key);
+    }
+    if keys.is_empty() {
+        return Err("no keys given".to_string());
+    }
+    Ok(keys)
+}
+
+/// A binding as it is written in `config.toml`.
+pub fn format_keys(keys: &[Key]) -> String {
+    keys.iter()
+        .map(|key| match key {
+            Key::Char('<') => "<lt>".to_string(),
+            Key::Char(c) if *c != ' ' => c.to_string(),
+            key => format!("<{key}>"),
+        })
+        .collect()
+}
+
+/// A binding as the help and submenus show it: lone special keys go
+/// without brackets (`C-r`, `S-Tab`).
+pub fn display_keys(keys: &[Key]) -> String {
+    match keys {
+        [key] => key.to_string(),
+        keys => format_keys(keys),
+    }
+}
+
+/// Everything a key binding can do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Left,
+    Down,
+    Up,
+    Right,
+    NextMode,
+    PrevMode,
+    Undo,
+    Redo,
+    Complete,
+    Delete,
+    SendTo(ViewMode),
+    Priority(char),
+    ClearPriority,
+    HideColumn,
+    PinColumn,
+    UnhideColumns,
+    CycleColumnSort,
+    MoveColumnLeft,
+    MoveColumnRight,
+    CycleGrouping,
+    ToggleAgenda,
+    ToggleDone,
+    Uncomplete,
+    Add,
+    AddBlank,
+    Edit,
+    OpenUrls,
+    Hint,
+    Search,
+    NextMatch,
+    PrevMatch,
+    Filter,
+    Templates,
+    ClaudeSendPlan,
+    ClaudeSendImplement,
+    ClaudeGetPlans,
+    ClaudeLaunchPlan,
+    ClaudeLaunchImplement,
+    Help,
+    Quit,
+}
+
+/// Every action with its name in `[keys]` and its default binding, in the
+/// order submenus list them.
+pub const ACTIONS: &[(Action, &str, &str)] = &[
+    (Action::Left, "left", "h"),
+    (Action::Down, "down", "j"),
+    (Action::Up, "up", "k"),
+    (Action::Right, "right", "l"),
+    (Action::NextMode, "next_mode", "<Tab>"),
+    (Action::PrevMode, "prev_mode", "<S-Tab>"),
+    (Action::Undo, "undo", "u"),
+    (Action::Redo, "redo", "<C-r>"),
+    (Action::Complete, "complete", "x"),
+    (Action::Delete, "delete", "dd"),
+    (Action::SendTo(ViewMode::Inbox), "send_to.inbox", "si"),
+    (Action::SendTo(ViewMode::Todo), "send_to.todo", "st"),
+    (Action::SendTo(ViewMode::Waiting), "send_to.waiting", "sw"),
+    (Action::SendTo(ViewMode::Ref), "send_to.ref", "sr"),
+    (Action::SendTo(ViewMode::Someday), "send_to.someday", "ss"),
+    (Action::Priority('A'), "priority.a", "pa"),
+    (Action::Priority('B'), "priority.b", "pb"),
+    (Action::Priority('C'), "priority.c", "pc"),
+    (Action::Priority('D'), "priority.d", "pd"),
+    (Action::Priority('E'), "priority.e", "pe"),
+    (Action::ClearPriority, "priority.clear", "px"),
+    (Action::HideColumn, "column.hide", "zh"),
+    (Action::PinColumn, "column.pin", "zp"),
+    (Action::UnhideColumns, "column.unhide", "zu"),
+    (Action::CycleColumnSort, "column.sort", "zs"),
+    (Action::MoveColumnLeft, "column.move_left", "H"),
+    (Action::MoveColumnRight, "column.move_right", "L"),
+    (Action::CycleGrouping, "grouping", "g"),
+    (Action::ToggleAgenda, "agenda", "v"),
+    (Action::ToggleDone, "done", "D"),
+    (Action::Uncomplete, "uncomplete", "U"),
+    (Action::Add, "add", "a"),
+    (Action::AddBlank, "add_blank", "A"),
+    (Action::Edit, "edit", "e"),
+    (Action::OpenUrls, "open_urls", "o"),
+    (Action::Hint, "hint", "f"),
+    (Action::Search, "search", "/"),
+    (Action::NextMatch, "next_match", "n"),
+    (Action::PrevMatch, "prev_match", "N"),
+    (Action::Filter, "filter", "F"),
+    (Action::Templates, "templates", "t"),
+    (Action::ClaudeSendPlan, "claude.send.plan", "csp"),
+    (Action::ClaudeSendImplement, "claude.send.implement", "csi"),
+    (Action::ClaudeGetPlans, "claude.get.plans", "cgp"),
+    (Action::ClaudeLaunchPlan, "claude.launch.plan", "clp"),
+    (
+        Action::ClaudeLaunchImplement,
+        "claude.launch.implement",
+        "cli",
+    ),
+    (Action::Help, "help", "?"),
+    (Action::Quit, "quit", "q"),
+];
+
+impl Action {
+    pub fn name(self) -> &'static str {
+        ACTIONS
+            .iter()
+            .find(|(action, _, _)| *action == self)
+            .map_or("", |(_, name, _)| name)
+    }
+
+    /// Short label for submenus.
+    pub fn label(self) -> String {
+        match self {
+            Self::SendTo(mode) => mode.label().to_string(),
+            Self::Priority(p) => format!("({p})"),
+            Self::ClearPriority => "Clear".to_string(),
+            Self::HideColumn => "Hide column".to_string(),
+            Self::PinColumn => "Pin/unpin".to_string(),
+            Self::UnhideColumns => "Show hidden".to_string(),
+            Self::CycleColumnSort => "Sort".to_string(),
+            Self::ClaudeSendPlan | Self::ClaudeLaunchPlan => "Plan".to_string(),
+            Self::ClaudeSendImplement | Self::ClaudeLaunchImplement => "Impl".to_string(),
+            Self::ClaudeGetPlans => "Plans".to_string(),
+            _ => {
+                let name = self.name().rsplit('.').next().unwrap_or_default();
+                let mut label = name.replace('_', " ");
+                if let Some(first) = label.get_mut(0..1) {
+                    first.make_ascii_uppercase();
+                }
+                label
+            }
+        }
+    }
+}
+
+/// Label for a submenu entry that leads to several `actions`, from the
+/// part of their names they share: `claude.send.plan` and
+/// `claude.send.implement` give "Send".
+pub fn group_label(actions: &[Action]) -> String {
+    let parents: Vec<Vec<&str>> = actions
+        .iter()
+        .map(|a| {
+            let mut parts: Vec<&str> = a.name().split('.').collect();
+            parts.pop();
+            parts
+        })
+        .collect();
+    let shared = parents.first().map_or(0, |first| {
+        (0..first.len())
+            .take_while(|i| parents.iter().all(|p| p.get(*i) == first.get(*i)))
+            .count()
+    });
+    let Some(last) = parents
+        .first()
+        .and_then(|p| shared.checked_sub(1).map(|i| p[i]))
+    else {
+        return "More".to_string();
+    };
+    let mut label = last.replace('_', " ");
+    if let Some(first) = label.get_mut(0..1) {
+        first.make_ascii_uppercase();
+    }
+    label
+}
+
+/// What the keys typed so far amount to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lookup {
+    Action(Action),
+    /// The start of one or more longer bindings
+    Prefix,
+    None,
+}
+
+/// Key bindings in effect: the defaults from `ACTIONS` with the `[keys]`
+/// overrides applied. Unbound actions are left out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<Key>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&BTreeMap::new()).expect("default bindings are valid")
+    }
+}
+
+impl Keymap {
+    /// Apply `overrides` (action name to binding, `""` to unbind). Unknown
+    /// actions, malformed bindings, two actions on the same keys and
+    /// bindings hidden behind a shorter one are errors.
+    pub fn new(overrides: &BTreeMap<String, String>) -> Result<Self, String> {
+        if let Some(name) = overrides
+            .keys()
+            .find(|name| !ACTIONS.iter().any(|(_, n, _)| n == name))
+        {
+            return Err(format!(
+                "keys.{name} is not an action (`torudo config show` lists them all)"
+            ));
+        }
+        let mut bindings: Vec<(Action, Vec<Key>)> = Vec::with_capacity(ACTIONS.len());
+        for (action, name, default) in ACTIONS {
+            let value = overrides.get(*name).map_or(*default, String::as_str);
+            if value.is_empty() {
+                continue;
+            }
+            let keys = parse_keys(value).map_err(|e| format!("keys.{name}: {e}"))?;
+            if let Some((other, other_keys)) = bindings
+                .iter()
+                .find(|(_, k)| k.starts_with(&keys) || keys.starts_with(k))
+            {
+                let (short, long) = if other_keys.len() <= keys.len() {
+                    ((other.name(), other_keys), (*name, &keys))
+                } else {
+                    ((*name, &keys), (other.name(), other_keys))
+                };
+                return Err(if short.1 == long.1 {
+                    format!(
+                        "keys.{} and keys.{} are both {}",
+                        other.name(),
+                        name,
+                        format_keys(&keys)
+                    )
+                } else {
+                    format!(
+                        "keys.{} ({}) can never be typed because keys.{} is {}",
+                        long.0,
+                        format_keys(long.1),
+                        short.0,
+                        format_keys(short.1)
+                    )
+                });
+            }
+            bindings.push((*action, keys));
+        }
+        Ok(Self { bindings })
+    }
+
+    pub fn keys(&self, action: Action) -> Option<&[Key]> {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, keys)| keys.as_slice())
+    }
+
+    /// `keys` of `action` as written in the help, empty when unbound.
+    pub fn display(&self, action: Action) -> String {
+        self.keys(action).map(display_keys).unwrap_or_default()
+    }
+
+    pub fn lookup(&self, typed: &[Key]) -> Lookup {
+        let mut prefix = false;
+        for (action, keys) in &self.bindings {
+            if keys == typed {
+                return Lookup::Action(*action);
+            }
+            prefix |= keys.starts_with(typed);
+        }
+        if prefix { Lookup::Prefix } else { Lookup::None }
+    }
+
+    /// The keys that can follow `prefix`, each with the actions it leads
+    /// to, in binding order.
+    pub fn next_keys(&self, prefix: &[Key]) -> Vec<(Key, Vec<Action>)> {
+        let mut next: Vec<(Key, Vec<Action>)> = Vec::new();
+        for (action, keys) in &self.bindings {
+            let Some(key) = keys.strip_prefix(prefix).and_then(|rest| rest.first()) else {
+                continue;
+            };
+            match next.iter_mut().find(|(k, _)| k == key) {
+                Some((_, actions)) => actions.push(*action),
+                None => next.push((*key, vec![*action])),
+            }
+        }
+        next
+    }
+
+    /// Every action's binding by name, `""` for unbound ones, as
+    /// `torudo config show` prints them.
+    pub fn to_names(&self) -> BTreeMap<String, String> {
+        ACTIONS
+            .iter()
+            .map(|(action, name, _)| {
+                let keys = self.keys(*action).map(format_keys).unwrap_or_default();
+                ((*name).to_string(), keys)
+            })
+            .collect()
+    }
+}
+
+static ACTIVE: OnceLock<Keymap> = OnceLock::new();
+
+/// The keymap of the loaded configuration.
+pub fn active() -> &'static Keymap {
+    ACTIVE.get_or_init(|| crate::config::get().keymap().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keymap(overrides: &[(&str, &str)]) -> Result<Keymap, String> {
+        Keymap::new(
+            &overrides
+                .iter()
+                .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_parse_and_format_keys() {
+        assert_eq!(parse_keys("dd"), Ok(vec![Key::Char('d'), Key::Char('d')]));
+        assert_eq!(parse_keys("<C-R>"), Ok(vec![Key::Ctrl('r')]));
+        assert_eq!(
+            parse_keys("<Tab><S-Tab><lt><Space>"),
+            Ok(vec![Key::Tab, Key::BackTab, Key::Char('<'), Key::Char(' ')])
+        );
+        assert!(parse_keys("").is_err());
+        assert!(parse_keys("a b").is_err());
+        assert_eq!(parse_keys("<Esc>"), Err("unknown key <Esc>".to_string()));
+
+        for binding in ["x", "csp", "<C-r>", "<S-Tab>", "g<Tab>", "<Space>", "z<lt>"] {
+            assert_eq!(format_keys(&parse_keys(binding).unwrap()), binding);
+        }
+        // The help shows lone special keys without brackets
+        assert_eq!(display_keys(&[Key::Ctrl('r')]), "C-r");
+        assert_eq!(display_keys(&[Key::BackTab]), "S-Tab");
+        assert_eq!(display_keys(&[Key::Char('g'), Key::Tab]), "g<Tab>");
+    }
+
+    #[test]
+    fn test_default_lookup() {
+        let keymap = Keymap::default();
+        let keys = |s| parse_keys(s).unwrap();
+        assert_eq!(keymap.lookup(&keys("x")), Lookup::Action(Action::Complete));
+        assert_eq!(keymap.lookup(&keys("c")), Lookup::Prefix);
+        assert_eq!(keymap.lookup(&keys("cs")), Lookup::Prefix);
+        assert_eq!(
+            keymap.lookup(&keys("csp")),
+            Lookup::Action(Action::ClaudeSendPlan)
+        );
+        assert_eq!(
+            keymap.lookup(&keys("sw")),
+            Lookup::Action(Action::SendTo(ViewMode::Waiting))
+        );
+        assert_eq!(keymap.lookup(&keys("<C-r>")), Lookup::Action(Action::Redo));
+        assert_eq!(keymap.lookup(&keys("dx")), Lookup::None);
+        assert_eq!(keymap.lookup(&keys("Z")), Lookup::None);
+    }
+
+    #[test]
+    fn test_overrides_rebind_and_unbind() {
+        let keymap = keymap(&[
+            ("complete", "X"),
+            ("delete", "x"),
+            ("send_to.waiting", "<Space>w"),
+            ("claude.launch.plan", ""),
+        ])
+        .unwrap();
+        let keys = |s| parse_keys(s).unwrap();
+        assert_eq!(keymap.lookup(&keys("X")), Lookup::Action(Action::Complete));
+        assert_eq!(keymap.lookup(&keys("x")), Lookup::Action(Action::Delete));
+        assert_eq!(keymap.lookup(&keys("d")), Lookup::None);
+        assert_eq!(
+            keymap.lookup(&keys("<Space>w")),
+            Lookup::Action(Action::SendTo(ViewMode::Waiting))
+        );
+        assert_eq!(keymap.keys(Action::ClaudeLaunchPlan), None);
+        assert_eq!(keymap.display(Action::ClaudeLaunchPlan), "");
+        assert_eq!(keymap.to_names()["claude.launch.plan"], "");
+        assert_eq!(keymap.to_names()["send_to.waiting"], "<Space>w");
+    }
+
+    #[test]
+    fn test_invalid_overrides() {
+        assert!(
+            keymap(&[("fly", "y")])
+                .unwrap_err()
+                .starts_with("keys.fly is not an action")
+        );
+        assert_eq!(
+            keymap(&[("quit", "<Esc>")]).unwrap_err(),
+            "keys.quit: unknown key <Esc>"
+        );
+        assert_eq!(
+            keymap(&[("quit", "x")]).unwrap_err(),
+            "keys.complete and keys.quit are both x"
+        );
+        // A binding that starts another one hides it
+        assert_eq!(
+            keymap(&[("quit", "s")]).unwrap_err(),
+            "keys.send_to.inbox (si) can never be typed because keys.quit is s"
+        );
+        assert_eq!(
+            keymap(&[("quit", "uu")]).unwrap_err(),
+            "keys.quit (uu) can never be typed because keys.undo is u"
+        );
+    }
+
+    #[test]
+    fn test_next_keys_groups_by_following_key() {
+        let keymap = Keymap::default();
+        let next = keymap.next_keys(&[Key::Char('c')]);
+        let keys: Vec<Key> = next.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, [Key::Char('s'), Key::Char('g'), Key::Char('l')]);
+        assert_eq!(
+            next[0].1,
+            [Action::ClaudeSendPlan, Action::ClaudeSendImplement]
+        );
+    }
+
+    #[test]
+    fn test_labels() {
+        assert_eq!(Action::SendTo(ViewMode::Waiting).label(), "Waiting");
+        assert_eq!(Action::Priority('A').label(), "(A)");
+        assert_eq!(Action::Delete.label(), "Delete");
+        assert_eq!(Action::OpenUrls.label(), "Open urls");
+        assert_eq!(
+            group_label(&[Action::ClaudeSendPlan, Action::ClaudeSendImplement]),
+            "Send"
+        );
+        assert_eq!(group_label(&[Action::ClaudeGetPlans]), "Get");
+        assert_eq!(group_label(&[Action::Quit, Action::Help]), "More");
+    }
+
+    #[test]
+    fn test_every_action_is_listed_once() {
+        let names: std::collections::BTreeSet<&str> =
+            ACTIONS.iter().map(|(_, name, _)| *name).collect();
+        assert_eq!(names.len(), ACTIONS.len());
+        // Dotted names become TOML tables, so no name may also be a group
+        for name in &names {
+            let group = format!("{name}.");
+            assert!(!names.iter().any(|n| n.starts_with(&group)), "{name}");
+        }
+    }
+}
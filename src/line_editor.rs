@@ -0,0 +1,227 @@
+use unicode_width::UnicodeWidthChar;
+
+/// Single-line text buffer with a char-indexed cursor, backing the inline
+/// prompts drawn in the footer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LineEditor {
+    buffer: String,
+    cursor: usize,
+}
+
+impl LineEditor {
+    /// Create an editor holding `text` with the cursor placed at `cursor`
+    /// (a char index, clamped to the text length).
+    pub fn with_cursor(text: &str, cursor: usize) -> Self {
+        let len = text.chars().count();
+        Self {
+            buffer: text.to_string(),
+            cursor: cursor.min(len),
+        }
+    }
+
+    /// Create an editor holding `text` with the cursor at the end.
+    pub fn new(text: &str) -> Self {
+        Self::with_cursor(text, usize::MAX)
+    }
+
+    pub fn text(&self) -> &str {
+        &self.buffer
+    }
+
+    pub const fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    fn char_len(&self) -> usize {
+        self.buffer.chars().count()
+    }
+
+    fn byte_index(&self, char_idx: usize) -> usize {
+        self.buffer
+            .char_indices()
+            .nth(char_idx)
+            .map_or(self.buffer.len(), |(i, _)| i)
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        let at = self.byte_index(self.cursor);
+        self.buffer.insert(at, c);
+        self.cursor += 1;
+    }
+
+    /// Insert pasted text at the cursor. Line breaks and tabs are folded into
+    /// single spaces because a todo.txt item must stay on one line.
+    pub fn insert_str(&mut self, s: &str) {
+        let normalized = s.replace("\r\n", "\n");
+        for c in normalized.chars() {
+            let c = if matches!(c, '\n' | '\r' | '\t') {
+                ' '
+            } else {
+                c
+            };
+            if !c.is_control() {
+                self.insert_char(c);
+            }
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if self.cursor == 0 {
+            return;
+        }
+        self.cursor -= 1;
+        let at = self.byte_index(self.cursor);
+        self.buffer.remove(at);
+    }
+
+    pub fn delete(&mut self) {
+        if self.cursor >= self.char_len() {
+            return;
+        }
+        let at = self.byte_index(self.cursor);
+        self.buffer.remove(at);
+    }
+
+    pub const fn move_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_right(&mut self) {
+        if self.cursor < self.char_len() {
+            self.cursor += 1;
+        }
+    }
+
+    pub const fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.char_len();
+    }
+
+    /// Delete from the start of the line up to the cursor (readline `C-u`).
+    pub fn delete_to_start(&mut self) {
+        let at = self.byte_index(self.cursor);
+        self.buffer.replace_range(..at, "");
+        self.cursor = 0;
+    }
+
+    /// Delete the word before the cursor (readline `C-w`).
+    pub fn delete_word_before(&mut self) {
+        let chars: Vec<char> = self.buffer.chars().collect();
+        let mut start = self.cursor;
+        while start > 0 && chars[start - 1] == ' ' {
+            start -= 1;
+        }
+        while start > 0 && chars[start - 1] != ' ' {
+            start -= 1;
+        }
+        let from = self.byte_index(start);
+        let to = self.byte_index(self.cursor);
+        self.buffer.replace_range(from..to, "");
+        self.cursor = start;
+    }
+
+    /// Display width (terminal cells) of the text before the cursor.
+    pub fn cursor_width(&self) -> usize {
+        self.buffer
+            .chars()
+            .take(self.cursor)
+            .map(|c| c.width().unwrap_or(0))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_places_cursor_at_end() {
+        let ed = LineEditor::new("abc");
+        assert_eq!(ed.cursor(), 3);
+        assert_eq!(ed.text(), "abc");
+    }
+
+    #[test]
+    fn with_cursor_clamps() {
+        let ed = LineEditor::with_cursor("ab", 10);
+        assert_eq!(ed.cursor(), 2);
+        let ed = LineEditor::with_cursor(" +proj", 0);
+        assert_eq!(ed.cursor(), 0);
+    }
+
+    #[test]
+    fn insert_at_cursor_in_middle() {
+        let mut ed = LineEditor::with_cursor(" +proj", 0);
+        for c in "Buy milk".chars() {
+            ed.insert_char(c);
+        }
+        assert_eq!(ed.text(), "Buy milk +proj");
+        assert_eq!(ed.cursor(), 8);
+    }
+
+    #[test]
+    fn backspace_and_delete() {
+        let mut ed = LineEditor::new("abcd");
+        ed.backspace();
+        assert_eq!(ed.text(), "abc");
+        ed.move_home();
+        ed.delete();
+        assert_eq!(ed.text(), "bc");
+        ed.backspace();
+        assert_eq!(ed.text(), "bc", "backspace at start is a no-op");
+        ed.move_end();
+        ed.delete();
+        assert_eq!(ed.text(), "bc", "delete at end is a no-op");
+    }
+
+    #[test]
+    fn cursor_movement_is_bounded() {
+        let mut ed = LineEditor::new("ab");
+        ed.move_right();
+        assert_eq!(ed.cursor(), 2);
+        ed.move_left();
+        ed.move_left();
+        ed.move_left();
+        assert_eq!(ed.cursor(), 0);
+    }
+
+    #[test]
+    fn multibyte_editing() {
+        let mut ed = LineEditor::new("テスト");
+        ed.move_left();
+        ed.backspace();
+        assert_eq!(ed.text(), "テト");
+        assert_eq!(ed.cursor(), 1);
+        assert_eq!(ed.cursor_width(), 2);
+    }
+
+    #[test]
+    fn paste_folds_newlines_into_spaces() {
+        let mut ed = LineEditor::new("");
+        ed.insert_str("line one\r\nline two\nthree\tfour");
+        assert_eq!(ed.text(), "line one line two three four");
+    }
+
+    #[test]
+    fn delete_word_before_cursor() {
+        let mut ed = LineEditor::new("Buy some milk  ");
+        ed.delete_word_before();
+        assert_eq!(ed.text(), "Buy some ");
+        ed.delete_word_before();
+        assert_eq!(ed.text(), "Buy ");
+    }
+
+    #[test]
+    fn delete_to_start_keeps_tail() {
+        let mut ed = LineEditor::new("Buy milk +proj");
+        for _ in 0..6 {
+            ed.move_left();
+        }
+        ed.delete_to_start();
+        assert_eq!(ed.text(), " +proj");
+        assert_eq!(ed.cursor(), 0);
+    }
+}
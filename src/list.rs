@@ -0,0 +1,288 @@
+use crate::app_state::ViewMode;
+use crate::query::Query;
+use crate::todo::{Item, item_to_value, load_todos};
+use std::error::Error;
+use std::fs;
+use std::process;
+use unicode_width::UnicodeWidthStr;
+
+/// File a `torudo list` reads from: one of the GTD modes or the archive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ListSource {
+    Inbox,
+    Todo,
+    Waiting,
+    Ref,
+    Someday,
+    Done,
+}
+
+impl ListSource {
+    pub fn filename(self) -> &'static str {
+        match self {
+            Self::Inbox => ViewMode::Inbox.filename(),
+            Self::Todo => ViewMode::Todo.filename(),
+            Self::Waiting => ViewMode::Waiting.filename(),
+            Self::Ref => ViewMode::Ref.filename(),
+            Self::Someday => ViewMode::Someday.filename(),
+            Self::Done => &crate::config::get().files.done,
+        }
+    }
+}
+
+impl From<ViewMode> for ListSource {
+    fn from(mode: ViewMode) -> Self {
+        match mode {
+            ViewMode::Inbox => Self::Inbox,
+            ViewMode::Todo => Self::Todo,
+            ViewMode::Waiting => Self::Waiting,
+            ViewMode::Ref => Self::Ref,
+            ViewMode::Someday => Self::Someday,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ListFormat {
+    /// Pretty-printed JSON array
+    Json,
+    /// One compact JSON object per line
+    Jsonl,
+    /// The original todo.txt lines
+    Txt,
+    /// Aligned columns for reading in a terminal
+    Table,
+}
+
+pub struct ListOptions<'a> {
+    pub source: ListSource,
+    pub project: Option<&'a str>,
+    pub filter: Option<&'a Query>,
+    pub format: ListFormat,
+}
+
+/// Items of the source file that pass the project and query filters, in
+/// display order. A mode file that has not been created yet lists nothing.
+pub fn collect_items(
+    todotxt_dir: &str,
+    opts: &ListOptions<'_>,
+) -> Result<Vec<Item>, Box<dyn Error>> {
+    let path = format!("{todotxt_dir}/{}", opts.source.filename());
+    if !std::path::Path::new(&path).exists() {
+        return Ok(Vec::new());
+    }
+    Ok(load_todos(&path)?
+        .into_iter()
+        .filter(|item| {
+            opts.project
+                .is_none_or(|p| item.projects.iter().any(|x| x == p))
+        })
+        .filter(|item| opts.filter.is_none_or(|q| q.matches(item, todotxt_dir)))
+        .collect())
+}
+
+fn render_table(items: &[Item]) -> String {
+    let rows: Vec<[String; 5]> = items
+        .iter()
+        .map(|item| {
+            [
+                item.priority
+                    .map_or_else(|| "-".to_string(), |p| p.to_string()),
+                item.due_date()
+                    .map_or_else(|| "-".to_string(), |d| d.to_string()),
+                item.projects
+                    .iter()
+                    .map(|p| format!("+{p}"))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                item.description.clone(),
+                item.id.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+    let header = ["PRI", "DUE", "PROJECTS", "TITLE", "ID"].map(String::from);
+    let mut widths = header.clone().map(|h| h.width());
+    for row in &rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.width());
+        }
+    }
+    std::iter::once(&header)
+        .chain(&rows)
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, w)| format!("{cell}{}", " ".repeat(w - cell.width())))
+                .collect();
+            cells.join("  ").trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render `items` (read from `source_path`) in the requested format.
+pub fn render(
+    items: &[Item],
+    format: ListFormat,
+    todotxt_dir: &str,
+    source_path: &str,
+) -> Result<String, Box<dyn Error>> {
+    Ok(match format {
+        ListFormat::Json => {
+            let values = items
+                .iter()
+                .map(|item| item_to_value(item, todotxt_dir))
+                .collect::<Result<Vec<_>, _>>()?;
+            serde_json::to_string_pretty(&values)?
+        }
+        ListFormat::Jsonl => items
+            .iter()
+            .map(|item| Ok(serde_json::to_string(&item_to_value(item, todotxt_dir)?)?))
+            .collect::<Result<Vec<_>, Box<dyn Error>>>()?
+            .join("\n"),
+        ListFormat::Txt => {
+            let content = fs::read_to_string(source_path).unwrap_or_default();
+            let lines: Vec<&str> = content.lines().collect();
+            items
+                .iter()
+                .filter_map(|item| lines.get(item.line_number.checked_sub(1)?))
+                .copied()
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        ListFormat::Table => render_table(items),
+    })
+}
+
+pub fn run(
+    todotxt_dir: &str,
+    source: ListSource,
+    project: Option<&str>,
+    filter: Option<&str>,
+    format: ListFormat,
+) -> Result<(), Box<dyn Error>> {
+    let query = match filter.map(Query::parse).transpose() {
+        Ok(query) => query,
+        Err(e) => {
+            eprintln!("Invalid filter: {e}");
+            process::exit(2);
+        }
+    };
+    let opts = ListOptions {
+        source,
+        project,
+        filter: query.as_ref(),
+        format,
+    };
+    let items = collect_items(todotxt_dir, &opts)?;
+    let source_path = format!("{todotxt_dir}/{}", source.filename());
+    let out = render(&items, opts.format, todotxt_dir, &source_path)?;
+    if !out.is_empty() {
+        println!("{out}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opts(source: ListSource) -> ListOptions<'static> {
+        ListOptions {
+            source,
+            project: None,
+            filter: None,
+            format: ListFormat::Json,
+        }
+    }
+
+    fn setup() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("todo.txt"),
+            "(C) Email Ann @mail +home id:b\n(A) Call Bob @phone +work due:2026-10-20 id:a\n",
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_collect_items_applies_query_and_project() {
+        let dir = setup();
+        let dir_path = dir.path().to_str().unwrap();
+
+        let all = collect_items(dir_path, &opts(ListSource::Todo)).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].id.as_deref(), Some("a"), "sorted by priority");
+
+        let query = Query::parse("@phone pri:A-B").unwrap();
+        let mut o = opts(ListSource::Todo);
+        o.filter = Some(&query);
+        assert_eq!(collect_items(dir_path, &o).unwrap().len(), 1);
+
+        let mut o = opts(ListSource::Todo);
+        o.project = Some("home");
+        let items = collect_items(dir_path, &o).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id.as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn test_collect_items_missing_mode_file_is_empty() {
+        let dir = setup();
+        let items =
+            collect_items(dir.path().to_str().unwrap(), &opts(ListSource::Someday)).unwrap();
+        assert!(items.is_empty());
+    }
+
+    #[test]
+    fn test_render_txt_returns_original_lines() {
+        let dir = setup();
+        let dir_path = dir.path().to_str().unwrap();
+        let items = collect_items(dir_path, &opts(ListSource::Todo)).unwrap();
+        let out = render(
+            &items,
+            ListFormat::Txt,
+            dir_path,
+            &format!("{dir_path}/todo.txt"),
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            "(A) Call Bob @phone +work due:2026-10-20 id:a\n(C) Email Ann @mail +home id:b"
+        );
+    }
+
+    #[test]
+    fn test_render_jsonl_includes_md() {
+        let dir = setup();
+        let dir_path = dir.path().to_str().unwrap();
+        std::fs::create_dir_all(dir.path().join("todos")).unwrap();
+        std::fs::write(dir.path().join("todos/a.md"), "# notes\n").unwrap();
+        let items = collect_items(dir_path, &opts(ListSource::Todo)).unwrap();
+
+        let out = render(&items, ListFormat::Jsonl, dir_path, "").unwrap();
+        let lines: Vec<serde_json::Value> = out
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["md"], "# notes\n");
+        assert!(lines[1].get("md").is_none());
+    }
+
+    #[test]
+    fn test_render_table_aligns_columns() {
+        let dir = setup();
+        let dir_path = dir.path().to_str().unwrap();
+        let items = collect_items(dir_path, &opts(ListSource::Todo)).unwrap();
+        let out = render(&items, ListFormat::Table, dir_path, "").unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("PRI  DUE"));
+        let title_col = lines[0].find("TITLE").unwrap();
+        assert_eq!(&lines[1][title_col..title_col + 8], "Call Bob");
+        assert_eq!(&lines[2][title_col..title_col + 9], "Email Ann");
+    }
+}
@@ -1,6 +1,8 @@
 use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+    },
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
@@ -9,19 +11,37 @@ use ratatui::{Terminal, backend::CrosstermBackend};
 use std::time::Duration;
 use std::{env, error::Error, io};
 
+mod agenda;
 mod app_state;
+mod archive;
 mod claude;
+mod columns;
+mod config;
 mod crmux;
 mod event_handler;
 mod file_watcher;
+mod grouping;
 mod help;
+mod keymap;
+mod line_editor;
+mod list;
 mod md_preview;
+mod mouse;
+mod mutate;
+mod query;
+mod recurrence;
+mod relative_date;
 mod rpc_client;
 mod rpc_server;
+mod search;
 mod setup;
+mod store;
 mod templates;
+mod theme;
 mod todo;
+mod todo_file;
 mod ui;
+mod undo;
 mod update;
 mod url;
 
@@ -41,14 +61,18 @@ struct Args {
     #[arg(short, long)]
     debug: bool,
 
-    /// Neovim socket path (set by nvim --listen)
-    #[arg(long, env = "NVIM_LISTEN_ADDRESS", default_value = "/tmp/nvim.sock")]
-    nvim_listen: String,
+    /// Neovim socket path (set by nvim --listen) [default: /tmp/nvim.sock]
+    #[arg(long, env = "NVIM_LISTEN_ADDRESS")]
+    nvim_listen: Option<String>,
 
     /// Path to the todotxt directory
     #[arg(long, env = "TODOTXT_DIR")]
     todotxt_dir: Option<String>,
 
+    /// Initial layout: project columns, or columns by due date across all modes [default: board]
+    #[arg(long, value_enum)]
+    view: Option<app_state::View>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -66,11 +90,67 @@ enum Commands {
         #[arg(long)]
         check: bool,
     },
+    /// Print the items of a mode file (works without a running TUI)
+    List {
+        /// Mode file to read
+        #[arg(long, value_enum, default_value = "todo")]
+        mode: list::ListSource,
+        /// Only items tagged with this project (without the leading +)
+        #[arg(long)]
+        project: Option<String>,
+        /// Filter query, e.g. '@phone pri:A-B due<2026-11-01 -+someday has:md'
+        #[arg(long)]
+        filter: Option<String>,
+        /// Output format
+        #[arg(long, value_enum, default_value = "json")]
+        format: list::ListFormat,
+    },
+    /// Complete an item (moves it to done.txt) and print it as JSON
+    Done {
+        /// Value of the item's id: tag
+        id: String,
+    },
+    /// Delete an item and its detail md, printing the removed item as JSON
+    Rm {
+        /// Value of the item's id: tag
+        id: String,
+    },
+    /// Set or clear an item's priority and print it as JSON
+    Pri {
+        /// Value of the item's id: tag
+        id: String,
+        /// Priority letter A-Z, or 'none' to clear it
+        // Fully qualified so clap treats it as a required value, not an optional argument
+        #[arg(value_parser = mutate::parse_priority)]
+        priority: ::std::option::Option<char>,
+    },
+    /// Move an item to another mode file and print it as JSON
+    Mv {
+        /// Value of the item's id: tag
+        id: String,
+        /// Destination mode
+        #[arg(value_enum)]
+        mode: app_state::ViewMode,
+    },
+    /// Undo the last file change made from torudo (TUI or CLI)
+    Undo,
+    /// Reapply the last undone change
+    Redo,
     /// Inbox operations
     Inbox {
         #[command(subcommand)]
         action: InboxAction,
     },
+    /// Configuration file operations
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// done.txt archive operations
+    Archive {
+        #[command(subcommand)]
+        action: ArchiveAction,
+    },
 }
 
 #[derive(Subcommand)]
@@ -83,42 +163,112 @@ enum InboxAction {
     },
 }
 
-fn resolve_todotxt_dir(cli: Option<String>) -> String {
-    cli.unwrap_or_else(|| {
-        let home_dir = env::var("HOME").unwrap();
-        format!("{home_dir}/todotxt")
-    })
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the effective configuration after merging files and flags
+    Show,
+}
+
+#[derive(Subcommand)]
+enum ArchiveAction {
+    /// Move items completed before this year or month into done-YYYY.txt / done-YYYY-MM.txt
+    Rotate {
+        /// Period per archive file [default: `[archive] rotate` from the config]
+        #[arg(long, value_enum)]
+        by: Option<archive::Rotation>,
+    },
+}
+
+/// Run a CLI subcommand. None of them start the TUI.
+fn run_command(command: &Commands, loaded: &config::Loaded) -> Result<(), Box<dyn Error>> {
+    let todotxt_dir = config::get().todotxt_dir();
+    match command {
+        Commands::Current => rpc_client::run_current(todotxt_dir),
+        Commands::Update { force, check } => {
+            handle_update(*force, *check);
+            Ok(())
+        }
+        Commands::Inbox {
+            action: InboxAction::Add { text },
+        } => {
+            let item = mutate::add(todotxt_dir, app_state::ViewMode::Inbox, &text.join(" "))?;
+            let json = todo::item_to_json(&item, todotxt_dir)?;
+            println!("{json}");
+            Ok(())
+        }
+        Commands::List {
+            mode,
+            project,
+            filter,
+            format,
+        } => list::run(
+            todotxt_dir,
+            *mode,
+            project.as_deref(),
+            filter.as_deref(),
+            *format,
+        ),
+        Commands::Done { id } => mutate::run(todotxt_dir, |dir| mutate::complete(dir, id)),
+        Commands::Rm { id } => mutate::run(todotxt_dir, |dir| mutate::remove(dir, id)),
+        Commands::Pri { id, priority } => {
+            mutate::run(todotxt_dir, |dir| mutate::prioritize(dir, id, *priority))
+        }
+        Commands::Mv { id, mode } => {
+            mutate::run(todotxt_dir, |dir| mutate::send_to(dir, id, *mode))
+        }
+        Commands::Undo | Commands::Redo => {
+            let stack = undo::UndoStack::new(todotxt_dir);
+            let (result, verb, empty) = if matches!(command, Commands::Undo) {
+                (stack.undo(), "Undid", "Nothing to undo")
+            } else {
+                (stack.redo(), "Redid", "Nothing to redo")
+            };
+            match result? {
+                Some(label) => println!("{verb}: {label}"),
+                None => println!("{empty}"),
+            }
+            Ok(())
+        }
+        Commands::Config {
+            action: ConfigAction::Show,
+        } => config::run_show(loaded, config::get()),
+        Commands::Archive {
+            action: ArchiveAction::Rotate { by },
+        } => archive::run_rotate(todotxt_dir, *by),
+    }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let matches = Args::command()
-        .after_help(help::cli_help_text())
+        .after_help(help::cli_help_text(&keymap::Keymap::default()))
         .get_matches();
     let args = Args::from_arg_matches(&matches).expect("arg parsing should not fail");
 
-    // Handle subcommands before TUI setup
-    if matches!(args.command, Some(Commands::Current)) {
-        return rpc_client::run_current();
-    }
-    if let Some(Commands::Update { force, check }) = args.command {
-        handle_update(force, check);
-        return Ok(());
+    let loaded = match config::load(args.todotxt_dir.clone()) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("torudo: {e}");
+            std::process::exit(2);
+        }
+    };
+    let mut effective = loaded.config.clone();
+    effective.apply_flags(args.nvim_listen.clone(), args.view);
+    config::init(effective);
+    let config = config::get();
+
+    match store::recover(config.todotxt_dir()) {
+        Ok(Some(note)) => eprintln!("torudo: {note}"),
+        Ok(None) => {}
+        Err(e) => eprintln!("torudo: could not recover an interrupted move: {e}"),
     }
-    if let Some(Commands::Inbox {
-        action: InboxAction::Add { text },
-    }) = &args.command
-    {
-        let todotxt_dir = resolve_todotxt_dir(args.todotxt_dir.clone());
-        let inbox_path = format!("{todotxt_dir}/{}", app_state::ViewMode::Inbox.filename());
-        let joined = text.join(" ");
-        let item = todo::add_item(&inbox_path, &joined)?;
-        let json = todo::item_to_json(&item, &todotxt_dir)?;
-        println!("{json}");
-        return Ok(());
+
+    // Handle subcommands before TUI setup
+    if let Some(command) = &args.command {
+        return run_command(command, &loaded);
     }
 
-    let todotxt_dir = resolve_todotxt_dir(args.todotxt_dir);
-    let todo_file = format!("{todotxt_dir}/todo.txt");
+    let todotxt_dir = config.todotxt_dir().to_string();
+    let todo_file = format!("{todotxt_dir}/{}", config.files.todo);
 
     // Setup debug mode
     if args.debug {
@@ -131,6 +281,16 @@ fn main() -> Result<(), Box<dyn Error>> {
     // Ensure required directories and files exist
     ensure_setup_exists(&todotxt_dir, &todo_file)?;
 
+    if config.archive.rotate != archive::Rotation::Off
+        && let Err(e) = archive::rotate(
+            &todotxt_dir,
+            config.archive.rotate,
+            chrono::Local::now().date_naive(),
+        )
+    {
+        eprintln!("torudo: could not rotate {}: {e}", config.files.done);
+    }
+
     // Add UUIDs to lines without IDs on first startup
     if add_missing_ids(&todo_file).is_err() {
         // Continue even if error occurs
@@ -147,7 +307,12 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
@@ -163,14 +328,16 @@ fn main() -> Result<(), Box<dyn Error>> {
         &todo_file,
         &todotxt_dir,
         args.debug,
-        args.nvim_listen,
+        config.nvim.listen.clone(),
+        config.view,
     );
 
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
     if let Err(err) = result {
@@ -188,14 +355,17 @@ fn run_app(
     todotxt_dir: &str,
     debug_mode: bool,
     nvim_socket: String,
+    view: app_state::View,
 ) -> io::Result<()> {
     let mut state = AppState::new(todos, nvim_socket, todotxt_dir.to_string());
+    state.set_view(view);
     let mut event_handler = EventHandler::new();
 
-    let rpc_server = match rpc_server::RpcServer::new(todotxt_dir) {
+    let mut rpc_server = match rpc_server::RpcServer::new(todotxt_dir) {
         Ok(server) => Some(server),
         Err(e) => {
             debug!("Failed to start RPC server: {e}");
+            state.status_message = Some(format!("RPC server not started: {e}"));
             None
         }
     };
@@ -225,8 +395,10 @@ fn run_app(
         // Handle file watcher events
         event_handler.handle_file_watcher_events(file_watcher_rx, &mut state, debug_mode);
 
-        if let Some(ref server) = rpc_server {
-            server.poll(state.get_current_todo());
+        if let Some(ref mut server) = rpc_server {
+            server.poll(&mut state);
+        } else {
+            state.events.clear();
         }
 
         // Check for background update result
@@ -2,8 +2,6 @@ use std::fs;
 use std::path::Path;
 use std::time::SystemTime;
 
-const PREVIEW_MAX: usize = 3;
-
 #[derive(Debug, Clone)]
 pub struct MdMeta {
     pub mtime: SystemTime,
@@ -20,7 +18,7 @@ fn read_mtime<P: AsRef<Path>>(path: P) -> Option<SystemTime> {
 }
 
 pub fn format_elapsed(mtime: SystemTime, now: SystemTime) -> String {
-    let secs = now.duration_since(mtime).map(|d| d.as_secs()).unwrap_or(0);
+    let secs = now.duration_since(mtime).map_or(0, |d| d.as_secs());
     if secs < 60 {
         format!("{secs:2}s")
     } else if secs < 3600 {
@@ -56,7 +54,7 @@ pub fn compute_meta(todotxt_dir: &str, id: &str) -> Option<MdMeta> {
     let mtime = read_mtime(&path)?;
     let (preview, stats) = fs::read_to_string(&path).map_or_else(
         |_| (Vec::new(), None),
-        |content| scan_md(&content, PREVIEW_MAX),
+        |content| scan_md(&content, crate::config::get().preview.max_items),
     );
     Some(MdMeta {
         mtime,
@@ -105,7 +103,7 @@ mod tests {
     #[test]
     fn format_elapsed_future_mtime_returns_zero() {
         let now = SystemTime::now();
-        let future = now + Duration::from_secs(60);
+        let future = now + Duration::from_mins(1);
         assert_eq!(format_elapsed(future, now), " 0s");
     }
 
@@ -0,0 +1,124 @@
+use crate::app_state::ViewMode;
+use ratatui::layout::{Position, Rect};
+use unicode_width::UnicodeWidthStr;
+
+/// What a tab in the tab bar switches to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TabTarget {
+    Mode(ViewMode),
+    Agenda,
+    Done,
+}
+
+/// What lies under the mouse pointer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hit {
+    Tab(TabTarget),
+    Card {
+        column: usize,
+        row: usize,
+    },
+    /// A column outside its cards (border, title or empty space)
+    Column(usize),
+}
+
+/// Screen areas of the last drawn frame, recorded by the UI so mouse events
+/// can be mapped back to tabs, columns and cards.
+#[derive(Debug, Default)]
+pub struct ClickMap {
+    tabs: Vec<(Rect, TabTarget)>,
+    columns: Vec<(Rect, usize)>,
+    cards: Vec<(Rect, usize, usize)>,
+}
+
+/// Where `Tabs` draws each title: a space of padding on both sides and a
+/// one-cell divider between titles. Titles cut off at the right edge are
+/// clipped; titles that do not fit at all get no area.
+pub fn tab_areas(area: Rect, titles: &[String]) -> Vec<Rect> {
+    let mut areas = Vec::new();
+    let mut x = area.left();
+    for title in titles {
+        if x >= area.right() {
+            break;
+        }
+        let width = u16::try_from(title.width() + 2).unwrap_or(u16::MAX);
+        let width = width.min(area.right() - x);
+        areas.push(Rect::new(x, area.top(), width, 1));
+        x = x.saturating_add(width).saturating_add(1);
+    }
+    areas
+}
+
+impl ClickMap {
+    pub fn clear(&mut self) {
+        self.tabs.clear();
+        self.columns.clear();
+        self.cards.clear();
+    }
+
+    pub fn add_tab(&mut self, area: Rect, target: TabTarget) {
+        self.tabs.push((area, target));
+    }
+
+    pub fn add_column(&mut self, area: Rect, column: usize) {
+        self.columns.push((area, column));
+    }
+
+    pub fn add_card(&mut self, area: Rect, column: usize, row: usize) {
+        self.cards.push((area, column, row));
+    }
+
+    /// The innermost target at cell (`x`, `y`).
+    pub fn hit(&self, x: u16, y: u16) -> Option<Hit> {
+        let pos = Position::new(x, y);
+        if let Some(&(_, column, row)) = self.cards.iter().find(|(r, ..)| r.contains(pos)) {
+            return Some(Hit::Card { column, row });
+        }
+        if let Some(&(_, column)) = self.columns.iter().find(|(r, _)| r.contains(pos)) {
+            return Some(Hit::Column(column));
+        }
+        self.tabs
+            .iter()
+            .find(|(r, _)| r.contains(pos))
+            .map(|&(_, target)| Hit::Tab(target))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tab_areas_follow_padding_and_dividers() {
+        let titles = vec!["Inbox (2)".to_string(), "Todo (10)".to_string()];
+        let areas = tab_areas(Rect::new(1, 1, 80, 1), &titles);
+        // " Inbox (2) │ Todo (10) "
+        assert_eq!(areas, [Rect::new(1, 1, 11, 1), Rect::new(13, 1, 11, 1)]);
+
+        let clipped = tab_areas(Rect::new(0, 0, 15, 1), &titles);
+        assert_eq!(clipped, [Rect::new(0, 0, 11, 1), Rect::new(12, 0, 3, 1)]);
+    }
+
+    #[test]
+    fn test_hit_prefers_cards_over_columns() {
+        let mut map = ClickMap::default();
+        map.add_tab(Rect::new(0, 0, 6, 1), TabTarget::Mode(ViewMode::Inbox));
+        map.add_tab(Rect::new(7, 0, 8, 1), TabTarget::Agenda);
+        map.add_column(Rect::new(0, 1, 20, 10), 0);
+        map.add_column(Rect::new(20, 1, 20, 10), 1);
+        map.add_card(Rect::new(21, 2, 18, 3), 1, 4);
+
+        assert_eq!(
+            map.hit(3, 0),
+            Some(Hit::Tab(TabTarget::Mode(ViewMode::Inbox)))
+        );
+        assert_eq!(map.hit(6, 0), None);
+        assert_eq!(map.hit(10, 0), Some(Hit::Tab(TabTarget::Agenda)));
+        assert_eq!(map.hit(25, 3), Some(Hit::Card { column: 1, row: 4 }));
+        assert_eq!(map.hit(25, 8), Some(Hit::Column(1)));
+        assert_eq!(map.hit(50, 8), None);
+
+        map.clear();
+        assert_eq!(map.hit(25, 3), None);
+    }
+}
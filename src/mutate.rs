@@ -0,0 +1,392 @@
+use crate::app_state::ViewMode;
+use crate::md_preview::md_path;
+use crate::store;
+use crate::todo::{
+    Item, add_item, delete_todo, item_to_json, load_todos, mark_complete, move_to_file,
+    set_priority,
+};
+use crate::undo::{UndoStack, mark_ends, snapshot};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::process;
+use uuid::Uuid;
+
+/// Exit status when no open item carries the requested id.
+pub const EXIT_NOT_FOUND: i32 = 3;
+/// Exit status when a file could not be read or written.
+pub const EXIT_IO: i32 = 4;
+
+/// Failure of a CLI mutation, split so scripts can tell a typo'd id from a
+/// broken todotxt directory.
+#[derive(Debug)]
+pub enum MutateError {
+    NotFound(String),
+    Io(Box<dyn Error>),
+}
+
+impl MutateError {
+    pub const fn exit_code(&self) -> i32 {
+        match self {
+            Self::NotFound(_) => EXIT_NOT_FOUND,
+            Self::Io(_) => EXIT_IO,
+        }
+    }
+}
+
+impl fmt::Display for MutateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(id) => write!(f, "No open item with id:{id}"),
+            Self::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for MutateError {}
+
+impl From<Box<dyn Error>> for MutateError {
+    fn from(e: Box<dyn Error>) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<std::io::Error> for MutateError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e.into())
+    }
+}
+
+/// Parse the `torudo pri` argument: a letter `A`-`Z` (any case) or `none`.
+pub fn parse_priority(s: &str) -> Result<Option<char>, String> {
+    if s.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => Ok(Some(c.to_ascii_uppercase())),
+        _ => Err(format!("expected a letter A-Z or 'none', got '{s}'")),
+    }
+}
+
+fn mode_path(todotxt_dir: &str, mode: ViewMode) -> String {
+    format!("{todotxt_dir}/{}", mode.filename())
+}
+
+fn find_in(path: &str, id: &str) -> Result<Option<Item>, MutateError> {
+    if !Path::new(path).exists() {
+        return Ok(None);
+    }
+    Ok(load_todos(path)?
+        .into_iter()
+        .find(|item| !item.completed && item.id.as_deref() == Some(id)))
+}
+
+/// Find the mode file holding the open item `id`. Mutations call it with
+/// the directory lock held, so the file they edit is the one it found.
+pub fn locate(todotxt_dir: &str, id: &str) -> Result<(ViewMode, Item), MutateError> {
+    for &mode in ViewMode::ALL {
+        if let Some(item) = find_in(&mode_path(todotxt_dir, mode), id)? {
+            return Ok((mode, item));
+        }
+    }
+    Err(MutateError::NotFound(id.to_string()))
+}
+
+/// Run `op` under the directory lock, recording the before-images of `paths`
+/// in the undo journal just like the TUI does for the same key.
+fn with_undo<T>(
+    todotxt_dir: &str,
+    label: &str,
+    paths: &[String],
+    op: impl FnOnce() -> Result<T, Box<dyn Error>>,
+) -> Result<T, Box<dyn Error>> {
+    with_undo_appending(todotxt_dir, label, paths, &[], op)
+}
+
+/// Like `with_undo`, but `appended` files are only appended to, so just the
+/// added lines are journaled.
+fn with_undo_appending<T>(
+    todotxt_dir: &str,
+    label: &str,
+    paths: &[String],
+    appended: &[String],
+    op: impl FnOnce() -> Result<T, Box<dyn Error>>,
+) -> Result<T, Box<dyn Error>> {
+    let _lock = store::lock(Path::new(todotxt_dir))?;
+    let before = snapshot(paths);
+    let ends = mark_ends(appended);
+    let result = op();
+    if let Err(e) = UndoStack::new(todotxt_dir).record_appending(label, before, ends) {
+        eprintln!("Failed to record undo entry: {e}");
+    }
+    result
+}
+
+/// Append `text` as a new item to the file of `mode`, like `a` in the TUI.
+pub fn add(todotxt_dir: &str, mode: ViewMode, text: &str) -> Result<Item, MutateError> {
+    let file = mode_path(todotxt_dir, mode);
+    let paths = [file.clone()];
+    Ok(with_undo(todotxt_dir, "Add", &paths, || {
+        add_item(&file, text)
+    })?)
+}
+
+/// `torudo done <id>`: move the item to done.txt and return it as completed.
+pub fn complete(todotxt_dir: &str, id: &str) -> Result<Item, MutateError> {
+    let _lock = store::lock(Path::new(todotxt_dir))?;
+    let (mode, _) = locate(todotxt_dir, id)?;
+    let file = mode_path(todotxt_dir, mode);
+    let done_file = format!("{todotxt_dir}/{}", crate::config::get().files.done);
+    // A recurring item's next occurrence may get a copy of the md
+    let next_id = Uuid::new_v4().to_string();
+    let paths = [file.clone(), md_path(todotxt_dir, &next_id)];
+    with_undo_appending(todotxt_dir, "Complete", &paths, &[done_file], || {
+        mark_complete(&file, id, &next_id)
+    })?
+    .map(|completion| completion.archived)
+    .ok_or_else(|| MutateError::NotFound(id.to_string()))
+}
+
+/// `torudo rm <id>`: delete the item and its detail md, returning the item as
+/// it was before removal.
+pub fn remove(todotxt_dir: &str, id: &str) -> Result<Item, MutateError> {
+    let _lock = store::lock(Path::new(todotxt_dir))?;
+    let (mode, item) = locate(todotxt_dir, id)?;
+    let file = mode_path(todotxt_dir, mode);
+    let md = md_path(todotxt_dir, id);
+    let paths = [file.clone(), md.clone()];
+    let removed = with_undo(todotxt_dir, "Delete", &paths, || {
+        let removed = delete_todo(&file, id)?;
+        if removed
+            && let Err(e) = fs::remove_file(&md)
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            return Err(e.into());
+        }
+        Ok(removed)
+    })?;
+    if !removed {
+        return Err(MutateError::NotFound(id.to_string()));
+    }
+    Ok(item)
+}
+
+/// `torudo pri <id> A|none`: set or clear the priority.
+pub fn prioritize(
+    todotxt_dir: &str,
+    id: &str,
+    priority: Option<char>,
+) -> Result<Item, MutateError> {
+    let _lock = store::lock(Path::new(todotxt_dir))?;
+    let (mode, _) = locate(todotxt_dir, id)?;
+    let file = mode_path(todotxt_dir, mode);
+    let paths = [file.clone()];
+    with_undo(todotxt_dir, "Priority", &paths, || {
+        set_priority(&file, id, priority)
+    })?;
+    find_in(&file, id)?.ok_or_else(|| MutateError::NotFound(id.to_string()))
+}
+
+/// `torudo mv <id> <mode>`: move the item to another mode file. Moving to the
+/// mode it is already in leaves the files untouched.
+pub fn send_to(todotxt_dir: &str, id: &str, target: ViewMode) -> Result<Item, MutateError> {
+    let _lock = store::lock(Path::new(todotxt_dir))?;
+    let (mode, item) = locate(todotxt_dir, id)?;
+    if mode == target {
+        return Ok(item);
+    }
+    let source_file = mode_path(todotxt_dir, mode);
+    let target_file = mode_path(todotxt_dir, target);
+    let paths = [source_file.clone(), target_file.clone()];
+    let label = format!("Send to {}", target.label());
+    with_undo(todotxt_dir, &label, &paths, || {
+        move_to_file(&source_file, &target_file, id)
+    })?;
+    find_in(&target_file, id)?.ok_or_else(|| MutateError::NotFound(id.to_string()))
+}
+
+/// Print the item returned by `op` as JSON, or report the error on stderr
+/// and exit with its status code.
+pub fn run(
+    todotxt_dir: &str,
+    op: impl FnOnce(&str) -> Result<Item, MutateError>,
+) -> Result<(), Box<dyn Error>> {
+    let item = op(todotxt_dir).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        process::exit(e.exit_code());
+    });
+    println!("{}", item_to_json(&item, todotxt_dir)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("todo.txt"),
+            "(B) Call Bob +work id:t1\nWrite report +work id:t2\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("waiting.txt"), "Parcel +home id:w1\n").unwrap();
+        dir
+    }
+
+    fn read(dir: &tempfile::TempDir, name: &str) -> String {
+        fs::read_to_string(dir.path().join(name)).unwrap_or_default()
+    }
+
+    #[test]
+    fn test_parse_priority() {
+        assert_eq!(parse_priority("a"), Ok(Some('A')));
+        assert_eq!(parse_priority("C"), Ok(Some('C')));
+        assert_eq!(parse_priority("NONE"), Ok(None));
+        assert!(parse_priority("AB").is_err());
+        assert!(parse_priority("1").is_err());
+    }
+
+    #[test]
+    fn test_locate_searches_every_mode_file() {
+        let dir = setup();
+        let dir_path = dir.path().to_str().unwrap();
+        let (mode, item) = locate(dir_path, "w1").unwrap();
+        assert_eq!(mode, ViewMode::Waiting);
+        assert_eq!(item.description, "Parcel");
+        let err = locate(dir_path, "nope").unwrap_err();
+        assert_eq!(err.exit_code(), EXIT_NOT_FOUND);
+    }
+
+    #[test]
+    fn test_add_appends_to_mode_file() {
+        let dir = setup();
+        let item = add(
+            dir.path().to_str().unwrap(),
+            ViewMode::Someday,
+            "Learn cello",
+        )
+        .unwrap();
+        assert!(item.id.is_some());
+        assert!(read(&dir, "someday.txt").contains("Learn cello"));
+    }
+
+    #[test]
+    fn test_complete_moves_to_done() {
+        let dir = setup();
+        let item = complete(dir.path().to_str().unwrap(), "w1").unwrap();
+        assert!(item.completed);
+        assert_eq!(item.id.as_deref(), Some("w1"));
+        assert_eq!(read(&dir, "waiting.txt"), "");
+        assert!(read(&dir, "done.txt").contains("Parcel +home id:w1"));
+    }
+
+    #[test]
+    fn test_undo_complete_of_recurring_item_removes_next_md() {
+        let dir = setup();
+        let dir_path = dir.path().to_str().unwrap();
+        fs::write(dir.path().join("todo.txt"), "Pay rent rec:1m id:r1\n").unwrap();
+        fs::create_dir_all(dir.path().join("todos")).unwrap();
+        fs::write(dir.path().join("todos/r1.md"), "# Account\n").unwrap();
+
+        complete(dir_path, "r1").unwrap();
+        let next = load_todos(&format!("{dir_path}/todo.txt"))
+            .unwrap()
+            .remove(0);
+        let next_md = md_path(dir_path, next.id.as_deref().unwrap());
+        assert!(Path::new(&next_md).exists());
+
+        UndoStack::new(dir_path).undo().unwrap();
+        assert_eq!(read(&dir, "todo.txt"), "Pay rent rec:1m id:r1\n");
+        assert!(!Path::new(&next_md).exists(), "copied md left behind");
+    }
+
+    #[test]
+    fn test_complete_journals_only_the_appended_line() {
+        let dir = setup();
+        let dir_path = dir.path().to_str().unwrap();
+        fs::write(dir.path().join("done.txt"), "x 2020-01-01 Ancient id:old\n").unwrap();
+
+        complete(dir_path, "t2").unwrap();
+        let journal = read(&dir, ".torudo/undo.json");
+        assert!(journal.contains("id:t2"), "{journal}");
+        assert!(!journal.contains("Ancient"), "{journal}");
+
+        UndoStack::new(dir_path).undo().unwrap();
+        assert_eq!(read(&dir, "done.txt"), "x 2020-01-01 Ancient id:old\n");
+        assert!(read(&dir, "todo.txt").contains("id:t2"));
+    }
+
+    #[test]
+    fn test_remove_deletes_line_and_md() {
+        let dir = setup();
+        fs::create_dir_all(dir.path().join("todos")).unwrap();
+        fs::write(dir.path().join("todos/t2.md"), "# notes\n").unwrap();
+        let item = remove(dir.path().to_str().unwrap(), "t2").unwrap();
+        assert_eq!(item.description, "Write report");
+        assert_eq!(read(&dir, "todo.txt"), "(B) Call Bob +work id:t1\n");
+        assert!(!dir.path().join("todos/t2.md").exists());
+    }
+
+    #[test]
+    fn test_remove_of_item_gone_while_waiting_for_the_lock_is_not_found() {
+        let dir = setup();
+        let dir_path = dir.path().to_str().unwrap().to_string();
+        let held = store::lock(dir.path()).unwrap();
+
+        let waiter = {
+            let dir_path = dir_path.clone();
+            std::thread::spawn(move || remove(&dir_path, "t2").map_err(|e| e.exit_code()))
+        };
+        // Another process completes the item before `rm` gets the lock
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        mark_complete(&format!("{dir_path}/todo.txt"), "t2", "next").unwrap();
+        drop(held);
+
+        assert_eq!(waiter.join().unwrap().unwrap_err(), EXIT_NOT_FOUND);
+        assert!(read(&dir, "done.txt").contains("id:t2"));
+    }
+
+    #[test]
+    fn test_prioritize_sets_and_clears() {
+        let dir = setup();
+        let dir_path = dir.path().to_str().unwrap();
+        let item = prioritize(dir_path, "t2", Some('A')).unwrap();
+        assert_eq!(item.priority, Some('A'));
+        let item = prioritize(dir_path, "t1", None).unwrap();
+        assert_eq!(item.priority, None);
+        assert_eq!(
+            read(&dir, "todo.txt"),
+            "Call Bob +work id:t1\n(A) Write report +work id:t2\n"
+        );
+    }
+
+    #[test]
+    fn test_send_to_moves_between_mode_files() {
+        let dir = setup();
+        let dir_path = dir.path().to_str().unwrap();
+        let item = send_to(dir_path, "t1", ViewMode::Someday).unwrap();
+        assert_eq!(item.id.as_deref(), Some("t1"));
+        assert!(read(&dir, "someday.txt").contains("id:t1"));
+        assert!(!read(&dir, "todo.txt").contains("id:t1"));
+
+        let before = read(&dir, "someday.txt");
+        send_to(dir_path, "t1", ViewMode::Someday).unwrap();
+        assert_eq!(read(&dir, "someday.txt"), before, "same mode is a no-op");
+    }
+
+    #[test]
+    fn test_mutations_are_undoable() {
+        let dir = setup();
+        let dir_path = dir.path().to_str().unwrap();
+        let original = read(&dir, "todo.txt");
+        send_to(dir_path, "t2", ViewMode::Inbox).unwrap();
+        assert_eq!(
+            UndoStack::new(dir_path).undo().unwrap().as_deref(),
+            Some("Send to Inbox")
+        );
+        assert_eq!(read(&dir, "todo.txt"), original);
+    }
+}
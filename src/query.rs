@@ -0,0 +1,390 @@
+use crate::md_preview::md_path;
+use crate::todo::Item;
+use chrono::NaiveDate;
+use std::fmt;
+
+/// Why a filter query could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    /// A lone `-` with nothing to negate
+    EmptyNegation,
+    /// A prefix such as `@`, `+`, `pri:` or `due<` without a value
+    MissingValue(String),
+    InvalidPriority(String),
+    InvalidDate(String),
+    UnknownHas(String),
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyNegation => write!(f, "'-' must be followed by a term"),
+            Self::MissingValue(term) => write!(f, "'{term}' is missing a value"),
+            Self::InvalidPriority(v) => {
+                write!(
+                    f,
+                    "invalid priority '{v}' (expected A-Z or a range like A-C)"
+                )
+            }
+            Self::InvalidDate(v) => write!(f, "invalid date '{v}' (expected YYYY-MM-DD)"),
+            Self::UnknownHas(v) => write!(
+                f,
+                "unknown has:{v} (expected md, due, t, pri, project or context)"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cmp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+}
+
+impl Cmp {
+    fn holds(self, lhs: NaiveDate, rhs: NaiveDate) -> bool {
+        match self {
+            Self::Lt => lhs < rhs,
+            Self::Le => lhs <= rhs,
+            Self::Gt => lhs > rhs,
+            Self::Ge => lhs >= rhs,
+            Self::Eq => lhs == rhs,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DateField {
+    Due,
+    Threshold,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HasField {
+    Md,
+    Due,
+    Threshold,
+    Priority,
+    Project,
+    Context,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Predicate {
+    Context(String),
+    Project(String),
+    /// Inclusive priority range, `A` being the highest
+    Priority(char, char),
+    Date(DateField, Cmp, NaiveDate),
+    Has(HasField),
+    KeyValue(String, String),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Term {
+    negated: bool,
+    predicate: Predicate,
+}
+
+/// A parsed filter such as `@phone pri:A-B due<2026-11-01 -+someday has:md`.
+/// All terms must hold for an item to match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    source: String,
+    terms: Vec<Term>,
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, QueryError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| QueryError::InvalidDate(value.to_string()))
+}
+
+fn parse_priority(value: &str) -> Result<Predicate, QueryError> {
+    let invalid = || QueryError::InvalidPriority(value.to_string());
+    let letter = |s: &str| {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_alphabetic() => Ok(c.to_ascii_uppercase()),
+            _ => Err(invalid()),
+        }
+    };
+    let (lo, hi) = if let Some((a, b)) = value.split_once('-') {
+        (letter(a)?, letter(b)?)
+    } else {
+        let c = letter(value)?;
+        (c, c)
+    };
+    Ok(Predicate::Priority(lo.min(hi), lo.max(hi)))
+}
+
+fn parse_has(value: &str) -> Result<Predicate, QueryError> {
+    let field = match value {
+        "md" => HasField::Md,
+        "due" => HasField::Due,
+        "t" => HasField::Threshold,
+        "pri" => HasField::Priority,
+        "project" => HasField::Project,
+        "context" => HasField::Context,
+        _ => return Err(QueryError::UnknownHas(value.to_string())),
+    };
+    Ok(Predicate::Has(field))
+}
+
+/// `due<2026-11-01`, `t>=2026-05-01` and friends.
+fn parse_date_comparison(term: &str) -> Option<Result<Predicate, QueryError>> {
+    const OPS: [(&str, Cmp); 5] = [
+        ("<=", Cmp::Le),
+        (">=", Cmp::Ge),
+        ("<", Cmp::Lt),
+        (">", Cmp::Gt),
+        ("=", Cmp::Eq),
+    ];
+    let (field, rest) = if let Some(rest) = term.strip_prefix("due") {
+        (DateField::Due, rest)
+    } else if let Some(rest) = term.strip_prefix('t') {
+        (DateField::Threshold, rest)
+    } else {
+        return None;
+    };
+    let (cmp, value) = OPS
+        .iter()
+        .find_map(|(op, cmp)| rest.strip_prefix(op).map(|v| (*cmp, v)))?;
+    if value.is_empty() {
+        return Some(Err(QueryError::MissingValue(term.to_string())));
+    }
+    Some(parse_date(value).map(|date| Predicate::Date(field, cmp, date)))
+}
+
+fn parse_predicate(term: &str) -> Result<Predicate, QueryError> {
+    let missing = || QueryError::MissingValue(term.to_string());
+    if let Some(ctx) = term.strip_prefix('@') {
+        return if ctx.is_empty() {
+            Err(missing())
+        } else {
+            Ok(Predicate::Context(ctx.to_string()))
+        };
+    }
+    if let Some(project) = term.strip_prefix('+') {
+        return if project.is_empty() {
+            Err(missing())
+        } else {
+            Ok(Predicate::Project(project.to_string()))
+        };
+    }
+    if let Some(result) = parse_date_comparison(term) {
+        return result;
+    }
+    if let Some((key, value)) = term.split_once(':')
+        && !key.is_empty()
+    {
+        if value.is_empty() {
+            return Err(missing());
+        }
+        return match key {
+            "pri" => parse_priority(value),
+            "has" => parse_has(value),
+            _ => Ok(Predicate::KeyValue(key.to_string(), value.to_string())),
+        };
+    }
+    Ok(Predicate::Text(term.to_lowercase()))
+}
+
+fn eq_ignore_case(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        let terms = input
+            .split_whitespace()
+            .map(|raw| {
+                let (negated, term) = raw.strip_prefix('-').map_or((false, raw), |t| (true, t));
+                if term.is_empty() {
+                    return Err(QueryError::EmptyNegation);
+                }
+                Ok(Term {
+                    negated,
+                    predicate: parse_predicate(term)?,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            source: input.trim().to_string(),
+            terms,
+        })
+    }
+
+    /// The query text as typed, for display.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether `item` satisfies every term. `todotxt_dir` is used to look
+    /// up the detail md for `has:md`.
+    pub fn matches(&self, item: &Item, todotxt_dir: &str) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term.negated != Self::holds(&term.predicate, item, todotxt_dir))
+    }
+
+    fn holds(predicate: &Predicate, item: &Item, todotxt_dir: &str) -> bool {
+        match predicate {
+            Predicate::Context(c) => item.contexts.iter().any(|x| eq_ignore_case(x, c)),
+            Predicate::Project(p) => item.projects.iter().any(|x| eq_ignore_case(x, p)),
+            Predicate::Priority(lo, hi) => item.priority.is_some_and(|p| (*lo..=*hi).contains(&p)),
+            Predicate::Date(field, cmp, date) => {
+                let value = match field {
+                    DateField::Due => item.due_date(),
+                    DateField::Threshold => item.threshold_date(),
+                };
+                value.is_some_and(|v| cmp.holds(v, *date))
+            }
+            Predicate::Has(field) => match field {
+                HasField::Md => item
+                    .id
+                    .as_deref()
+                    .is_some_and(|id| std::path::Path::new(&md_path(todotxt_dir, id)).exists()),
+                HasField::Due => item.due_date().is_some(),
+                HasField::Threshold => item.threshold_date().is_some(),
+                HasField::Priority => item.priority.is_some(),
+                HasField::Project => !item.projects.is_empty(),
+                HasField::Context => !item.contexts.is_empty(),
+            },
+            Predicate::KeyValue(k, v) => item.key_values.get(k).is_some_and(|x| x == v),
+            Predicate::Text(text) => item.description.to_lowercase().contains(text.as_str()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(line: &str) -> Item {
+        Item::parse(line, 1)
+    }
+
+    fn q(input: &str) -> Query {
+        Query::parse(input).unwrap()
+    }
+
+    #[test]
+    fn test_parse_example_query() {
+        let query = q("@phone pri:A-B due<2026-11-01 -+someday has:md");
+        assert_eq!(query.terms.len(), 5);
+        assert_eq!(query.terms[1].predicate, Predicate::Priority('A', 'B'));
+        assert!(query.terms[3].negated);
+        assert_eq!(
+            query.terms[3].predicate,
+            Predicate::Project("someday".to_string())
+        );
+        assert_eq!(
+            query.source(),
+            "@phone pri:A-B due<2026-11-01 -+someday has:md"
+        );
+    }
+
+    #[test]
+    fn test_context_project_and_negation() {
+        let it = item("Call Bob +work @phone id:a");
+        assert!(q("@phone").matches(&it, ""));
+        assert!(q("+Work").matches(&it, ""));
+        assert!(!q("-@phone").matches(&it, ""));
+        assert!(q("-+someday").matches(&it, ""));
+        assert!(q("").matches(&it, ""));
+    }
+
+    #[test]
+    fn test_priority_range() {
+        let a = item("(A) Task id:a");
+        let c = item("(C) Task id:c");
+        let none = item("Task id:n");
+        assert!(q("pri:A-B").matches(&a, ""));
+        assert!(!q("pri:A-B").matches(&c, ""));
+        assert!(q("pri:b-a").matches(&a, ""), "reversed range is normalized");
+        assert!(q("pri:C").matches(&c, ""));
+        assert!(!q("pri:A-Z").matches(&none, ""));
+    }
+
+    #[test]
+    fn test_date_comparisons() {
+        let it = item("Task due:2026-10-31 t:2026-10-01 id:a");
+        assert!(q("due<2026-11-01").matches(&it, ""));
+        assert!(!q("due<2026-10-31").matches(&it, ""));
+        assert!(q("due<=2026-10-31").matches(&it, ""));
+        assert!(q("due=2026-10-31").matches(&it, ""));
+        assert!(q("t>2026-09-30").matches(&it, ""));
+        assert!(!q("t>=2026-10-02").matches(&it, ""));
+        assert!(!q("due<2026-11-01").matches(&item("No due id:b"), ""));
+    }
+
+    #[test]
+    fn test_has_and_key_value() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir_path = dir.path().to_str().unwrap();
+        std::fs::create_dir_all(dir.path().join("todos")).unwrap();
+        std::fs::write(dir.path().join("todos/with-md.md"), "# d\n").unwrap();
+
+        let with_md = item("(B) Task +p @c due:2026-01-01 owner:bob id:with-md");
+        let bare = item("Task id:bare");
+        for has in ["md", "due", "pri", "project", "context"] {
+            let query = q(&format!("has:{has}"));
+            assert!(query.matches(&with_md, dir_path), "has:{has}");
+            assert!(!query.matches(&bare, dir_path), "has:{has} on bare");
+        }
+        assert!(q("owner:bob").matches(&with_md, dir_path));
+        assert!(!q("owner:alice").matches(&with_md, dir_path));
+        assert!(q("-has:t").matches(&with_md, dir_path));
+    }
+
+    #[test]
+    fn test_plain_text_is_case_insensitive_substring() {
+        let it = item("Renew Passport id:a");
+        assert!(q("passport renew").matches(&it, ""));
+        assert!(!q("visa").matches(&it, ""));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Query::parse("-"), Err(QueryError::EmptyNegation));
+        assert_eq!(
+            Query::parse("@"),
+            Err(QueryError::MissingValue("@".to_string()))
+        );
+        assert_eq!(
+            Query::parse("pri:A-"),
+            Err(QueryError::InvalidPriority("A-".to_string()))
+        );
+        assert_eq!(
+            Query::parse("pri:AB"),
+            Err(QueryError::InvalidPriority("AB".to_string()))
+        );
+        assert_eq!(
+            Query::parse("due<tomorrowish"),
+            Err(QueryError::InvalidDate("tomorrowish".to_string()))
+        );
+        assert_eq!(
+            Query::parse("due<"),
+            Err(QueryError::MissingValue("due<".to_string()))
+        );
+        assert_eq!(
+            Query::parse("has:notes"),
+            Err(QueryError::UnknownHas("notes".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_error_messages_are_readable() {
+        let err = Query::parse("due<2026-13-01").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid date '2026-13-01' (expected YYYY-MM-DD)"
+        );
+    }
+}
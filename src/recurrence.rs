@@ -0,0 +1,176 @@
+use chrono::{Days, Months, NaiveDate};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+/// A parsed `rec:` tag: `rec:3d`, `rec:2w`, `rec:1m`, `rec:1y`, optionally
+/// prefixed with `+` for strict mode.
+///
+/// Normal recurrence counts from the completion date, so a late payment
+/// pushes the next one back. Strict recurrence counts from the old `due:`,
+/// keeping a fixed schedule however late the item was done.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Recurrence {
+    pub amount: u32,
+    pub unit: Unit,
+    pub strict: bool,
+}
+
+impl Recurrence {
+    pub fn parse(value: &str) -> Option<Self> {
+        let (strict, rest) = value
+            .strip_prefix('+')
+            .map_or((false, value), |rest| (true, rest));
+        let unit = match rest.chars().last()?.to_ascii_lowercase() {
+            'd' => Unit::Day,
+            'w' => Unit::Week,
+            'm' => Unit::Month,
+            'y' => Unit::Year,
+            _ => return None,
+        };
+        let amount: u32 = rest[..rest.len() - 1].parse().ok()?;
+        (amount > 0).then_some(Self {
+            amount,
+            unit,
+            strict,
+        })
+    }
+
+    /// `date` moved forward by the interval. Months and years clamp to the
+    /// end of a shorter month, so Jan 31 + 1m is Feb 28 (or 29) and
+    /// Feb 29 + 1y is Feb 28.
+    pub fn advance(self, date: NaiveDate) -> Option<NaiveDate> {
+        match self.unit {
+            Unit::Day => date.checked_add_days(Days::new(self.amount.into())),
+            Unit::Week => date.checked_add_days(Days::new(u64::from(self.amount) * 7)),
+            Unit::Month => date.checked_add_months(Months::new(self.amount)),
+            Unit::Year => date.checked_add_months(Months::new(self.amount.checked_mul(12)?)),
+        }
+    }
+}
+
+/// Dates of the next occurrence, given the current `due:`/`t:` and the
+/// completion date. Returns `(due, t)`; an item without either gets a due
+/// date one interval from `today`.
+pub fn next_dates(
+    rec: Recurrence,
+    due: Option<NaiveDate>,
+    threshold: Option<NaiveDate>,
+    today: NaiveDate,
+) -> Option<(Option<NaiveDate>, Option<NaiveDate>)> {
+    match (due, threshold) {
+        (Some(due), threshold) => {
+            let base = if rec.strict { due } else { today };
+            let new_due = rec.advance(base)?;
+            // Keep the gap between t: and due: so the item shows up as early
+            // before its deadline as it did this time
+            let new_t = threshold.map(|t| new_due - (due - t));
+            Some((Some(new_due), new_t))
+        }
+        (None, Some(t)) => {
+            let base = if rec.strict { t } else { today };
+            Some((None, Some(rec.advance(base)?)))
+        }
+        (None, None) => Some((Some(rec.advance(today)?), None)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Recurrence::parse("1m"),
+            Some(Recurrence {
+                amount: 1,
+                unit: Unit::Month,
+                strict: false
+            })
+        );
+        assert_eq!(
+            Recurrence::parse("+2W"),
+            Some(Recurrence {
+                amount: 2,
+                unit: Unit::Week,
+                strict: true
+            })
+        );
+        assert_eq!(Recurrence::parse("10d").unwrap().amount, 10);
+        assert!(Recurrence::parse("m").is_none());
+        assert!(Recurrence::parse("0d").is_none());
+        assert!(Recurrence::parse("1q").is_none());
+        assert!(Recurrence::parse("+").is_none());
+        assert!(Recurrence::parse("").is_none());
+    }
+
+    #[test]
+    fn test_advance_days_and_weeks() {
+        let rec = Recurrence::parse("3d").unwrap();
+        assert_eq!(rec.advance(date("2026-12-30")), Some(date("2027-01-02")));
+        let rec = Recurrence::parse("2w").unwrap();
+        assert_eq!(rec.advance(date("2026-02-20")), Some(date("2026-03-06")));
+    }
+
+    #[test]
+    fn test_advance_month_end_clamps() {
+        let rec = Recurrence::parse("1m").unwrap();
+        assert_eq!(rec.advance(date("2026-01-31")), Some(date("2026-02-28")));
+        assert_eq!(rec.advance(date("2028-01-31")), Some(date("2028-02-29")));
+        assert_eq!(rec.advance(date("2026-03-31")), Some(date("2026-04-30")));
+        assert_eq!(rec.advance(date("2026-12-15")), Some(date("2027-01-15")));
+    }
+
+    #[test]
+    fn test_advance_leap_years() {
+        let rec = Recurrence::parse("1y").unwrap();
+        assert_eq!(rec.advance(date("2028-02-29")), Some(date("2029-02-28")));
+        let rec = Recurrence::parse("4y").unwrap();
+        assert_eq!(rec.advance(date("2028-02-29")), Some(date("2032-02-29")));
+        let rec = Recurrence::parse("1d").unwrap();
+        assert_eq!(rec.advance(date("2028-02-28")), Some(date("2028-02-29")));
+    }
+
+    #[test]
+    fn test_next_dates_normal_counts_from_today() {
+        let rec = Recurrence::parse("1m").unwrap();
+        let next = next_dates(
+            rec,
+            Some(date("2026-11-01")),
+            Some(date("2026-10-25")),
+            date("2026-11-05"),
+        );
+        assert_eq!(
+            next,
+            Some((Some(date("2026-12-05")), Some(date("2026-11-28"))))
+        );
+    }
+
+    #[test]
+    fn test_next_dates_strict_counts_from_due() {
+        let rec = Recurrence::parse("+1m").unwrap();
+        let next = next_dates(rec, Some(date("2026-01-31")), None, date("2026-02-10"));
+        assert_eq!(next, Some((Some(date("2026-02-28")), None)));
+    }
+
+    #[test]
+    fn test_next_dates_without_due() {
+        let rec = Recurrence::parse("+1w").unwrap();
+        let next = next_dates(rec, None, Some(date("2026-10-01")), date("2026-10-16"));
+        assert_eq!(next, Some((None, Some(date("2026-10-08")))));
+
+        let rec = Recurrence::parse("1w").unwrap();
+        let next = next_dates(rec, None, None, date("2026-10-16"));
+        assert_eq!(next, Some((Some(date("2026-10-23")), None)));
+    }
+}
@@ -0,0 +1,122 @@
+use crate::recurrence::Recurrence;
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+
+/// Tags whose values are dates and get resolved on input.
+const DATE_KEYS: &[&str] = &["due", "t"];
+
+/// Resolve a date as typed by the user: ISO `YYYY-MM-DD`, `today`/`tod`,
+/// `tomorrow`/`tom`, `yesterday`, a weekday (`fri`, `friday`: the next one
+/// after today), `eow`/`eom`/`eoy` (end of week, month, year), or an offset
+/// `3d`, `+2w`, `1m`, `1y` from today.
+pub fn resolve(value: &str, today: NaiveDate) -> Option<NaiveDate> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(date);
+    }
+    let value = value.to_ascii_lowercase();
+    match value.as_str() {
+        "today" | "tod" => return Some(today),
+        "tomorrow" | "tom" => return today.succ_opt(),
+        "yesterday" => return today.pred_opt(),
+        "eow" => return Some(next_weekday(today, Weekday::Sun, true)),
+        "eom" => {
+            let first_of_next = NaiveDate::from_ymd_opt(today.year(), today.month(), 1)?
+                .checked_add_months(chrono::Months::new(1))?;
+            return first_of_next.pred_opt();
+        }
+        "eoy" => return NaiveDate::from_ymd_opt(today.year(), 12, 31),
+        _ => {}
+    }
+    if let Ok(weekday) = value.parse::<Weekday>() {
+        return Some(next_weekday(today, weekday, false));
+    }
+    Recurrence::parse(&value)?.advance(today)
+}
+
+/// The first `weekday` after `today`, or `today` itself when it already is
+/// that day and `inclusive` is set.
+fn next_weekday(today: NaiveDate, weekday: Weekday, inclusive: bool) -> NaiveDate {
+    let ahead = (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+    let ahead = if ahead == 0 && !inclusive { 7 } else { ahead };
+    today + Days::new(ahead.into())
+}
+
+/// Rewrite relative `due:`/`t:` values in a todo.txt line to `YYYY-MM-DD`.
+/// Values that cannot be resolved are left as typed.
+pub fn resolve_line(line: &str, today: NaiveDate) -> String {
+    line.split(' ')
+        .map(|word| {
+            word.split_once(':')
+                .filter(|(key, _)| DATE_KEYS.contains(key))
+                .and_then(|(key, value)| Some(format!("{key}:{}", resolve(value, today)?)))
+                .unwrap_or_else(|| word.to_string())
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    // 2026-10-16 is a Friday
+    const TODAY: &str = "2026-10-16";
+
+    #[test]
+    fn test_resolve_keywords() {
+        let today = date(TODAY);
+        assert_eq!(resolve("today", today), Some(today));
+        assert_eq!(resolve("Tomorrow", today), Some(date("2026-10-17")));
+        assert_eq!(resolve("tom", today), Some(date("2026-10-17")));
+        assert_eq!(resolve("yesterday", today), Some(date("2026-10-15")));
+        assert_eq!(resolve("eow", today), Some(date("2026-10-18")));
+        assert_eq!(resolve("eom", today), Some(date("2026-10-31")));
+        assert_eq!(resolve("eom", date("2028-02-10")), Some(date("2028-02-29")));
+        assert_eq!(resolve("eoy", today), Some(date("2026-12-31")));
+    }
+
+    #[test]
+    fn test_resolve_weekdays_are_strictly_after_today() {
+        let today = date(TODAY);
+        assert_eq!(resolve("fri", today), Some(date("2026-10-23")));
+        assert_eq!(resolve("mon", today), Some(date("2026-10-19")));
+        assert_eq!(resolve("Saturday", today), Some(date("2026-10-17")));
+        assert_eq!(resolve("eow", date("2026-10-18")), Some(date("2026-10-18")));
+    }
+
+    #[test]
+    fn test_resolve_offsets() {
+        let today = date(TODAY);
+        assert_eq!(resolve("+3d", today), Some(date("2026-10-19")));
+        assert_eq!(resolve("2w", today), Some(date("2026-10-30")));
+        assert_eq!(resolve("1m", date("2026-01-31")), Some(date("2026-02-28")));
+        assert_eq!(resolve("1y", today), Some(date("2027-10-16")));
+    }
+
+    #[test]
+    fn test_resolve_iso_and_garbage() {
+        let today = date(TODAY);
+        assert_eq!(resolve("2026-12-24", today), Some(date("2026-12-24")));
+        assert_eq!(resolve("someday", today), None);
+        assert_eq!(resolve("3x", today), None);
+    }
+
+    #[test]
+    fn test_resolve_line_rewrites_only_date_tags() {
+        let today = date(TODAY);
+        assert_eq!(
+            resolve_line(
+                "(A) Pay rent due:eom t:+3d rec:1m note:tomorrow due:later",
+                today
+            ),
+            "(A) Pay rent due:2026-10-31 t:2026-10-19 rec:1m note:tomorrow due:later"
+        );
+        assert_eq!(
+            resolve_line("See https://x.test/due:fri", today),
+            "See https://x.test/due:fri"
+        );
+    }
+}
@@ -4,14 +4,15 @@ use std::process;
 
 use crate::rpc_server;
 
-pub fn run_current() -> Result<(), Box<dyn std::error::Error>> {
-    let path = rpc_server::socket_path();
+/// Print the item selected in the TUI that serves `todotxt_dir`.
+pub fn run_current(todotxt_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let path = rpc_server::socket_path(todotxt_dir);
     let Ok(mut stream) = UnixStream::connect(&path) else {
         eprintln!("torudo is not running");
         process::exit(1);
     };
 
-    let payload = rpc_server::encode_request(1, rpc_server::METHOD_GET_CURRENT);
+    let payload = rpc_server::encode_request(1, rpc_server::METHOD_GET_CURRENT, vec![]);
     stream.write_all(&payload)?;
     stream.shutdown(std::net::Shutdown::Write)?;
 
@@ -25,8 +26,9 @@ pub fn run_current() -> Result<(), Box<dyn std::error::Error>> {
         process::exit(1);
     }
 
-    if let Some(content) = result {
-        println!("{content}");
+    if let Some(value) = result {
+        let json = rpc_server::msgpack_to_json(&value)?;
+        println!("{}", serde_json::to_string_pretty(&json)?);
     }
 
     Ok(())
@@ -1,38 +1,86 @@
+use clap::ValueEnum;
 use log::debug;
-use std::io::{Read, Write};
-use std::os::unix::net::UnixListener;
-use std::path::PathBuf;
+use std::io::{BufReader, Write};
+use std::net::Shutdown;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::thread;
 
+use crate::app_state::{AppEvent, AppState, View, ViewMode};
+use crate::list::{self, ListOptions, ListSource};
+use crate::mutate::{self, MutateError};
+use crate::query::Query;
 use crate::todo;
 
 pub const METHOD_GET_CURRENT: &str = "get_current";
+pub const METHOD_LIST: &str = "list";
+pub const METHOD_GET: &str = "get";
+pub const METHOD_ADD: &str = "add";
+pub const METHOD_COMPLETE: &str = "complete";
+pub const METHOD_DELETE: &str = "delete";
+pub const METHOD_SET_PRIORITY: &str = "set_priority";
+pub const METHOD_MOVE: &str = "move";
+pub const METHOD_SELECT: &str = "select";
+pub const METHOD_SET_MODE: &str = "set_mode";
+pub const METHOD_SUBSCRIBE: &str = "subscribe";
 
-const MAX_REQUEST_SIZE: usize = 4096;
+pub const EVENT_SELECTION_CHANGED: &str = "selection_changed";
+pub const EVENT_ITEM_COMPLETED: &str = "item_completed";
+pub const EVENT_MODE_CHANGED: &str = "mode_changed";
+pub const EVENT_FILE_RELOADED: &str = "file_reloaded";
 
-/// Return the torudo RPC socket path: `/tmp/torudo-{uid}.sock`
-pub fn socket_path() -> PathBuf {
+/// Replies and notifications queued for one client before it is considered
+/// stalled and disconnected.
+const OUTBOX_CAPACITY: usize = 256;
+
+/// Return the RPC socket path of the instance serving `todotxt_dir`:
+/// `$XDG_RUNTIME_DIR/torudo-{uid}-{hash}.sock`, or under `/tmp` when the
+/// runtime dir is unset.
+pub fn socket_path(todotxt_dir: &str) -> PathBuf {
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR").ok();
+    socket_path_in(runtime_dir.as_deref(), todotxt_dir)
+}
+
+fn socket_path_in(runtime_dir: Option<&str>, todotxt_dir: &str) -> PathBuf {
     let uid = unsafe { libc::getuid() };
-    PathBuf::from(format!("/tmp/torudo-{uid}.sock"))
+    let base = runtime_dir
+        .filter(|d| !d.is_empty() && Path::new(d).is_dir())
+        .unwrap_or("/tmp");
+    let canonical =
+        std::fs::canonicalize(todotxt_dir).unwrap_or_else(|_| PathBuf::from(todotxt_dir));
+    let hash = fnv1a(canonical.as_os_str().as_bytes());
+    Path::new(base).join(format!("torudo-{uid}-{hash:016x}.sock"))
+}
+
+/// FNV-1a, so the socket name stays the same across Rust releases (unlike
+/// `DefaultHasher`) and every torudo build finds the same instance.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
 }
 
 /// Encode a msgpack-rpc request: `[0, msgid, method, params]`
-pub fn encode_request(msgid: u32, method: &str) -> Vec<u8> {
+pub fn encode_request(msgid: u32, method: &str, params: Vec<rmpv::Value>) -> Vec<u8> {
     let request = rmpv::Value::Array(vec![
         rmpv::Value::Integer(0.into()),
         rmpv::Value::Integer(msgid.into()),
         rmpv::Value::String(method.into()),
-        rmpv::Value::Array(vec![]),
+        rmpv::Value::Array(params),
     ]);
     let mut buf = Vec::new();
     rmpv::encode::write_value(&mut buf, &request).expect("encode should not fail");
     buf
 }
 
-/// Decode a msgpack-rpc request: `[0, msgid, method, params]`
-pub fn decode_request(data: &[u8]) -> Result<(u32, String, rmpv::Value), String> {
-    let value =
-        rmpv::decode::read_value(&mut &data[..]).map_err(|e| format!("decode error: {e}"))?;
-    let arr = value.as_array().ok_or("expected array")?;
+/// Split an already framed msgpack value into `(msgid, method, params)`.
+fn parse_request(value: rmpv::Value) -> Result<(u32, String, rmpv::Value), String> {
+    let rmpv::Value::Array(mut arr) = value else {
+        return Err("expected array".to_string());
+    };
     if arr.len() != 4 {
         return Err(format!("expected 4 elements, got {}", arr.len()));
     }
@@ -43,19 +91,30 @@ pub fn decode_request(data: &[u8]) -> Result<(u32, String, rmpv::Value), String>
     #[allow(clippy::cast_possible_truncation)]
     let msgid = arr[1].as_u64().ok_or("invalid msgid")? as u32;
     let method = arr[2].as_str().ok_or("invalid method")?.to_string();
-    let params = arr[3].clone();
+    let params = arr.pop().unwrap_or(rmpv::Value::Nil);
     Ok((msgid, method, params))
 }
 
+/// Encode a msgpack-rpc notification: `[2, method, params]`
+pub fn encode_notification(method: &str, params: Vec<rmpv::Value>) -> Vec<u8> {
+    let notification = rmpv::Value::Array(vec![
+        rmpv::Value::Integer(2.into()),
+        rmpv::Value::String(method.into()),
+        rmpv::Value::Array(params),
+    ]);
+    let mut buf = Vec::new();
+    rmpv::encode::write_value(&mut buf, &notification).expect("encode should not fail");
+    buf
+}
+
 /// Encode a msgpack-rpc response: `[1, msgid, error, result]`
-pub fn encode_response(msgid: u32, error: Option<&str>, result: Option<&str>) -> Vec<u8> {
+pub fn encode_response(msgid: u32, error: Option<&str>, result: Option<rmpv::Value>) -> Vec<u8> {
     let error_val = error.map_or(rmpv::Value::Nil, |e| rmpv::Value::String(e.into()));
-    let result_val = result.map_or(rmpv::Value::Nil, |r| rmpv::Value::String(r.into()));
     let response = rmpv::Value::Array(vec![
         rmpv::Value::Integer(1.into()),
         rmpv::Value::Integer(msgid.into()),
         error_val,
-        result_val,
+        result.unwrap_or(rmpv::Value::Nil),
     ]);
     let mut buf = Vec::new();
     rmpv::encode::write_value(&mut buf, &response).expect("encode should not fail");
@@ -63,90 +122,252 @@ pub fn encode_response(msgid: u32, error: Option<&str>, result: Option<&str>) ->
 }
 
 /// Decode a msgpack-rpc response: `[1, msgid, error, result]`
-pub fn decode_response(data: &[u8]) -> Result<(Option<String>, Option<String>), String> {
+pub fn decode_response(data: &[u8]) -> Result<(Option<String>, Option<rmpv::Value>), String> {
     let value =
         rmpv::decode::read_value(&mut &data[..]).map_err(|e| format!("decode error: {e}"))?;
-    let arr = value.as_array().ok_or("expected array")?;
-    if arr.len() != 4 {
-        return Err(format!("expected 4 elements, got {}", arr.len()));
-    }
+    let mut arr = match value {
+        rmpv::Value::Array(arr) if arr.len() == 4 => arr,
+        rmpv::Value::Array(arr) => return Err(format!("expected 4 elements, got {}", arr.len())),
+        _ => return Err("expected array".to_string()),
+    };
+    let result = arr.pop().filter(|r| !r.is_nil());
     let error = if arr[2].is_nil() {
         None
     } else {
         Some(arr[2].as_str().unwrap_or("unknown error").to_string())
     };
-    let result = if arr[3].is_nil() {
-        None
-    } else {
-        Some(arr[3].as_str().unwrap_or("").to_string())
-    };
     Ok((error, result))
 }
 
+/// Convert a JSON value (e.g. from [`todo::item_to_value`]) to msgpack.
+pub fn json_to_msgpack(value: &serde_json::Value) -> Result<rmpv::Value, String> {
+    let bytes = rmp_serde::to_vec(value).map_err(|e| e.to_string())?;
+    rmpv::decode::read_value(&mut bytes.as_slice()).map_err(|e| e.to_string())
+}
+
+/// Convert a msgpack response value back to JSON for printing.
+pub fn msgpack_to_json(value: &rmpv::Value) -> Result<serde_json::Value, String> {
+    let mut buf = Vec::new();
+    rmpv::encode::write_value(&mut buf, value).map_err(|e| e.to_string())?;
+    rmp_serde::from_slice(&buf).map_err(|e| e.to_string())
+}
+
+/// A request read by a connection thread, answered on the main loop.
+struct Request {
+    msgid: u32,
+    method: String,
+    params: rmpv::Value,
+    conn: Connection,
+}
+
+/// Write side of a client connection. Messages are queued for the
+/// connection's writer thread, so the main loop never blocks on a client
+/// that stopped reading.
+#[derive(Clone)]
+struct Connection {
+    stream: Arc<UnixStream>,
+    outbox: SyncSender<Vec<u8>>,
+}
+
+impl Connection {
+    /// Queue `payload` for the client. When the client is gone or its queue
+    /// is full, the connection is shut down and false is returned.
+    fn send(&self, payload: Vec<u8>) -> bool {
+        if self.outbox.try_send(payload).is_ok() {
+            return true;
+        }
+        let _ = self.stream.shutdown(Shutdown::Both);
+        false
+    }
+
+    fn same(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.stream, &other.stream)
+    }
+}
+
+/// The torudo socket. Accepting and reading happen on background threads
+/// (one per connection, each framing any number of requests off the
+/// stream); every call is handed to the main loop through a channel so it
+/// runs against the same `AppState` the UI draws.
 pub struct RpcServer {
-    listener: UnixListener,
     path: PathBuf,
-    todotxt_dir: String,
+    requests: Receiver<Request>,
+    subscribers: Vec<Connection>,
+    last_selection: Option<String>,
+    last_mode: Option<ViewMode>,
 }
 
 impl RpcServer {
     pub fn new(todotxt_dir: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let path = socket_path();
-        let _ = std::fs::remove_file(&path);
+        Self::bind(socket_path(todotxt_dir))
+    }
+
+    /// Listen on `path`, replacing a stale socket file left by a crash. Fails
+    /// if another instance still answers on it.
+    pub fn bind(path: PathBuf) -> Result<Self, Box<dyn std::error::Error>> {
+        if path.exists() {
+            if UnixStream::connect(&path).is_ok() {
+                return Err(
+                    format!("another torudo is already listening on {}", path.display()).into(),
+                );
+            }
+            std::fs::remove_file(&path)?;
+        }
         let listener = UnixListener::bind(&path)?;
-        listener.set_nonblocking(true)?;
         debug!("RPC server listening on {}", path.display());
+        let (tx, requests) = mpsc::channel();
+        thread::spawn(move || accept_loop(&listener, &tx));
         Ok(Self {
-            listener,
             path,
-            todotxt_dir: todotxt_dir.to_string(),
+            requests,
+            subscribers: Vec::new(),
+            last_selection: None,
+            last_mode: None,
         })
     }
 
-    /// Poll for incoming RPC requests (non-blocking).
-    pub fn poll(&self, current_todo: Option<&todo::Item>) {
-        let stream = match self.listener.accept() {
-            Ok((stream, _)) => stream,
-            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => return,
-            Err(e) => {
-                debug!("RPC accept error: {e}");
-                return;
-            }
+    /// Push whatever changed in `state` since the last poll to the
+    /// subscribers, then answer every request queued by the connection
+    /// threads. Mutations are applied to `state` right away instead of
+    /// waiting for the file watcher; their notifications go out on the next
+    /// poll. Returns the number of requests answered.
+    pub fn poll(&mut self, state: &mut AppState) -> usize {
+        self.publish(state);
+        let mut handled = 0;
+        while let Ok(request) = self.requests.try_recv() {
+            self.handle_request(request, state);
+            handled += 1;
+        }
+        handled
+    }
+
+    fn handle_request(&mut self, request: Request, state: &mut AppState) {
+        let Request {
+            msgid,
+            method,
+            params,
+            conn,
+        } = request;
+        debug!("RPC request: method={method}, msgid={msgid}");
+
+        let result = if method == METHOD_SUBSCRIBE {
+            snapshot(state)
+        } else {
+            dispatch(&method, &params, state)
+        };
+        let response = match result {
+            Ok(result) => encode_response(msgid, None, Some(result)),
+            Err(e) => encode_response(msgid, Some(&e), None),
         };
-        Self::handle_connection(stream, current_todo, &self.todotxt_dir);
+        if !conn.send(response) {
+            return;
+        }
+        if method == METHOD_SUBSCRIBE && !self.subscribers.iter().any(|s| s.same(&conn)) {
+            self.subscribers.push(conn);
+        }
     }
 
-    fn handle_connection(
-        mut stream: std::os::unix::net::UnixStream,
-        current_todo: Option<&todo::Item>,
-        todotxt_dir: &str,
-    ) {
-        let _ = stream.set_read_timeout(Some(std::time::Duration::from_millis(500)));
+    /// Drain queued events and diff the selection and mode against the last
+    /// poll, notifying every subscriber.
+    fn publish(&mut self, state: &mut AppState) {
+        let mut notifications = Vec::new();
+        for event in state.events.drain(..) {
+            match event {
+                AppEvent::ItemCompleted(item) => {
+                    if let Ok(value) = item_value(&item, &state.todotxt_dir) {
+                        notifications.push((EVENT_ITEM_COMPLETED, value));
+                    }
+                }
+                AppEvent::FileReloaded(path) => notifications.push((
+                    EVENT_FILE_RELOADED,
+                    rmpv::Value::Map(vec![("path".into(), path.into())]),
+                )),
+            }
+        }
+        if self.last_mode != Some(state.view_mode) {
+            self.last_mode = Some(state.view_mode);
+            notifications.push((EVENT_MODE_CHANGED, mode_value(state.view_mode)));
+        }
+        let selection = state.get_current_todo_id().map(str::to_string);
+        if self.last_selection != selection {
+            self.last_selection = selection;
+            let value = state
+                .get_current_todo()
+                .and_then(|item| item_value(item, &state.todotxt_dir).ok())
+                .unwrap_or(rmpv::Value::Nil);
+            notifications.push((EVENT_SELECTION_CHANGED, value));
+        }
 
-        let mut buf = [0u8; MAX_REQUEST_SIZE];
-        let Ok(n) = stream.read(&mut buf) else {
+        if self.subscribers.is_empty() {
             return;
-        };
+        }
+        for (method, value) in notifications {
+            let payload = encode_notification(method, vec![value]);
+            // A subscriber that went away or fell too far behind is dropped
+            self.subscribers.retain(|conn| conn.send(payload.clone()));
+        }
+    }
+}
+
+fn accept_loop(listener: &UnixListener, tx: &Sender<Request>) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let tx = tx.clone();
+                thread::spawn(move || read_requests(stream, &tx));
+            }
+            Err(e) => debug!("RPC accept error: {e}"),
+        }
+    }
+}
 
-        let (msgid, method, _params) = match decode_request(&buf[..n]) {
-            Ok(v) => v,
+/// Read msgpack-rpc requests off one connection until the client closes it
+/// or sends something that is not msgpack. Writing happens on a second
+/// thread that drains the connection's outbox.
+fn read_requests(stream: UnixStream, tx: &Sender<Request>) {
+    let Ok(read_half) = stream.try_clone() else {
+        return;
+    };
+    let stream = Arc::new(stream);
+    let (outbox, queued) = mpsc::sync_channel(OUTBOX_CAPACITY);
+    let writer = Arc::clone(&stream);
+    thread::spawn(move || write_messages(&writer, &queued));
+    let conn = Connection { stream, outbox };
+    let mut reader = BufReader::new(read_half);
+    loop {
+        let value = match rmpv::decode::read_value(&mut reader) {
+            Ok(value) => value,
             Err(e) => {
-                debug!("RPC decode error: {e}");
+                debug!("RPC connection closed: {e}");
                 return;
             }
         };
+        match parse_request(value) {
+            Ok((msgid, method, params)) => {
+                let request = Request {
+                    msgid,
+                    method,
+                    params,
+                    conn: conn.clone(),
+                };
+                if tx.send(request).is_err() {
+                    return;
+                }
+            }
+            Err(e) => debug!("RPC decode error: {e}"),
+        }
+    }
+}
 
-        debug!("RPC request: method={method}, msgid={msgid}");
-
-        let response = match method.as_str() {
-            METHOD_GET_CURRENT => match handle_get_current(current_todo, todotxt_dir) {
-                Ok(json) => encode_response(msgid, None, Some(&json)),
-                Err(e) => encode_response(msgid, Some(&e), None),
-            },
-            _ => encode_response(msgid, Some(&format!("unknown method: {method}")), None),
-        };
-
-        let _ = stream.write_all(&response);
+/// Write queued messages until every sender is gone or the client stops
+/// accepting them.
+fn write_messages(stream: &UnixStream, queued: &Receiver<Vec<u8>>) {
+    for payload in queued {
+        let mut stream = stream;
+        if stream.write_all(&payload).is_err() {
+            let _ = stream.shutdown(Shutdown::Both);
+            return;
+        }
     }
 }
 
@@ -157,31 +378,305 @@ impl Drop for RpcServer {
     }
 }
 
+/// Run one RPC method against the TUI state. Params are a single map, e.g.
+/// `[{"id": "abc"}]`.
+fn dispatch(
+    method: &str,
+    params: &rmpv::Value,
+    state: &mut AppState,
+) -> Result<rmpv::Value, String> {
+    match method {
+        METHOD_GET_CURRENT => handle_get_current(state.get_current_todo(), &state.todotxt_dir),
+        METHOD_LIST => handle_list(params, state),
+        METHOD_GET => {
+            let (_, item) = mutate::locate(&state.todotxt_dir, required_str(params, "id")?)
+                .map_err(|e| e.to_string())?;
+            item_value(&item, &state.todotxt_dir)
+        }
+        METHOD_ADD => {
+            let text = required_str(params, "text")?;
+            let mode = mode_param(params)?.unwrap_or(ViewMode::Inbox);
+            apply(state, |dir| mutate::add(dir, mode, text))
+        }
+        METHOD_COMPLETE => {
+            let id = required_str(params, "id")?;
+            let item = mutate::complete(&state.todotxt_dir, id).map_err(|e| e.to_string())?;
+            state
+                .events
+                .push(AppEvent::ItemCompleted(Box::new(item.clone())));
+            state.reload_keeping_selection();
+            item_value(&item, &state.todotxt_dir)
+        }
+        METHOD_DELETE => {
+            let id = required_str(params, "id")?;
+            apply(state, |dir| mutate::remove(dir, id))
+        }
+        METHOD_SET_PRIORITY => {
+            let id = required_str(params, "id")?;
+            let priority =
+                optional_str(params, "priority")?.map_or(Ok(None), mutate::parse_priority)?;
+            apply(state, |dir| mutate::prioritize(dir, id, priority))
+        }
+        METHOD_MOVE => {
+            let id = required_str(params, "id")?;
+            let mode = mode_param(params)?.ok_or("missing param: mode")?;
+            apply(state, |dir| mutate::send_to(dir, id, mode))
+        }
+        METHOD_SELECT => handle_select(params, state),
+        METHOD_SET_MODE => {
+            let mode = mode_param(params)?.ok_or("missing param: mode")?;
+            state.set_view_mode(mode);
+            Ok(mode_value(mode))
+        }
+        _ => Err(format!("unknown method: {method}")),
+    }
+}
+
+fn param<'a>(params: &'a rmpv::Value, key: &str) -> Option<&'a rmpv::Value> {
+    let map = params.as_array().and_then(|a| a.first()).unwrap_or(params);
+    map.as_map()?
+        .iter()
+        .find(|(k, _)| k.as_str() == Some(key))
+        .map(|(_, v)| v)
+}
+
+fn optional_str<'a>(params: &'a rmpv::Value, key: &str) -> Result<Option<&'a str>, String> {
+    match param(params, key) {
+        None | Some(rmpv::Value::Nil) => Ok(None),
+        Some(v) => v
+            .as_str()
+            .map(Some)
+            .ok_or_else(|| format!("param {key} must be a string")),
+    }
+}
+
+fn required_str<'a>(params: &'a rmpv::Value, key: &str) -> Result<&'a str, String> {
+    optional_str(params, key)?.ok_or_else(|| format!("missing param: {key}"))
+}
+
+fn mode_param(params: &rmpv::Value) -> Result<Option<ViewMode>, String> {
+    optional_str(params, "mode")?
+        .map(|s| ViewMode::from_str(s, true).map_err(|_| format!("unknown mode: {s}")))
+        .transpose()
+}
+
+fn mode_value(mode: ViewMode) -> rmpv::Value {
+    rmpv::Value::Map(vec![(
+        "mode".into(),
+        mode.label().to_ascii_lowercase().into(),
+    )])
+}
+
+/// Result of `subscribe`: the current mode and selection, so a subscriber
+/// starts in sync before the first notification arrives.
+fn snapshot(state: &AppState) -> Result<rmpv::Value, String> {
+    let selection = state
+        .get_current_todo()
+        .map(|item| item_value(item, &state.todotxt_dir))
+        .transpose()?
+        .unwrap_or(rmpv::Value::Nil);
+    let rmpv::Value::Map(mut map) = mode_value(state.view_mode) else {
+        unreachable!("mode_value returns a map");
+    };
+    map.push(("selection".into(), selection));
+    Ok(rmpv::Value::Map(map))
+}
+
+fn item_value(item: &todo::Item, todotxt_dir: &str) -> Result<rmpv::Value, String> {
+    let json = todo::item_to_value(item, todotxt_dir).map_err(|e| e.to_string())?;
+    json_to_msgpack(&json)
+}
+
+/// Run a file mutation, then reload the TUI so the change shows up before
+/// the file watcher notices it.
+fn apply(
+    state: &mut AppState,
+    op: impl FnOnce(&str) -> Result<todo::Item, MutateError>,
+) -> Result<rmpv::Value, String> {
+    let item = op(&state.todotxt_dir).map_err(|e| e.to_string())?;
+    state.reload_keeping_selection();
+    item_value(&item, &state.todotxt_dir)
+}
+
 fn handle_get_current(
     current_todo: Option<&todo::Item>,
     todotxt_dir: &str,
-) -> Result<String, String> {
+) -> Result<rmpv::Value, String> {
     let item = current_todo.ok_or("no todo selected")?;
-    todo::item_to_json(item, todotxt_dir).map_err(|e| e.to_string())
+    item_value(item, todotxt_dir)
+}
+
+/// `list {mode?, project?, filter?}`; `mode` defaults to the TUI's current
+/// mode and also accepts `done`.
+fn handle_list(params: &rmpv::Value, state: &AppState) -> Result<rmpv::Value, String> {
+    let source = match optional_str(params, "mode")? {
+        Some(s) => ListSource::from_str(s, true).map_err(|_| format!("unknown mode: {s}"))?,
+        None => state.view_mode.into(),
+    };
+    let query = optional_str(params, "filter")?
+        .map(Query::parse)
+        .transpose()
+        .map_err(|e| format!("invalid filter: {e}"))?;
+    let opts = ListOptions {
+        source,
+        project: optional_str(params, "project")?,
+        filter: query.as_ref(),
+        format: list::ListFormat::Json,
+    };
+    let items = list::collect_items(&state.todotxt_dir, &opts).map_err(|e| e.to_string())?;
+    items
+        .iter()
+        .map(|item| item_value(item, &state.todotxt_dir))
+        .collect::<Result<_, _>>()
+        .map(rmpv::Value::Array)
+}
+
+/// `select {id}`: switch to the item's mode and put the cursor on it.
+fn handle_select(params: &rmpv::Value, state: &mut AppState) -> Result<rmpv::Value, String> {
+    let id = required_str(params, "id")?;
+    let (mode, _) = mutate::locate(&state.todotxt_dir, id).map_err(|e| e.to_string())?;
+    // The agenda already shows every mode, so only switch when it is not there
+    if !(state.view == View::Agenda && state.select_todo_by_id(id)) {
+        state.set_view_mode(mode);
+        if !state.select_todo_by_id(id) {
+            return Err(format!("id:{id} is hidden by the active search or filter"));
+        }
+    }
+    handle_get_current(state.get_current_todo(), &state.todotxt_dir)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::Read;
+    use std::path::Path;
+    use std::time::Duration;
+
+    fn setup() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("todo.txt"),
+            "(A) Call Bob +work id:t1\nWrite report +work id:t2\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("waiting.txt"), "Parcel +home id:w1\n").unwrap();
+        dir
+    }
+
+    fn make_state(dir: &tempfile::TempDir) -> AppState {
+        let dir_path = dir.path().to_str().unwrap().to_string();
+        let todos = todo::load_todos(&format!("{dir_path}/todo.txt")).unwrap();
+        let mut state = AppState::new(todos, "/tmp/nonexistent-nvim.sock".to_string(), dir_path);
+        state.crmux_version = None;
+        state.claude_available = false;
+        state
+    }
+
+    fn params(pairs: &[(&str, &str)]) -> rmpv::Value {
+        rmpv::Value::Array(vec![rmpv::Value::Map(
+            pairs
+                .iter()
+                .map(|(k, v)| ((*k).into(), (*v).into()))
+                .collect(),
+        )])
+    }
+
+    fn call(state: &mut AppState, method: &str, pairs: &[(&str, &str)]) -> serde_json::Value {
+        let value = dispatch(method, &params(pairs), state).unwrap();
+        msgpack_to_json(&value).unwrap()
+    }
+
+    fn decode_request(data: &[u8]) -> Result<(u32, String, rmpv::Value), String> {
+        let value = rmpv::decode::read_value(&mut &data[..]).map_err(|e| e.to_string())?;
+        parse_request(value)
+    }
+
+    fn test_server(dir: &tempfile::TempDir, name: &str) -> (RpcServer, PathBuf) {
+        let sock_path = dir.path().join(name);
+        let server = RpcServer::bind(sock_path.clone()).unwrap();
+        (server, sock_path)
+    }
+
+    fn connect(sock_path: &Path) -> UnixStream {
+        let client = UnixStream::connect(sock_path).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+        client
+    }
+
+    /// Poll until `n` requests have been answered; they arrive from the
+    /// connection threads asynchronously.
+    fn serve(server: &mut RpcServer, state: &mut AppState, n: usize) {
+        let deadline = std::time::Instant::now() + Duration::from_secs(2);
+        let mut handled = 0;
+        while handled < n {
+            assert!(std::time::Instant::now() < deadline, "timed out serving");
+            handled += server.poll(state);
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    fn read_message(client: &mut UnixStream) -> Vec<rmpv::Value> {
+        match rmpv::decode::read_value(client).unwrap() {
+            rmpv::Value::Array(arr) => arr,
+            other => panic!("expected array, got {other}"),
+        }
+    }
 
     #[test]
-    fn test_socket_path() {
-        let path = socket_path();
+    fn test_socket_path_is_per_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a");
+        let b = dir.path().join("b");
+        std::fs::create_dir(&a).unwrap();
+        std::fs::create_dir(&b).unwrap();
+        let runtime = dir.path().to_str().unwrap();
+        let a_str = a.to_str().unwrap();
+
+        let path = socket_path_in(Some(runtime), a_str);
+        assert_eq!(path.parent(), Some(dir.path()));
         let uid = unsafe { libc::getuid() };
-        assert_eq!(path, PathBuf::from(format!("/tmp/torudo-{uid}.sock")));
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with(&format!("torudo-{uid}-")));
+        assert_eq!(
+            path,
+            socket_path_in(Some(runtime), &format!("{a_str}/../a/")),
+            "same directory spelled differently"
+        );
+        assert_ne!(path, socket_path_in(Some(runtime), b.to_str().unwrap()));
+    }
+
+    #[test]
+    fn test_socket_path_falls_back_to_tmp() {
+        let path = socket_path_in(None, "/nonexistent/todotxt");
+        assert_eq!(path.parent(), Some(Path::new("/tmp")));
+        let path = socket_path_in(Some(""), "/nonexistent/todotxt");
+        assert_eq!(path.parent(), Some(Path::new("/tmp")));
+    }
+
+    #[test]
+    fn test_bind_refuses_live_socket_and_replaces_stale_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let live = dir.path().join("live.sock");
+        let _server = RpcServer::bind(live.clone()).unwrap();
+        let err = RpcServer::bind(live.clone()).err().unwrap();
+        assert!(err.to_string().contains("already listening"));
+        assert!(live.exists(), "live socket is left alone");
+
+        let stale = dir.path().join("stale.sock");
+        drop(UnixListener::bind(&stale).unwrap());
+        assert!(stale.exists());
+        assert!(RpcServer::bind(stale).is_ok());
     }
 
     #[test]
     fn test_encode_decode_response_success() {
-        let encoded = encode_response(42, None, Some("hello world"));
-        let (error, result) = decode_response(&encoded).unwrap();
+        let result = rmpv::Value::Map(vec![("title".into(), "hello world".into())]);
+        let encoded = encode_response(42, None, Some(result.clone()));
+        let (error, decoded) = decode_response(&encoded).unwrap();
         assert!(error.is_none());
-        assert_eq!(result.unwrap(), "hello world");
+        assert_eq!(decoded.unwrap(), result);
     }
 
     #[test]
@@ -194,17 +689,11 @@ mod tests {
 
     #[test]
     fn test_encode_decode_request_roundtrip() {
-        let encoded = encode_request(1, METHOD_GET_CURRENT);
-        let (msgid, method, params) = decode_request(&encoded).unwrap();
+        let encoded = encode_request(1, METHOD_GET, vec![params(&[("id", "t1")])[0].clone()]);
+        let (msgid, method, decoded) = decode_request(&encoded).unwrap();
         assert_eq!(msgid, 1);
-        assert_eq!(method, METHOD_GET_CURRENT);
-        assert!(params.as_array().unwrap().is_empty());
-    }
-
-    #[test]
-    fn test_decode_request_invalid_bytes() {
-        let result = decode_request(&[0xff, 0xff]);
-        assert!(result.is_err());
+        assert_eq!(method, METHOD_GET);
+        assert_eq!(optional_str(&decoded, "id"), Ok(Some("t1")));
     }
 
     #[test]
@@ -232,7 +721,7 @@ mod tests {
 
         let item = todo::Item::parse("(A) My task +project @home id:abc-123", 0);
         let result = handle_get_current(Some(&item), dir.path().to_str().unwrap()).unwrap();
-        let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+        let json = msgpack_to_json(&result).unwrap();
 
         assert_eq!(json["title"], "My task");
         assert_eq!(json["priority"], "A");
@@ -251,7 +740,7 @@ mod tests {
 
         let item = todo::Item::parse("Simple task id:xyz-789", 0);
         let result = handle_get_current(Some(&item), dir.path().to_str().unwrap()).unwrap();
-        let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+        let json = msgpack_to_json(&result).unwrap();
 
         assert_eq!(json["title"], "Simple task");
         assert_eq!(json["id"], "xyz-789");
@@ -266,69 +755,297 @@ mod tests {
     }
 
     #[test]
-    fn test_rpc_roundtrip() {
-        use std::io::{Read, Write};
-        use std::os::unix::net::UnixStream;
+    fn test_list_and_get() {
+        let dir = setup();
+        let mut state = make_state(&dir);
 
-        let dir = tempfile::tempdir().unwrap();
+        let items = call(&mut state, METHOD_LIST, &[]);
+        assert_eq!(items.as_array().unwrap().len(), 2);
+        let items = call(&mut state, METHOD_LIST, &[("mode", "waiting")]);
+        assert_eq!(items[0]["id"], "w1");
+        let items = call(&mut state, METHOD_LIST, &[("filter", "pri:A")]);
+        assert_eq!(items.as_array().unwrap().len(), 1);
+
+        let item = call(&mut state, METHOD_GET, &[("id", "w1")]);
+        assert_eq!(item["title"], "Parcel");
+        let err = dispatch(METHOD_GET, &params(&[("id", "nope")]), &mut state).unwrap_err();
+        assert!(err.contains("id:nope"));
+    }
+
+    #[test]
+    fn test_mutations_update_state_immediately() {
+        let dir = setup();
+        let mut state = make_state(&dir);
+        state.select_todo_by_id("t2");
+
+        let item = call(
+            &mut state,
+            METHOD_ADD,
+            &[("mode", "todo"), ("text", "New +work")],
+        );
+        let new_id = item["id"].as_str().unwrap().to_string();
+        assert!(state.todos.iter().any(|t| t.id.as_deref() == Some(&new_id)));
+        assert_eq!(state.get_current_todo_id(), Some("t2"), "cursor stays put");
+
+        let item = call(
+            &mut state,
+            METHOD_SET_PRIORITY,
+            &[("id", "t2"), ("priority", "b")],
+        );
+        assert_eq!(item["priority"], "B");
+        assert_eq!(state.get_current_todo().unwrap().priority, Some('B'));
+
+        call(&mut state, METHOD_COMPLETE, &[("id", "t1")]);
+        assert!(!state.todos.iter().any(|t| t.id.as_deref() == Some("t1")));
+
+        call(
+            &mut state,
+            METHOD_MOVE,
+            &[("id", "t2"), ("mode", "someday")],
+        );
+        assert!(!state.todos.iter().any(|t| t.id.as_deref() == Some("t2")));
+
+        let item = call(&mut state, METHOD_DELETE, &[("id", &new_id)]);
+        assert_eq!(item["title"], "New");
+        assert!(state.todos.is_empty());
+    }
+
+    #[test]
+    fn test_select_switches_mode_and_moves_cursor() {
+        let dir = setup();
+        let mut state = make_state(&dir);
+
+        let item = call(&mut state, METHOD_SELECT, &[("id", "w1")]);
+        assert_eq!(item["id"], "w1");
+        assert_eq!(state.view_mode, ViewMode::Waiting);
+        assert_eq!(state.get_current_todo_id(), Some("w1"));
+
+        let result = call(&mut state, METHOD_SET_MODE, &[("mode", "todo")]);
+        assert_eq!(result["mode"], "todo");
+        assert_eq!(state.view_mode, ViewMode::Todo);
+    }
+
+    #[test]
+    fn test_missing_and_invalid_params() {
+        let dir = setup();
+        let mut state = make_state(&dir);
+        let err = dispatch(METHOD_COMPLETE, &rmpv::Value::Array(vec![]), &mut state).unwrap_err();
+        assert_eq!(err, "missing param: id");
+        let err = dispatch(METHOD_SET_MODE, &params(&[("mode", "later")]), &mut state).unwrap_err();
+        assert_eq!(err, "unknown mode: later");
+    }
+
+    #[test]
+    fn test_rpc_roundtrip() {
+        let dir = setup();
         let todos_dir = dir.path().join("todos");
         std::fs::create_dir(&todos_dir).unwrap();
-        std::fs::write(todos_dir.join("test-id.md"), "# Test Content").unwrap();
-
-        let sock_path = dir.path().join("test.sock");
-        let listener = UnixListener::bind(&sock_path).unwrap();
-        listener.set_nonblocking(true).unwrap();
-        let server = RpcServer {
-            listener,
-            path: sock_path.clone(),
-            todotxt_dir: dir.path().to_str().unwrap().to_string(),
-        };
-
-        let item = todo::Item::parse("Test todo id:test-id", 0);
+        std::fs::write(todos_dir.join("t1.md"), "# Test Content").unwrap();
+        let mut state = make_state(&dir);
+        let (mut server, sock_path) = test_server(&dir, "test.sock");
 
-        let mut client = UnixStream::connect(&sock_path).unwrap();
-        let req_buf = encode_request(42, METHOD_GET_CURRENT);
+        let mut client = connect(&sock_path);
+        let req_buf = encode_request(42, METHOD_GET_CURRENT, vec![]);
         client.write_all(&req_buf).unwrap();
         client.shutdown(std::net::Shutdown::Write).unwrap();
 
-        server.poll(Some(&item));
+        serve(&mut server, &mut state, 1);
 
         let mut resp_buf = Vec::new();
         client.read_to_end(&mut resp_buf).unwrap();
         let (error, result) = decode_response(&resp_buf).unwrap();
         assert!(error.is_none());
-        let json: serde_json::Value = serde_json::from_str(&result.unwrap()).unwrap();
-        assert_eq!(json["title"], "Test todo");
+        let json = msgpack_to_json(&result.unwrap()).unwrap();
+        assert_eq!(json["title"], "Call Bob");
         assert_eq!(json["md"], "# Test Content");
     }
 
     #[test]
     fn test_rpc_unknown_method() {
-        use std::io::{Read, Write};
-        use std::os::unix::net::UnixStream;
+        let dir = setup();
+        let mut state = make_state(&dir);
+        let (mut server, sock_path) = test_server(&dir, "test2.sock");
 
-        let dir = tempfile::tempdir().unwrap();
-        let sock_path = dir.path().join("test2.sock");
-        let listener = UnixListener::bind(&sock_path).unwrap();
-        listener.set_nonblocking(true).unwrap();
-        let server = RpcServer {
-            listener,
-            path: sock_path.clone(),
-            todotxt_dir: dir.path().to_str().unwrap().to_string(),
-        };
+        let mut client = connect(&sock_path);
+        client
+            .write_all(&encode_request(1, "nonexistent", vec![]))
+            .unwrap();
+        serve(&mut server, &mut state, 1);
 
-        let item = todo::Item::parse("x id:x", 0);
+        let response = read_message(&mut client);
+        assert!(response[2].as_str().unwrap().contains("unknown method"));
+    }
 
-        let mut client = UnixStream::connect(&sock_path).unwrap();
-        let req_buf = encode_request(1, "nonexistent");
-        client.write_all(&req_buf).unwrap();
-        client.shutdown(std::net::Shutdown::Write).unwrap();
+    #[test]
+    fn test_pipelined_and_large_requests_on_one_connection() {
+        let dir = setup();
+        let mut state = make_state(&dir);
+        let (mut server, sock_path) = test_server(&dir, "test4.sock");
 
-        server.poll(Some(&item));
+        let long_text = format!("{} +work", "x".repeat(10_000));
+        let mut burst = encode_request(1, METHOD_GET, vec![params(&[("id", "t1")])[0].clone()]);
+        burst.extend(encode_request(
+            2,
+            METHOD_ADD,
+            vec![params(&[("mode", "todo"), ("text", &long_text)])[0].clone()],
+        ));
+        burst.extend(encode_request(3, METHOD_LIST, vec![]));
+        let mut client = connect(&sock_path);
+        client.write_all(&burst).unwrap();
+        serve(&mut server, &mut state, 3);
 
-        let mut resp_buf = Vec::new();
-        client.read_to_end(&mut resp_buf).unwrap();
-        let (error, _result) = decode_response(&resp_buf).unwrap();
-        assert!(error.unwrap().contains("unknown method"));
+        let responses: Vec<_> = (0..3).map(|_| read_message(&mut client)).collect();
+        let msgids: Vec<_> = responses.iter().map(|r| r[1].as_u64().unwrap()).collect();
+        assert_eq!(msgids, [1, 2, 3]);
+        assert!(responses.iter().all(|r| r[2].is_nil()));
+        assert_eq!(responses[2][3].as_array().unwrap().len(), 3);
+        assert!(state.todos.iter().any(|t| t.description.len() == 10_000));
+    }
+
+    #[test]
+    fn test_concurrent_clients_each_get_their_response() {
+        let dir = setup();
+        let mut state = make_state(&dir);
+        let (mut server, sock_path) = test_server(&dir, "test5.sock");
+
+        let mut clients: Vec<UnixStream> = (0..4).map(|_| connect(&sock_path)).collect();
+        for (i, client) in (0u32..).zip(&mut clients) {
+            let id = if i % 2 == 0 { "t1" } else { "w1" };
+            let request = encode_request(i, METHOD_GET, vec![params(&[("id", id)])[0].clone()]);
+            client.write_all(&request).unwrap();
+        }
+        serve(&mut server, &mut state, 4);
+
+        for (i, client) in (0u64..).zip(&mut clients) {
+            let response = read_message(client);
+            assert_eq!(response[1].as_u64(), Some(i));
+            let item = msgpack_to_json(&response[3]).unwrap();
+            assert_eq!(item["id"], if i % 2 == 0 { "t1" } else { "w1" });
+        }
+    }
+
+    #[test]
+    fn test_non_request_message_is_skipped() {
+        let dir = setup();
+        let mut state = make_state(&dir);
+        let (mut server, sock_path) = test_server(&dir, "test6.sock");
+
+        let mut buf = Vec::new();
+        rmpv::encode::write_value(&mut buf, &rmpv::Value::from("hello")).unwrap();
+        buf.extend(encode_request(7, METHOD_GET_CURRENT, vec![]));
+        let mut client = connect(&sock_path);
+        client.write_all(&buf).unwrap();
+        serve(&mut server, &mut state, 1);
+
+        assert_eq!(read_message(&mut client)[1].as_u64(), Some(7));
+    }
+
+    #[test]
+    fn test_subscribe_streams_notifications_to_every_subscriber() {
+        let dir = setup();
+        let mut state = make_state(&dir);
+        let (mut server, sock_path) = test_server(&dir, "test3.sock");
+        server.poll(&mut state);
+
+        let mut clients: Vec<UnixStream> = (0..2)
+            .map(|i| {
+                let mut client = connect(&sock_path);
+                client
+                    .write_all(&encode_request(i, METHOD_SUBSCRIBE, vec![]))
+                    .unwrap();
+                client
+            })
+            .collect();
+        serve(&mut server, &mut state, 2);
+
+        for client in &mut clients {
+            let response = read_message(client);
+            assert_eq!(response[0], rmpv::Value::from(1));
+            let snapshot = msgpack_to_json(&response[3]).unwrap();
+            assert_eq!(snapshot["mode"], "todo");
+            assert_eq!(snapshot["selection"]["id"], "t1");
+        }
+
+        state.handle_navigation_key('j');
+        server.poll(&mut state);
+        for client in &mut clients {
+            let msg = read_message(client);
+            assert_eq!(msg[0], rmpv::Value::from(2));
+            assert_eq!(msg[1].as_str(), Some(EVENT_SELECTION_CHANGED));
+            let item = msgpack_to_json(&msg[2].as_array().unwrap()[0]).unwrap();
+            assert_eq!(item["id"], "t2");
+        }
+
+        drop(clients.pop());
+        state.handle_complete_todo(&state.active_file());
+        state.set_view_mode(ViewMode::Waiting);
+        server.poll(&mut state);
+        let methods: Vec<String> = (0..5)
+            .map(|_| {
+                read_message(&mut clients[0])[1]
+                    .as_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(
+            methods,
+            [
+                EVENT_ITEM_COMPLETED,
+                EVENT_FILE_RELOADED,
+                EVENT_FILE_RELOADED,
+                EVENT_MODE_CHANGED,
+                EVENT_SELECTION_CHANGED,
+            ]
+        );
+        // The closed subscriber's writer fails and it is dropped on a later send
+        let deadline = std::time::Instant::now() + Duration::from_secs(2);
+        while server.subscribers.len() > 1 {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "closed subscriber kept"
+            );
+            state.handle_navigation_key('j');
+            state.handle_navigation_key('k');
+            server.poll(&mut state);
+            read_message(&mut clients[0]);
+            read_message(&mut clients[0]);
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_stalled_subscriber_never_blocks_the_main_loop() {
+        let dir = setup();
+        let mut state = make_state(&dir);
+        let (mut server, sock_path) = test_server(&dir, "test4.sock");
+        server.poll(&mut state);
+
+        // Subscribes, then never reads again
+        let mut stalled = connect(&sock_path);
+        stalled
+            .write_all(&encode_request(1, METHOD_SUBSCRIBE, vec![]))
+            .unwrap();
+        serve(&mut server, &mut state, 1);
+
+        let started = std::time::Instant::now();
+        while !server.subscribers.is_empty() {
+            assert!(
+                started.elapsed() < Duration::from_secs(10),
+                "stalled subscriber kept"
+            );
+            state.handle_navigation_key('j');
+            server.poll(&mut state);
+            state.handle_navigation_key('k');
+            server.poll(&mut state);
+        }
+
+        // Other clients are still answered
+        let mut client = connect(&sock_path);
+        client
+            .write_all(&encode_request(2, METHOD_GET_CURRENT, vec![]))
+            .unwrap();
+        serve(&mut server, &mut state, 1);
+        assert_eq!(read_message(&mut client)[1].as_u64(), Some(2));
     }
 }
@@ -0,0 +1,77 @@
+use crate::todo::Item;
+
+/// Text a search query is matched against: the description plus every
+/// `+project`, `@context` and `key:value` tag of the item.
+pub fn item_haystack(item: &Item) -> String {
+    let mut parts = vec![item.description.clone()];
+    parts.extend(item.projects.iter().map(|p| format!("+{p}")));
+    parts.extend(item.contexts.iter().map(|c| format!("@{c}")));
+    let mut tags: Vec<String> = item
+        .key_values
+        .iter()
+        .map(|(k, v)| format!("{k}:{v}"))
+        .collect();
+    tags.sort();
+    parts.extend(tags);
+    parts.join(" ")
+}
+
+/// Case-insensitive subsequence match: every char of `needle` appears in
+/// `haystack` in order, not necessarily adjacent.
+pub fn fuzzy_match(haystack: &str, needle: &str) -> bool {
+    let mut hay = haystack.chars().flat_map(char::to_lowercase);
+    needle
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|n| hay.any(|h| h == n))
+}
+
+/// Whether `item` matches `query`. Whitespace separates terms, and every
+/// term has to fuzzy-match on its own. An empty query matches everything.
+pub fn matches(item: &Item, query: &str) -> bool {
+    let haystack = item_haystack(item);
+    query
+        .split_whitespace()
+        .all(|term| fuzzy_match(&haystack, term))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(line: &str) -> Item {
+        Item::parse(line, 1)
+    }
+
+    #[test]
+    fn test_fuzzy_match_subsequence() {
+        assert!(fuzzy_match("Buy milk", "bmk"));
+        assert!(fuzzy_match("Buy milk", "MILK"));
+        assert!(!fuzzy_match("Buy milk", "klim"));
+        assert!(fuzzy_match("anything", ""));
+    }
+
+    #[test]
+    fn test_fuzzy_match_multibyte() {
+        assert!(fuzzy_match("牛乳を買う", "牛買"));
+        assert!(!fuzzy_match("牛乳を買う", "買牛"));
+    }
+
+    #[test]
+    fn test_matches_projects_contexts_and_tags() {
+        let it = item("Call plumber +house @phone due:2026-11-01 id:x1");
+        assert!(matches(&it, "+house"));
+        assert!(matches(&it, "@phone"));
+        assert!(matches(&it, "due:2026-11"));
+        assert!(matches(&it, "plumb"));
+        assert!(!matches(&it, "+garden"));
+    }
+
+    #[test]
+    fn test_matches_requires_every_term() {
+        let it = item("Call plumber +house @phone id:x1");
+        assert!(matches(&it, "call @ph"));
+        assert!(!matches(&it, "call @office"));
+        assert!(matches(&it, "   "));
+    }
+}
@@ -0,0 +1,355 @@
+use crate::todo_file::TodoFile;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+thread_local! {
+    /// Directories whose lock this thread holds, so nested mutations (an undo
+    /// record around a move, say) don't deadlock on their own `flock`.
+    static HELD: RefCell<Vec<PathBuf>> = const { RefCell::new(Vec::new()) };
+}
+
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Exclusive advisory lock on a todotxt directory, released on drop. Every
+/// torudo process (TUI, CLI subcommands, RPC calls) takes it around a
+/// read-modify-write of the todo files, so their changes never interleave.
+pub struct DirLock {
+    dir: PathBuf,
+    file: Option<File>,
+}
+
+impl Drop for DirLock {
+    fn drop(&mut self) {
+        if self.file.is_some() {
+            HELD.with_borrow_mut(|held| held.retain(|d| d != &self.dir));
+        }
+    }
+}
+
+/// Block until this process holds the lock on `dir`. Taking it again on the
+/// same thread while it is held is a no-op.
+pub fn lock(dir: &Path) -> io::Result<DirLock> {
+    let dir = dir.canonicalize()?;
+    if HELD.with_borrow(|held| held.contains(&dir)) {
+        return Ok(DirLock { dir, file: None });
+    }
+    let file = File::open(&dir)?;
+    file.lock()?;
+    HELD.with_borrow_mut(|held| held.push(dir.clone()));
+    Ok(DirLock {
+        dir,
+        file: Some(file),
+    })
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    path.parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."))
+}
+
+/// Lock the directory containing `file`.
+pub fn lock_for(file: &str) -> io::Result<DirLock> {
+    lock(parent_dir(Path::new(file)))
+}
+
+/// Replace `path` with `content` so readers see either the old or the new
+/// file, never a partial one: the content goes to a temporary file in the
+/// same directory, which is flushed and renamed over `path`. A symlinked
+/// `path` keeps its link and has its target replaced.
+pub fn write_atomic(path: impl AsRef<Path>, content: &str) -> io::Result<()> {
+    let path = path.as_ref();
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?
+        .to_string_lossy();
+    let tmp = parent_dir(&path).join(format!(
+        ".{name}.{}-{}.tmp",
+        std::process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = (|| {
+        let mut file = File::create(&tmp)?;
+        file.write_all(content.as_bytes())?;
+        if let Ok(meta) = fs::metadata(&path) {
+            file.set_permissions(meta.permissions())?;
+        }
+        file.sync_all()?;
+        fs::rename(&tmp, &path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// A move of one item between two files, recorded before the destination
+/// is written and removed once the source is, so a crash in between can be
+/// finished on the next start.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+struct PendingMove {
+    id: String,
+    from: String,
+    to: String,
+}
+
+fn pending_path(dir: &Path) -> PathBuf {
+    dir.join(".torudo").join("pending.json")
+}
+
+/// Move item `id` from `from` to `to` by writing both files' new content,
+/// destination first. The caller holds the directory lock.
+pub fn write_move(
+    id: &str,
+    (from, from_content): (&str, &str),
+    (to, to_content): (&str, &str),
+) -> Result<(), Box<dyn Error>> {
+    with_pending_move(id, (from, from_content), to, || {
+        write_atomic(to, to_content)
+    })
+}
+
+/// Like `write_move`, but the item is appended to `to` as `line` instead of
+/// rewriting it, so a long archive is never read.
+pub fn append_move(
+    id: &str,
+    (from, from_content): (&str, &str),
+    (to, line): (&str, &str),
+) -> Result<(), Box<dyn Error>> {
+    with_pending_move(id, (from, from_content), to, || {
+        crate::todo_file::append_line(to, line)
+    })
+}
+
+fn with_pending_move(
+    id: &str,
+    (from, from_content): (&str, &str),
+    to: &str,
+    write_to: impl FnOnce() -> io::Result<()>,
+) -> Result<(), Box<dyn Error>> {
+    let marker = pending_path(parent_dir(Path::new(from)));
+    if let Some(parent) = marker.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let pending = PendingMove {
+        id: id.to_string(),
+        from: from.to_string(),
+        to: to.to_string(),
+    };
+    write_atomic(&marker, &serde_json::to_string(&pending)?)?;
+    write_to()?;
+    write_atomic(from, from_content)?;
+    fs::remove_file(&marker)?;
+    Ok(())
+}
+
+/// Remove the line carrying `id:{id}` from `path`, if there is one.
+fn remove_item(path: &str, id: &str) -> io::Result<bool> {
+    let mut doc = match TodoFile::read(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        result => result?,
+    };
+    let Some(index) = doc.find(id) else {
+        return Ok(false);
+    };
+    doc.remove_line(index);
+    write_atomic(path, &doc.to_string())?;
+    Ok(true)
+}
+
+fn contains_id(path: &str, id: &str) -> bool {
+    let tag = format!("id:{id}");
+    fs::read_to_string(path).is_ok_and(|content| {
+        content
+            .lines()
+            .any(|line| line.split_whitespace().any(|word| word == tag))
+    })
+}
+
+/// Finish a move interrupted by a crash: when the item already reached its
+/// destination but is still in its source, its line is removed from the
+/// source as it is now, keeping any edit made to it since the crash.
+/// Returns a note for the user when something was repaired.
+pub fn recover(todotxt_dir: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let marker = pending_path(Path::new(todotxt_dir));
+    if !marker.exists() {
+        return Ok(None);
+    }
+    let _lock = lock(Path::new(todotxt_dir))?;
+    let Ok(content) = fs::read_to_string(&marker) else {
+        // Another process finished it while we waited for the lock
+        return Ok(None);
+    };
+    let note = match serde_json::from_str::<PendingMove>(&content) {
+        Ok(pending) if contains_id(&pending.to, &pending.id) => {
+            remove_item(&pending.from, &pending.id)?.then(|| {
+                format!(
+                    "Finished an interrupted move of id:{} to {}",
+                    pending.id, pending.to
+                )
+            })
+        }
+        _ => None,
+    };
+    fs::remove_file(&marker)?;
+    Ok(note)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path_in(dir: &tempfile::TempDir, name: &str) -> String {
+        dir.path().join(name).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_content_and_cleans_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let todo = path_in(&dir, "todo.txt");
+        write_atomic(&todo, "a\n").unwrap();
+        write_atomic(&todo, "b\n").unwrap();
+        assert_eq!(fs::read_to_string(&todo).unwrap(), "b\n");
+        let names: Vec<_> = fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(names.len(), 1, "temporary files left behind");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_keeps_symlinks_and_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let target = path_in(&dir, "real.txt");
+        let link = path_in(&dir, "todo.txt");
+        fs::write(&target, "a\n").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o600)).unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        write_atomic(&link, "b\n").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "b\n");
+        let mode = fs::metadata(&target).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_lock_is_reentrant_and_exclusive() {
+        let dir = tempfile::tempdir().unwrap();
+        let outer = lock(dir.path()).unwrap();
+        let inner = lock(dir.path()).unwrap();
+        assert!(inner.file.is_none());
+        drop(inner);
+
+        // Another thread has to wait until the outer lock is released
+        let (tx, rx) = std::sync::mpsc::channel();
+        let path = dir.path().to_path_buf();
+        let waiter = std::thread::spawn(move || {
+            let _lock = lock(&path).unwrap();
+            tx.send(()).unwrap();
+        });
+        assert!(
+            rx.recv_timeout(std::time::Duration::from_millis(100))
+                .is_err()
+        );
+        drop(outer);
+        rx.recv_timeout(std::time::Duration::from_secs(5)).unwrap();
+        waiter.join().unwrap();
+    }
+
+    #[test]
+    fn test_write_move_leaves_no_marker() {
+        let dir = tempfile::tempdir().unwrap();
+        let inbox = path_in(&dir, "inbox.txt");
+        let todo = path_in(&dir, "todo.txt");
+        write_move("m1", (&inbox, ""), (&todo, "Task id:m1\n")).unwrap();
+        assert_eq!(fs::read_to_string(&todo).unwrap(), "Task id:m1\n");
+        assert!(!pending_path(dir.path()).exists());
+        assert_eq!(recover(dir.path().to_str().unwrap()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_recover_finishes_half_applied_move() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir_str = dir.path().to_str().unwrap();
+        let inbox = path_in(&dir, "inbox.txt");
+        let todo = path_in(&dir, "todo.txt");
+        // Crash after the destination was written but before the source
+        fs::write(&inbox, "Keep id:k1\nTask id:m1\n").unwrap();
+        fs::write(&todo, "Task id:m1\n").unwrap();
+        fs::create_dir_all(dir.path().join(".torudo")).unwrap();
+        let pending = PendingMove {
+            id: "m1".to_string(),
+            from: inbox.clone(),
+            to: todo.clone(),
+        };
+        fs::write(
+            pending_path(dir.path()),
+            serde_json::to_string(&pending).unwrap(),
+        )
+        .unwrap();
+
+        let note = recover(dir_str).unwrap().unwrap();
+        assert!(note.contains("id:m1"), "{note}");
+        assert_eq!(fs::read_to_string(&inbox).unwrap(), "Keep id:k1\n");
+        assert_eq!(fs::read_to_string(&todo).unwrap(), "Task id:m1\n");
+        assert!(!pending_path(dir.path()).exists());
+    }
+
+    #[test]
+    fn test_recover_keeps_source_edits_made_after_the_crash() {
+        let dir = tempfile::tempdir().unwrap();
+        let inbox = path_in(&dir, "inbox.txt");
+        let todo = path_in(&dir, "todo.txt");
+        fs::write(&todo, "Task id:m1\n").unwrap();
+        fs::create_dir_all(dir.path().join(".torudo")).unwrap();
+        let pending = PendingMove {
+            id: "m1".to_string(),
+            from: inbox.clone(),
+            to: todo,
+        };
+        fs::write(
+            pending_path(dir.path()),
+            serde_json::to_string(&pending).unwrap(),
+        )
+        .unwrap();
+        // Edited by hand before torudo ran again
+        fs::write(&inbox, "Keep id:k1\r\nTask id:m1\r\nAdded later id:k2\r\n").unwrap();
+
+        assert!(recover(dir.path().to_str().unwrap()).unwrap().is_some());
+        assert_eq!(
+            fs::read_to_string(&inbox).unwrap(),
+            "Keep id:k1\r\nAdded later id:k2\r\n"
+        );
+    }
+
+    #[test]
+    fn test_recover_ignores_move_that_never_started() {
+        let dir = tempfile::tempdir().unwrap();
+        let inbox = path_in(&dir, "inbox.txt");
+        let todo = path_in(&dir, "todo.txt");
+        // Crash before the destination was written: nothing to finish
+        fs::write(&inbox, "Task id:m1\n").unwrap();
+        fs::write(&todo, "").unwrap();
+        fs::create_dir_all(dir.path().join(".torudo")).unwrap();
+        let pending = PendingMove {
+            id: "m1".to_string(),
+            from: inbox.clone(),
+            to: todo,
+        };
+        fs::write(
+            pending_path(dir.path()),
+            serde_json::to_string(&pending).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(recover(dir.path().to_str().unwrap()).unwrap(), None);
+        assert_eq!(fs::read_to_string(&inbox).unwrap(), "Task id:m1\n");
+        assert!(!pending_path(dir.path()).exists());
+    }
+}
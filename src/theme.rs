@@ -0,0 +1,461 @@
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::sync::OnceLock;
+
+/// Every style the UI draws with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+    /// Card and column borders
+    pub border: Style,
+    /// Selected card, active column and the `>` marker
+    pub selection: Style,
+    /// Border of cards past their `due:` date
+    pub overdue: Style,
+    /// Borders of completed and not-yet-started cards, and other quiet text
+    pub dimmed: Style,
+    /// Text of cards whose `t:` date is in the future
+    pub pending: Style,
+    /// Checklist preview and age of a card's detail md
+    pub meta: Style,
+    pub tab: Style,
+    pub tab_active: Style,
+    /// `f` jump labels
+    pub hint_label: Style,
+    /// Focused row of the template picker
+    pub focus: Style,
+    /// The `✓` of completed items
+    pub done_badge: Style,
+    /// The `🔗` of items with URLs
+    pub url_badge: Style,
+    pub priority_a: Style,
+    pub priority_b: Style,
+    pub priority_c: Style,
+    pub priority_other: Style,
+    pub context: Style,
+    /// Status line messages
+    pub status: Style,
+    pub error: Style,
+    /// Overlay borders and the active filter
+    pub accent: Style,
+    /// Input prompt and its border
+    pub prompt: Style,
+    /// The active search
+    pub search: Style,
+    /// Update notice
+    pub notice: Style,
+    /// Keys in the help overlay
+    pub key: Style,
+    /// Plain text in overlays
+    pub text: Style,
+}
+
+/// Names of the styles as written in `[themes.NAME]`.
+pub const STYLE_NAMES: &[&str] = &[
+    "border",
+    "selection",
+    "overdue",
+    "dimmed",
+    "pending",
+    "meta",
+    "tab",
+    "tab_active",
+    "hint_label",
+    "focus",
+    "done_badge",
+    "url_badge",
+    "priority_a",
+    "priority_b",
+    "priority_c",
+    "priority_other",
+    "context",
+    "status",
+    "error",
+    "accent",
+    "prompt",
+    "search",
+    "notice",
+    "key",
+    "text",
+];
+
+/// Themes that need no configuration.
+pub const BUILT_IN: &[&str] = &["dark", "light", "high-contrast"];
+
+fn fg(color: Color) -> Style {
+    Style::default().fg(color)
+}
+
+fn bold(color: Color) -> Style {
+    fg(color).add_modifier(Modifier::BOLD)
+}
+
+fn label(fg: Color, bg: Color) -> Style {
+    Style::default().fg(fg).bg(bg).add_modifier(Modifier::BOLD)
+}
+
+impl Theme {
+    /// The original colors, made for dark terminals.
+    pub fn dark() -> Self {
+        Self {
+            border: fg(Color::White),
+            selection: fg(Color::Yellow),
+            overdue: fg(Color::Red),
+            dimmed: fg(Color::DarkGray),
+            pending: fg(Color::Rgb(120, 120, 120)),
+            meta: fg(Color::Rgb(170, 170, 170)),
+            tab: Style::default(),
+            tab_active: bold(Color::Yellow),
+            hint_label: label(Color::Black, Color::Yellow),
+            focus: label(Color::Black, Color::Yellow),
+            done_badge: fg(Color::Green),
+            url_badge: fg(Color::Blue),
+            priority_a: bold(Color::Red),
+            priority_b: bold(Color::Yellow),
+            priority_c: bold(Color::Blue),
+            priority_other: bold(Color::White),
+            context: fg(Color::Cyan),
+            status: fg(Color::Green),
+            error: fg(Color::Red),
+            accent: fg(Color::Cyan),
+            prompt: fg(Color::Yellow),
+            search: fg(Color::Yellow),
+            notice: fg(Color::Yellow),
+            key: bold(Color::Yellow),
+            text: fg(Color::White),
+        }
+    }
+
+    /// Dark text on a light background: no white or yellow foregrounds.
+    pub fn light() -> Self {
+        let blue = Color::Rgb(0, 85, 170);
+        let amber = Color::Rgb(160, 100, 0);
+        Self {
+            border: fg(Color::Rgb(90, 90, 90)),
+            selection: fg(blue),
+            overdue: fg(Color::Rgb(190, 0, 0)),
+            dimmed: fg(Color::Rgb(160, 160, 160)),
+            pending: fg(Color::Rgb(140, 140, 140)),
+            meta: fg(Color::Rgb(100, 100, 100)),
+            tab: Style::default(),
+            tab_active: bold(blue),
+            hint_label: label(Color::White, blue),
+            focus: label(Color::White, blue),
+            done_badge: fg(Color::Rgb(0, 130, 0)),
+            url_badge: fg(blue),
+            priority_a: bold(Color::Rgb(190, 0, 0)),
+            priority_b: bold(amber),
+            priority_c: bold(blue),
+            priority_other: bold(Color::Rgb(60, 60, 60)),
+            context: fg(Color::Rgb(0, 120, 120)),
+            status: fg(Color::Rgb(0, 130, 0)),
+            error: fg(Color::Rgb(190, 0, 0)),
+            accent: fg(Color::Rgb(0, 120, 120)),
+            prompt: fg(blue),
+            search: fg(amber),
+            notice: fg(amber),
+            key: bold(blue),
+            text: fg(Color::Black),
+        }
+    }
+
+    /// Bright, bold colors and inverted labels for low-vision use.
+    pub fn high_contrast() -> Self {
+        Self {
+            border: fg(Color::White),
+            selection: bold(Color::LightYellow),
+            overdue: bold(Color::LightRed),
+            dimmed: fg(Color::Gray),
+            pending: fg(Color::Gray),
+            meta: fg(Color::White),
+            tab: fg(Color::White),
+            tab_active: label(Color::Black, Color::LightYellow),
+            hint_label: label(Color::Black, Color::LightYellow),
+            focus: label(Color::Black, Color::LightYellow),
+            done_badge: bold(Color::LightGreen),
+            url_badge: bold(Color::LightCyan),
+            priority_a: bold(Color::LightRed),
+            priority_b: bold(Color::LightYellow),
+            priority_c: bold(Color::LightCyan),
+            priority_other: bold(Color::White),
+            context: bold(Color::LightCyan),
+            status: bold(Color::LightGreen),
+            error: bold(Color::LightRed),
+            accent: bold(Color::LightCyan),
+            prompt: bold(Color::LightYellow),
+            search: bold(Color::LightYellow),
+            notice: bold(Color::LightYellow),
+            key: bold(Color::LightYellow),
+            text: fg(Color::White),
+        }
+    }
+
+    fn built_in(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            _ => None,
+        }
+    }
+
+    fn style_mut(&mut self, name: &str) -> Option<&mut Style> {
+        Some(match name {
+            "border" => &mut self.border,
+            "selection" => &mut self.selection,
+            "overdue" => &mut self.overdue,
+            "dimmed" => &mut self.dimmed,
+            "pending" => &mut self.pending,
+            "meta" => &mut self.meta,
+            "tab" => &mut self.tab,
+            "tab_active" => &mut self.tab_active,
+            "hint_label" => &mut self.hint_label,
+            "focus" => &mut self.focus,
+            "done_badge" => &mut self.done_badge,
+            "url_badge" => &mut self.url_badge,
+            "priority_a" => &mut self.priority_a,
+            "priority_b" => &mut self.priority_b,
+            "priority_c" => &mut self.priority_c,
+            "priority_other" => &mut self.priority_other,
+            "context" => &mut self.context,
+            "status" => &mut self.status,
+            "error" => &mut self.error,
+            "accent" => &mut self.accent,
+            "prompt" => &mut self.prompt,
+            "search" => &mut self.search,
+            "notice" => &mut self.notice,
+            "key" => &mut self.key,
+            "text" => &mut self.text,
+            _ => return None,
+        })
+    }
+
+    /// The same theme with every color removed, for `NO_COLOR`. Styles that
+    /// relied on a background (labels) are shown inverted instead.
+    #[must_use]
+    pub fn without_colors(mut self) -> Self {
+        for name in STYLE_NAMES {
+            if let Some(style) = self.style_mut(name) {
+                let mut plain = Style::default().add_modifier(style.add_modifier);
+                if style.bg.is_some() {
+                    plain = plain.add_modifier(Modifier::REVERSED);
+                }
+                *style = plain;
+            }
+        }
+        self
+    }
+}
+
+/// One style in a custom theme. Colors are names (`red`, `lightblue`,
+/// `darkgray`), `#rrggbb` or a 0-255 palette index.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct StyleSpec {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bg: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub bold: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub italic: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub underlined: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub reversed: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub dim: bool,
+}
+
+fn parse_color(value: &str) -> Result<Color, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{value:?} is not a color name, #rrggbb or 0-255"))
+}
+
+impl StyleSpec {
+    fn to_style(&self) -> Result<Style, String> {
+        let mut style = Style::default();
+        if let Some(fg) = &self.fg {
+            style = style.fg(parse_color(fg)?);
+        }
+        if let Some(bg) = &self.bg {
+            style = style.bg(parse_color(bg)?);
+        }
+        for (on, modifier) in [
+            (self.bold, Modifier::BOLD),
+            (self.italic, Modifier::ITALIC),
+            (self.underlined, Modifier::UNDERLINED),
+            (self.reversed, Modifier::REVERSED),
+            (self.dim, Modifier::DIM),
+        ] {
+            if on {
+                style = style.add_modifier(modifier);
+            }
+        }
+        Ok(style)
+    }
+}
+
+/// A custom theme: a built-in theme with some styles replaced.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ThemeSpec {
+    /// Built-in theme the other styles come from (default `dark`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    #[serde(flatten)]
+    pub styles: BTreeMap<String, StyleSpec>,
+}
+
+/// Resolve the theme called `name`, built in or from `custom`.
+pub fn resolve(name: &str, custom: &BTreeMap<String, ThemeSpec>) -> Result<Theme, String> {
+    if let Some(theme) = Theme::built_in(name) {
+        return Ok(theme);
+    }
+    let Some(spec) = custom.get(name) else {
+        let mut known: Vec<&str> = BUILT_IN.to_vec();
+        known.extend(custom.keys().map(String::as_str));
+        return Err(format!(
+            "theme {name:?} is not defined (one of: {})",
+            known.join(", ")
+        ));
+    };
+    let base = spec.base.as_deref().unwrap_or("dark");
+    let mut theme = Theme::built_in(base).ok_or_else(|| {
+        format!(
+            "themes.{name}.base must be a built-in theme ({}), got {base:?}",
+            BUILT_IN.join(", ")
+        )
+    })?;
+    for (style_name, style) in &spec.styles {
+        let style = style
+            .to_style()
+            .map_err(|e| format!("themes.{name}.{style_name}: {e}"))?;
+        *theme
+            .style_mut(style_name)
+            .ok_or_else(|| format!("themes.{name}.{style_name} is not a style"))? = style;
+    }
+    Ok(theme)
+}
+
+/// Whether `NO_COLOR` asks for monochrome output (<https://no-color.org>).
+fn no_color() -> bool {
+    env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+static ACTIVE: OnceLock<Theme> = OnceLock::new();
+
+/// The configured theme, without colors when `NO_COLOR` is set.
+pub fn active() -> &'static Theme {
+    ACTIVE.get_or_init(|| {
+        let theme = crate::config::get()
+            .theme()
+            .unwrap_or_else(|_| Theme::dark());
+        if no_color() {
+            theme.without_colors()
+        } else {
+            theme
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom(toml: &str) -> BTreeMap<String, ThemeSpec> {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_every_style_name_is_settable() {
+        let mut theme = Theme::dark();
+        for name in STYLE_NAMES {
+            assert!(theme.style_mut(name).is_some(), "{name}");
+        }
+        assert!(theme.style_mut("bogus").is_none());
+    }
+
+    #[test]
+    fn test_built_in_themes() {
+        for name in BUILT_IN {
+            assert!(resolve(name, &BTreeMap::new()).is_ok(), "{name}");
+        }
+        // Light terminals get no white or yellow text
+        let light = Theme::light();
+        for style in [light.text, light.border, light.selection, light.key] {
+            assert!(!matches!(style.fg, Some(Color::White | Color::Yellow)));
+        }
+    }
+
+    #[test]
+    fn test_custom_theme_overrides_base() {
+        let themes = custom(
+            r##"
+            [mine]
+            base = "light"
+            selection = { fg = "magenta", bold = true }
+            hint_label = { fg = "#000000", bg = "214" }
+            "##,
+        );
+        let theme = resolve("mine", &themes).unwrap();
+        assert_eq!(
+            theme.selection,
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD)
+        );
+        assert_eq!(
+            theme.hint_label,
+            Style::default()
+                .fg(Color::Rgb(0, 0, 0))
+                .bg(Color::Indexed(214))
+        );
+        assert_eq!(theme.overdue, Theme::light().overdue);
+    }
+
+    #[test]
+    fn test_custom_theme_errors() {
+        let err = resolve("nope", &BTreeMap::new()).unwrap_err();
+        assert!(err.contains("dark, light, high-contrast"), "{err}");
+
+        let themes = custom("[mine]\nbase = \"mine\"\n");
+        let err = resolve("mine", &themes).unwrap_err();
+        assert!(
+            err.starts_with("themes.mine.base must be a built-in theme"),
+            "{err}"
+        );
+
+        let themes = custom("[mine]\nselected = { fg = \"red\" }\n");
+        assert_eq!(
+            resolve("mine", &themes).unwrap_err(),
+            "themes.mine.selected is not a style"
+        );
+
+        let themes = custom("[mine]\nselection = { fg = \"rouge\" }\n");
+        let err = resolve("mine", &themes).unwrap_err();
+        assert!(err.starts_with("themes.mine.selection: \"rouge\""), "{err}");
+
+        assert!(
+            toml::from_str::<BTreeMap<String, ThemeSpec>>(
+                "[mine]\nselection = { fg = \"red\", blink = true }\n"
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_without_colors_keeps_labels_visible() {
+        let theme = Theme::dark().without_colors();
+        assert_eq!(theme.border, Style::default());
+        assert_eq!(theme.key, Style::default().add_modifier(Modifier::BOLD));
+        assert_eq!(
+            theme.hint_label,
+            Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED)
+        );
+        assert!(theme.hint_label.fg.is_none() && theme.hint_label.bg.is_none());
+    }
+}
@@ -1,4 +1,9 @@
-use crate::md_preview::MdMeta;
+use crate::app_state::ViewMode;
+use crate::md_preview::{MdMeta, md_path};
+use crate::recurrence::{self, Recurrence};
+use crate::relative_date;
+use crate::store;
+use crate::todo_file::TodoFile;
 use chrono::NaiveDate;
 use log::debug;
 use serde::Serialize;
@@ -111,6 +116,20 @@ impl Item {
         self.due_date().is_some_and(|d| d <= today)
     }
 
+    /// Mode file a completed item was completed from, per its `from:` tag.
+    /// Items archived without one came from the Todo mode.
+    pub fn origin(&self) -> ViewMode {
+        self.key_values
+            .get("from")
+            .and_then(|name| {
+                ViewMode::ALL
+                    .iter()
+                    .find(|m| m.label().eq_ignore_ascii_case(name))
+            })
+            .copied()
+            .unwrap_or(ViewMode::Todo)
+    }
+
     fn parse_key_date(&self, key: &str) -> Option<NaiveDate> {
         self.key_values
             .get(key)
@@ -152,12 +171,10 @@ fn split_key_value(part: &str) -> Option<(&str, &str)> {
 }
 
 pub fn load_todos(file_path: &str) -> Result<Vec<Item>, Box<dyn Error>> {
-    let content = fs::read_to_string(file_path)?;
-    let mut todos: Vec<Item> = content
-        .lines()
-        .enumerate()
-        .filter(|(_, line)| !line.trim().is_empty())
-        .map(|(line_num, line)| Item::parse(line, line_num + 1))
+    let doc = TodoFile::read(file_path)?;
+    let mut todos: Vec<Item> = doc
+        .items()
+        .map(|(index, line)| Item::parse(line, index + 1))
         .collect();
 
     let today = chrono::Local::now().date_naive();
@@ -185,35 +202,21 @@ fn sort_todos(todos: &mut [Item], today: NaiveDate) {
 }
 
 pub fn add_missing_ids(file_path: &str) -> Result<(), Box<dyn Error>> {
-    let content = fs::read_to_string(file_path)?;
-    let lines: Vec<&str> = content.lines().collect();
-    let mut modified = false;
-    let mut new_lines = Vec::new();
-
-    for (line_num, line) in lines.iter().enumerate() {
-        if line.trim().is_empty() {
-            new_lines.push(line.to_string());
-            continue;
-        }
+    let _lock = store::lock_for(file_path)?;
+    let mut doc = TodoFile::read(file_path)?;
+    let missing: Vec<usize> = doc
+        .items()
+        .filter(|(index, line)| Item::parse(line, index + 1).id.is_none())
+        .map(|(index, _)| index)
+        .collect();
 
-        let todo = Item::parse(line, line_num + 1);
-        if todo.id.is_none() {
-            let new_id = Uuid::new_v4().to_string();
-            let new_line = format!("{line} id:{new_id}");
-            new_lines.push(new_line);
-            modified = true;
-        } else {
-            new_lines.push(line.to_string());
+    if !missing.is_empty() {
+        debug!("Adding missing IDs to {} lines in todo file", missing.len());
+        for index in missing {
+            let line = format!("{} id:{}", doc.line(index), Uuid::new_v4());
+            doc.set_line(index, line);
         }
-    }
-
-    if modified {
-        let new_content = new_lines.join("\n");
-        debug!(
-            "Adding missing IDs to {} lines in todo file",
-            usize::from(modified)
-        );
-        fs::write(file_path, new_content)?;
+        store::write_atomic(file_path, &doc.to_string())?;
     }
 
     Ok(())
@@ -227,95 +230,200 @@ fn split_priority_prefix(line: &str) -> (Option<&str>, &str) {
     }
 }
 
-pub fn mark_complete(todo_file: &str, todo_id: &str) -> Result<(), Box<dyn Error>> {
-    let content = fs::read_to_string(todo_file)?;
-    let lines: Vec<&str> = content.lines().collect();
-    let mut new_lines = Vec::new();
-    let mut completed_line = None;
+/// Line of the next occurrence of a `rec:` item completed on `today`, with
+/// shifted `due:`/`t:` and today's creation date. The `id:` is dropped so the
+/// caller can assign a fresh one.
+pub fn next_occurrence(line: &str, today: NaiveDate) -> Option<String> {
+    let item = Item::parse(line, 0);
+    if item.completed {
+        return None;
+    }
+    let rec = Recurrence::parse(item.key_values.get("rec")?)?;
+    let (due, threshold) =
+        recurrence::next_dates(rec, item.due_date(), item.threshold_date(), today)?;
 
-    for (line_num, line) in lines.iter().enumerate() {
-        if line.trim().is_empty() {
-            new_lines.push(line.to_string());
+    let (priority, rest) = split_priority_prefix(line);
+    let mut words: Vec<String> = Vec::new();
+    let (mut has_due, mut has_t) = (false, false);
+    for (i, word) in rest.split_whitespace().enumerate() {
+        if i == 0 && NaiveDate::parse_from_str(word, "%Y-%m-%d").is_ok() {
             continue;
         }
-
-        let todo = Item::parse(line, line_num + 1);
-        if let Some(id) = &todo.id
-            && id == todo_id
-        {
-            let today = chrono::Local::now().format("%Y-%m-%d").to_string();
-            let completed_todo_line = if todo.completed {
-                line.to_string()
-            } else {
-                format!("x {}", insert_date_after_priority(line, &today))
-            };
-            completed_line = Some(completed_todo_line);
-            continue;
+        match split_key_value(word) {
+            Some(("id", _)) => {}
+            Some(("due", _)) if let Some(due) = due => {
+                has_due = true;
+                words.push(format!("due:{due}"));
+            }
+            Some(("t", _)) if let Some(t) = threshold => {
+                has_t = true;
+                words.push(format!("t:{t}"));
+            }
+            _ => words.push(word.to_string()),
         }
-        new_lines.push(line.to_string());
+    }
+    if let Some(due) = due.filter(|_| !has_due) {
+        words.push(format!("due:{due}"));
+    }
+    if let Some(t) = threshold.filter(|_| !has_t) {
+        words.push(format!("t:{t}"));
     }
 
-    if let Some(completed_todo) = completed_line {
-        let todo_dir = std::path::Path::new(todo_file).parent().unwrap();
-        let done_file = todo_dir.join("done.txt");
+    let text = words.join(" ");
+    let text = match priority {
+        Some(pri) => format!("{pri} {text}"),
+        None => text,
+    };
+    Some(insert_date_after_priority(&text, &today.to_string()))
+}
 
-        debug!("Moving completed todo to done.txt: {completed_todo}");
+/// Result of [`mark_complete`].
+pub struct Completion {
+    /// The line as appended to `done.txt`
+    pub archived: Item,
+    /// Next occurrence of a `rec:` item
+    pub next: Option<Item>,
+}
 
-        let mut done_content = if done_file.exists() {
-            fs::read_to_string(&done_file)?
-        } else {
-            String::new()
-        };
+/// Move the item to `done.txt` by appending to it, without reading it. A
+/// `rec:` item is replaced in `todo_file` by its next occurrence, which gets
+/// `next_id` and a copy of the detail md. The caller picks `next_id` so it
+/// knows which md may be created before anything is written. Returns `None`
+/// when no line has `todo_id`.
+pub fn mark_complete(
+    todo_file: &str,
+    todo_id: &str,
+    next_id: &str,
+) -> Result<Option<Completion>, Box<dyn Error>> {
+    let _lock = store::lock_for(todo_file)?;
+    let mut doc = TodoFile::read(todo_file)?;
+    let Some(index) = doc.find(todo_id) else {
+        return Ok(None);
+    };
 
-        if !done_content.is_empty() && !done_content.ends_with('\n') {
-            done_content.push('\n');
+    let line = doc.remove_line(index);
+    let today = chrono::Local::now().date_naive();
+    let todo_dir = std::path::Path::new(todo_file).parent().unwrap();
+    let completed_todo = if line.starts_with("x ") {
+        line.clone()
+    } else {
+        let completed = format!(
+            "x {}",
+            insert_date_after_priority(&line, &today.to_string())
+        );
+        // Remember where it came from so un-completing can put it back
+        match mode_of_file(todo_file) {
+            Some(mode) => format!("{completed} from:{}", mode.label().to_lowercase()),
+            None => completed,
         }
-        done_content.push_str(&completed_todo);
-        done_content.push('\n');
+    };
 
-        fs::write(&done_file, done_content)?;
+    let done_file = todo_dir.join(&crate::config::get().files.done);
+    let done_file = done_file.to_string_lossy();
 
-        let new_todo_content = new_lines.join("\n");
-        fs::write(todo_file, new_todo_content)?;
+    debug!("Moving completed todo to done.txt: {completed_todo}");
 
-        debug!("Successfully moved todo to done.txt and updated todo.txt");
-    }
+    let next_item = next_occurrence(&line, today).map(|next| {
+        let next = format!("{next} id:{next_id}");
+        debug!("Adding next occurrence: {next}");
+        let item = Item::parse(&next, doc.len() + 1);
+        doc.push_line(next);
+        item
+    });
 
-    Ok(())
+    store::append_move(
+        todo_id,
+        (todo_file, &doc.to_string()),
+        (&done_file, &completed_todo),
+    )?;
+    // Only once the next occurrence is written, so a failed write leaves no md behind
+    if next_item.is_some()
+        && let Some(dir) = todo_dir.to_str()
+    {
+        copy_md(dir, todo_id, next_id)?;
+    }
+
+    debug!("Successfully moved todo to done.txt and updated todo.txt");
+    Ok(Some(Completion {
+        archived: Item::parse(&completed_todo, 0),
+        next: next_item,
+    }))
 }
 
-pub fn delete_todo(todo_file: &str, todo_id: &str) -> Result<bool, Box<dyn Error>> {
-    let content = fs::read_to_string(todo_file)?;
-    let lines: Vec<&str> = content.lines().collect();
-    let mut new_lines = Vec::with_capacity(lines.len());
-    let mut removed = false;
-
-    for (line_num, line) in lines.iter().enumerate() {
-        if line.trim().is_empty() {
-            new_lines.push((*line).to_string());
-            continue;
-        }
+fn mode_of_file(path: &str) -> Option<ViewMode> {
+    let name = std::path::Path::new(path).file_name()?.to_str()?;
+    ViewMode::ALL.iter().copied().find(|m| m.filename() == name)
+}
 
-        let todo = Item::parse(line, line_num + 1);
-        if let Some(id) = &todo.id
-            && id == todo_id
-        {
-            removed = true;
-            continue;
+/// The open line a completed `line` came from: the `x`, the completion
+/// date and the trailing `from:<mode>` tag added by [`mark_complete`] are
+/// dropped, the rest (including any other `from:` word) is kept as is.
+fn reopen_line(line: &str) -> String {
+    let rest = line.strip_prefix("x ").unwrap_or(line).trim_start();
+    let (priority, rest) = split_priority_prefix(rest);
+    let rest = match rest.split_once(' ') {
+        Some((first, after)) if NaiveDate::parse_from_str(first, "%Y-%m-%d").is_ok() => {
+            after.trim_start()
         }
-        new_lines.push((*line).to_string());
-    }
+        _ => rest,
+    };
+    let rest = rest
+        .rsplit_once(' ')
+        .filter(|(_, tag)| {
+            tag.strip_prefix("from:").is_some_and(|name| {
+                ViewMode::ALL
+                    .iter()
+                    .any(|m| m.label().eq_ignore_ascii_case(name))
+            })
+        })
+        .map_or(rest, |(before, _)| before);
+    priority.map_or_else(|| rest.to_string(), |pri| format!("{pri} {rest}"))
+}
 
-    if removed {
-        let mut out = new_lines.join("\n");
-        if content.ends_with('\n') {
-            out.push('\n');
-        }
-        fs::write(todo_file, out)?;
-        debug!("Deleted todo {todo_id} from {todo_file}");
+/// Move the completed item with `todo_id` from `done_file` back to
+/// `dest_file` as an open item (see [`Item::origin`] for where it came
+/// from). Returns the reopened item, or `None` when no line has that id.
+pub fn uncomplete(
+    done_file: &str,
+    dest_file: &str,
+    todo_id: &str,
+) -> Result<Option<Item>, Box<dyn Error>> {
+    let _lock = store::lock_for(done_file)?;
+    let mut done = TodoFile::read(done_file)?;
+    let Some(index) = done.find(todo_id) else {
+        return Ok(None);
+    };
+    let line = reopen_line(&done.remove_line(index));
+    let mut dest = TodoFile::read_or_empty(dest_file)?;
+    let item = Item::parse(&line, dest.len() + 1);
+    dest.push_line(line);
+    store::write_move(
+        todo_id,
+        (done_file, &done.to_string()),
+        (dest_file, &dest.to_string()),
+    )?;
+    debug!("Reopened {todo_id} into {dest_file}");
+    Ok(Some(item))
+}
+
+/// Carry the detail md of a recurring item over to its next occurrence.
+fn copy_md(todotxt_dir: &str, from_id: &str, to_id: &str) -> Result<(), Box<dyn Error>> {
+    match fs::copy(md_path(todotxt_dir, from_id), md_path(todotxt_dir, to_id)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
     }
+}
 
-    Ok(removed)
+pub fn delete_todo(todo_file: &str, todo_id: &str) -> Result<bool, Box<dyn Error>> {
+    let _lock = store::lock_for(todo_file)?;
+    let mut doc = TodoFile::read(todo_file)?;
+    let Some(index) = doc.find(todo_id) else {
+        return Ok(false);
+    };
+    doc.remove_line(index);
+    store::write_atomic(todo_file, &doc.to_string())?;
+    debug!("Deleted todo {todo_id} from {todo_file}");
+    Ok(true)
 }
 
 pub fn set_priority(
@@ -323,36 +431,53 @@ pub fn set_priority(
     todo_id: &str,
     priority: Option<char>,
 ) -> Result<(), Box<dyn Error>> {
-    let content = fs::read_to_string(todo_file)?;
-    let lines: Vec<&str> = content.lines().collect();
-    let mut new_lines = Vec::with_capacity(lines.len());
-    let mut changed = false;
-
-    for (line_num, line) in lines.iter().enumerate() {
-        if line.trim().is_empty() || line.starts_with("x ") {
-            new_lines.push((*line).to_string());
-            continue;
-        }
-        let todo = Item::parse(line, line_num + 1);
-        if todo.id.as_deref() != Some(todo_id) {
-            new_lines.push((*line).to_string());
-            continue;
-        }
-        let (_, rest) = split_priority_prefix(line);
-        let new_line = priority.map_or_else(|| rest.to_string(), |c| format!("({c}) {rest}"));
-        new_lines.push(new_line);
-        changed = true;
-    }
+    let _lock = store::lock_for(todo_file)?;
+    let mut doc = TodoFile::read(todo_file)?;
+    let Some(index) = doc.find_open(todo_id) else {
+        return Ok(());
+    };
+    let (_, rest) = split_priority_prefix(doc.line(index));
+    let new_line = priority.map_or_else(|| rest.to_string(), |c| format!("({c}) {rest}"));
+    doc.set_line(index, new_line);
+    store::write_atomic(todo_file, &doc.to_string())?;
+    debug!("Set priority {priority:?} on {todo_id} in {todo_file}");
+    Ok(())
+}
 
-    if changed {
-        let mut out = new_lines.join("\n");
-        if content.ends_with('\n') {
-            out.push('\n');
-        }
-        fs::write(todo_file, out)?;
-        debug!("Set priority {priority:?} on {todo_id} in {todo_file}");
+/// Return the raw line of the open item with `todo_id`, exactly as stored.
+pub fn find_line(todo_file: &str, todo_id: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let doc = TodoFile::read(todo_file)?;
+    Ok(doc
+        .find_open(todo_id)
+        .map(|index| doc.line(index).to_string()))
+}
+
+/// Replace the line of the open item with `todo_id` by `new_line`, keeping
+/// every other line, their order and the trailing newline untouched.
+/// Returns false when no line has that id. The new line must still carry
+/// `id:{todo_id}`, otherwise the item would lose its detail md and identity.
+pub fn replace_line(
+    todo_file: &str,
+    todo_id: &str,
+    new_line: &str,
+) -> Result<bool, Box<dyn Error>> {
+    let new_line = new_line.trim();
+    if new_line.contains('\n') || new_line.contains('\r') {
+        return Err("Edited item must stay on a single line".into());
     }
-    Ok(())
+    if Item::parse(new_line, 0).id.as_deref() != Some(todo_id) {
+        return Err(format!("Edited line must keep id:{todo_id}").into());
+    }
+
+    let _lock = store::lock_for(todo_file)?;
+    let mut doc = TodoFile::read(todo_file)?;
+    let Some(index) = doc.find_open(todo_id) else {
+        return Ok(false);
+    };
+    doc.set_line(index, new_line.to_string());
+    store::write_atomic(todo_file, &doc.to_string())?;
+    debug!("Replaced line of {todo_id} in {todo_file}");
+    Ok(true)
 }
 
 pub fn move_to_file(
@@ -360,36 +485,21 @@ pub fn move_to_file(
     dest_file: &str,
     todo_id: &str,
 ) -> Result<(), Box<dyn Error>> {
-    let content = fs::read_to_string(source_file)?;
-    let lines: Vec<&str> = content.lines().collect();
-    let mut new_lines = Vec::new();
-    let mut moved_line = None;
-
-    for (line_num, line) in lines.iter().enumerate() {
-        if line.trim().is_empty() {
-            new_lines.push(line.to_string());
-            continue;
-        }
-
-        let todo = Item::parse(line, line_num + 1);
-        if let Some(id) = &todo.id
-            && id == todo_id
-        {
-            moved_line = Some(line.to_string());
-            continue;
-        }
-        new_lines.push(line.to_string());
-    }
-
-    if let Some(line) = moved_line {
-        append_todo(dest_file, &line)?;
-
-        let new_source_content = new_lines.join("\n");
-        fs::write(source_file, new_source_content)?;
-
-        debug!("Moved todo to {dest_file}: {line}");
-    }
-
+    let _lock = store::lock_for(source_file)?;
+    let mut source = TodoFile::read(source_file)?;
+    let Some(index) = source.find(todo_id) else {
+        return Ok(());
+    };
+    let line = source.remove_line(index);
+    let mut dest = TodoFile::read_or_empty(dest_file)?;
+    dest.push_line(line.clone());
+    store::write_move(
+        todo_id,
+        (source_file, &source.to_string()),
+        (dest_file, &dest.to_string()),
+    )?;
+
+    debug!("Moved todo to {dest_file}: {line}");
     Ok(())
 }
 
@@ -403,9 +513,9 @@ pub fn has_todo_with_id(file_path: &str, id: &str) -> bool {
         .any(|line| line.split_whitespace().any(|word| word == id_tag))
 }
 
-/// Render an `Item` as pretty JSON, merging in the contents of
+/// Convert an `Item` to a JSON value, merging in the contents of
 /// `{todotxt_dir}/todos/{id}.md` as the `md` field when present.
-pub fn item_to_json(item: &Item, todotxt_dir: &str) -> Result<String, Box<dyn Error>> {
+pub fn item_to_value(item: &Item, todotxt_dir: &str) -> Result<serde_json::Value, Box<dyn Error>> {
     let mut json = serde_json::to_value(item)?;
     if let Some(todo_id) = &item.id {
         let path = crate::md_preview::md_path(todotxt_dir, todo_id);
@@ -413,16 +523,27 @@ pub fn item_to_json(item: &Item, todotxt_dir: &str) -> Result<String, Box<dyn Er
             json["md"] = serde_json::Value::String(content);
         }
     }
-    Ok(serde_json::to_string_pretty(&json)?)
+    Ok(json)
+}
+
+/// Render an `Item` as pretty JSON (see [`item_to_value`]).
+pub fn item_to_json(item: &Item, todotxt_dir: &str) -> Result<String, Box<dyn Error>> {
+    Ok(serde_json::to_string_pretty(&item_to_value(
+        item,
+        todotxt_dir,
+    )?)?)
 }
 
+/// Append a new item typed by the user: relative `due:`/`t:` values are
+/// resolved, and a creation date and `id:` are added when missing.
 pub fn add_item(file_path: &str, text: &str) -> Result<Item, Box<dyn Error>> {
-    let parsed = Item::parse(text, 0);
+    let today = chrono::Local::now().date_naive();
+    let text = relative_date::resolve_line(text, today);
+    let parsed = Item::parse(&text, 0);
     let text_with_date = if parsed.creation_date.is_some() {
-        text.to_string()
+        text
     } else {
-        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
-        insert_date_after_priority(text, &today)
+        insert_date_after_priority(&text, &today.to_string())
     };
     let mut item = Item::parse(&text_with_date, 0);
     let final_line = if item.id.is_some() {
@@ -438,40 +559,13 @@ pub fn add_item(file_path: &str, text: &str) -> Result<Item, Box<dyn Error>> {
 }
 
 pub fn append_todo(file_path: &str, line: &str) -> Result<(), Box<dyn Error>> {
-    let mut content = if std::path::Path::new(file_path).exists() {
-        fs::read_to_string(file_path)?
-    } else {
-        String::new()
-    };
-    if !content.is_empty() && !content.ends_with('\n') {
-        content.push('\n');
-    }
-    content.push_str(line);
-    content.push('\n');
-    fs::write(file_path, content)?;
+    let _lock = store::lock_for(file_path)?;
+    let mut doc = TodoFile::read_or_empty(file_path)?;
+    doc.push_line(line.to_string());
+    store::write_atomic(file_path, &doc.to_string())?;
     Ok(())
 }
 
-pub fn group_todos_by_project_owned(todos: &[Item]) -> HashMap<String, Vec<Item>> {
-    let mut grouped = HashMap::new();
-    for todo in todos {
-        if todo.projects.is_empty() {
-            grouped
-                .entry("No Project".to_string())
-                .or_insert_with(Vec::new)
-                .push(todo.clone());
-        } else {
-            for project in &todo.projects {
-                grouped
-                    .entry(project.clone())
-                    .or_insert_with(Vec::new)
-                    .push(todo.clone());
-            }
-        }
-    }
-    grouped
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -661,72 +755,6 @@ Learn Rust +learning @coding id:rust-001";
         fs::remove_file(&test_file).ok();
     }
 
-    #[test]
-    fn test_group_todos_by_project() {
-        let todos = vec![
-            Item {
-                completed: false,
-                priority: Some('A'),
-                creation_date: None,
-                completion_date: None,
-                description: "Task 1".to_string(),
-                projects: vec!["work".to_string()],
-                contexts: vec![],
-                id: Some("1".to_string()),
-                key_values: HashMap::new(),
-                line_number: 1,
-                md_meta: None,
-            },
-            Item {
-                completed: false,
-                priority: Some('B'),
-                creation_date: None,
-                completion_date: None,
-                description: "Task 2".to_string(),
-                projects: vec!["personal".to_string()],
-                contexts: vec![],
-                id: Some("2".to_string()),
-                key_values: HashMap::new(),
-                line_number: 2,
-                md_meta: None,
-            },
-            Item {
-                completed: false,
-                priority: None,
-                creation_date: None,
-                completion_date: None,
-                description: "Task 3".to_string(),
-                projects: vec![],
-                contexts: vec![],
-                id: Some("3".to_string()),
-                key_values: HashMap::new(),
-                line_number: 3,
-                md_meta: None,
-            },
-            Item {
-                completed: false,
-                priority: None,
-                creation_date: None,
-                completion_date: None,
-                description: "Task 4".to_string(),
-                projects: vec!["work".to_string(), "urgent".to_string()],
-                contexts: vec![],
-                id: Some("4".to_string()),
-                key_values: HashMap::new(),
-                line_number: 4,
-                md_meta: None,
-            },
-        ];
-
-        let grouped = group_todos_by_project_owned(&todos);
-
-        assert_eq!(grouped.len(), 4); // work, personal, No Project, urgent
-        assert_eq!(grouped.get("work").unwrap().len(), 2); // Task 1 and Task 4
-        assert_eq!(grouped.get("personal").unwrap().len(), 1); // Task 2
-        assert_eq!(grouped.get("No Project").unwrap().len(), 1); // Task 3
-        assert_eq!(grouped.get("urgent").unwrap().len(), 1); // Task 4
-    }
-
     #[test]
     fn test_add_missing_ids() {
         let temp_dir = std::env::temp_dir();
@@ -773,7 +801,7 @@ Learn Rust +learning @coding id:task-003";
         fs::write(&todo_file, content).unwrap();
 
         // Mark task-002 as complete
-        mark_complete(todo_file.to_str().unwrap(), "task-002").unwrap();
+        mark_complete(todo_file.to_str().unwrap(), "task-002", "next").unwrap();
 
         // Check todo.txt - should have 2 remaining tasks
         let remaining_content = fs::read_to_string(&todo_file).unwrap();
@@ -798,6 +826,119 @@ Learn Rust +learning @coding id:task-003";
         fs::remove_file(&done_file).ok();
     }
 
+    #[test]
+    fn test_add_item_resolves_relative_dates() {
+        let dir = tempfile::tempdir().unwrap();
+        let inbox = dir.path().join("inbox.txt");
+        let today = chrono::Local::now().date_naive();
+
+        let item = add_item(inbox.to_str().unwrap(), "Call Ann due:tomorrow t:today").unwrap();
+        assert_eq!(item.due_date(), today.succ_opt());
+        assert_eq!(item.threshold_date(), Some(today));
+        let content = fs::read_to_string(&inbox).unwrap();
+        assert!(content.contains(&format!("due:{} t:{today}", today.succ_opt().unwrap())));
+    }
+
+    #[test]
+    fn test_next_occurrence_shifts_dates_and_drops_id() {
+        let today = NaiveDate::from_ymd_opt(2026, 11, 3).unwrap();
+        let next = next_occurrence(
+            "(A) 2026-10-01 Pay rent +home t:2026-10-25 rec:1m due:2026-11-01 id:r1",
+            today,
+        );
+        assert_eq!(
+            next.as_deref(),
+            Some("(A) 2026-11-03 Pay rent +home t:2026-11-26 rec:1m due:2026-12-03")
+        );
+    }
+
+    #[test]
+    fn test_next_occurrence_strict_month_end() {
+        let today = NaiveDate::from_ymd_opt(2027, 2, 2).unwrap();
+        let next = next_occurrence("Close books rec:+1m due:2027-01-31 id:c1", today);
+        assert_eq!(
+            next.as_deref(),
+            Some("2027-02-02 Close books rec:+1m due:2027-02-28")
+        );
+    }
+
+    #[test]
+    fn test_next_occurrence_adds_due_when_missing() {
+        let today = NaiveDate::from_ymd_opt(2028, 2, 29).unwrap();
+        let next = next_occurrence("Renew domain rec:1y", today);
+        assert_eq!(
+            next.as_deref(),
+            Some("2028-02-29 Renew domain rec:1y due:2029-02-28")
+        );
+    }
+
+    #[test]
+    fn test_next_occurrence_ignores_plain_and_invalid() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        assert!(next_occurrence("Plain task due:2026-10-20", today).is_none());
+        assert!(next_occurrence("Odd rec:often", today).is_none());
+        assert!(next_occurrence("x 2026-10-01 Done rec:1d", today).is_none());
+    }
+
+    #[test]
+    fn test_mark_complete_recurring_appends_next_and_copies_md() {
+        let dir = tempfile::tempdir().unwrap();
+        let todo_file = dir.path().join("todo.txt");
+        fs::write(
+            &todo_file,
+            "Other id:o1\nWater plants rec:1w due:2026-10-10 id:w1\n",
+        )
+        .unwrap();
+        fs::create_dir(dir.path().join("todos")).unwrap();
+        fs::write(dir.path().join("todos/w1.md"), "# Ferns first").unwrap();
+
+        let completion = mark_complete(todo_file.to_str().unwrap(), "w1", "w2")
+            .unwrap()
+            .unwrap();
+        assert!(completion.archived.completed);
+        assert_eq!(completion.archived.id.as_deref(), Some("w1"));
+        let next = completion.next.expect("next occurrence");
+        let new_id = next.id.clone().unwrap();
+        assert_eq!(new_id, "w2");
+        assert_eq!(next.description, "Water plants");
+
+        let content = fs::read_to_string(&todo_file).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "Other id:o1");
+        assert!(lines[1].ends_with(&format!(
+            "rec:1w due:{} id:{new_id}",
+            next.due_date().unwrap()
+        )));
+        assert!(
+            fs::read_to_string(dir.path().join("done.txt"))
+                .unwrap()
+                .contains("id:w1")
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join(format!("todos/{new_id}.md"))).unwrap(),
+            "# Ferns first"
+        );
+    }
+
+    #[test]
+    fn test_mark_complete_recurring_copies_no_md_when_the_write_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let todo_file = dir.path().join("todo.txt");
+        fs::write(&todo_file, "Water plants rec:1w id:w1\n").unwrap();
+        fs::create_dir(dir.path().join("todos")).unwrap();
+        fs::write(dir.path().join("todos/w1.md"), "# Ferns first").unwrap();
+        // done.txt cannot be appended to
+        fs::create_dir(dir.path().join("done.txt")).unwrap();
+
+        assert!(mark_complete(todo_file.to_str().unwrap(), "w1", "w2").is_err());
+        assert!(!dir.path().join("todos/w2.md").exists());
+        assert_eq!(
+            fs::read_to_string(&todo_file).unwrap(),
+            "Water plants rec:1w id:w1\n"
+        );
+    }
+
     #[test]
     fn test_delete_todo_removes_matching_line() {
         let temp_dir = std::env::temp_dir();
@@ -1017,6 +1158,68 @@ Learn Rust +learning @coding id:task-003";
         fs::remove_dir_all(&temp_dir).ok();
     }
 
+    #[test]
+    fn test_reopen_line_drops_completion_marks_only() {
+        assert_eq!(
+            reopen_line("x 2026-10-01 2026-09-01 Call Bob +work id:a"),
+            "2026-09-01 Call Bob +work id:a"
+        );
+        assert_eq!(
+            reopen_line("x (A) 2026-10-01 Pay  rent id:b from:waiting"),
+            "(A) Pay  rent id:b"
+        );
+        assert_eq!(reopen_line("x Undated id:c"), "Undated id:c");
+    }
+
+    #[test]
+    fn test_reopen_line_keeps_user_from_tags() {
+        assert_eq!(
+            reopen_line("x 2026-10-01 Gift from:alice id:a from:someday"),
+            "Gift from:alice id:a"
+        );
+        assert_eq!(
+            reopen_line("x 2026-10-01 Gift id:a from:alice"),
+            "Gift id:a from:alice"
+        );
+        assert_eq!(
+            reopen_line("x 2026-10-01 Gift from:todo id:a"),
+            "Gift from:todo id:a"
+        );
+    }
+
+    #[test]
+    fn test_uncomplete_returns_item_to_its_origin() {
+        let dir = tempfile::tempdir().unwrap();
+        let waiting = dir.path().join("waiting.txt");
+        let done = dir.path().join("done.txt");
+        fs::write(&waiting, "Parcel +home id:w1\r\nOther id:w2\r\n").unwrap();
+        fs::write(&done, "x 2026-01-01 Old id:o1\n").unwrap();
+        let (waiting, done) = (waiting.to_str().unwrap(), done.to_str().unwrap());
+
+        mark_complete(waiting, "w1", "next").unwrap();
+        let archived = fs::read_to_string(done).unwrap();
+        assert!(archived.ends_with(" Parcel +home id:w1 from:waiting\n"));
+        let item = load_todos(done)
+            .unwrap()
+            .into_iter()
+            .find(|t| t.id.as_deref() == Some("w1"))
+            .unwrap();
+        assert_eq!(item.origin(), ViewMode::Waiting);
+
+        let reopened = uncomplete(done, waiting, "w1").unwrap().unwrap();
+        assert!(!reopened.completed);
+        assert_eq!(
+            fs::read_to_string(waiting).unwrap(),
+            "Other id:w2\r\nParcel +home id:w1\r\n"
+        );
+        assert_eq!(
+            fs::read_to_string(done).unwrap(),
+            "x 2026-01-01 Old id:o1\n"
+        );
+        assert!(uncomplete(done, waiting, "w1").unwrap().is_none());
+        assert_eq!(Item::parse("x Old id:o1", 1).origin(), ViewMode::Todo);
+    }
+
     #[test]
     fn test_mark_complete_with_priority() {
         let temp_dir = std::env::temp_dir().join("torudo_test_priority");
@@ -1031,7 +1234,7 @@ Learn Rust +learning @coding id:task-003";
         let content = "(A) 2024-01-10 Call Mom +family @phone id:task-001";
         fs::write(&todo_file, content).unwrap();
 
-        mark_complete(todo_file.to_str().unwrap(), "task-001").unwrap();
+        mark_complete(todo_file.to_str().unwrap(), "task-001", "next").unwrap();
 
         let done_content = fs::read_to_string(&done_file).unwrap();
 
@@ -1045,6 +1248,98 @@ Learn Rust +learning @coding id:task-003";
         fs::remove_dir_all(&temp_dir).ok();
     }
 
+    #[test]
+    fn test_find_line_returns_raw_line() {
+        let temp_dir = std::env::temp_dir().join("torudo_test_find_line");
+        fs::create_dir_all(&temp_dir).unwrap();
+        let todo_file = temp_dir.join("todo.txt");
+        fs::write(
+            &todo_file,
+            "x 2024-01-11 Done id:t1\n(B) 2024-01-10 Task  two +p due:2024-02-01 id:t2\n",
+        )
+        .unwrap();
+        let path = todo_file.to_str().unwrap();
+
+        assert_eq!(
+            find_line(path, "t2").unwrap().as_deref(),
+            Some("(B) 2024-01-10 Task  two +p due:2024-02-01 id:t2")
+        );
+        assert_eq!(find_line(path, "t1").unwrap(), None);
+        assert_eq!(find_line(path, "missing").unwrap(), None);
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn test_replace_line_preserves_order_and_trailing_newline() {
+        let temp_dir = std::env::temp_dir().join("torudo_test_replace_line");
+        fs::create_dir_all(&temp_dir).unwrap();
+        let todo_file = temp_dir.join("todo.txt");
+        fs::write(
+            &todo_file,
+            "Task one id:t1\nTask two id:t2\n\nTask three id:t3\n",
+        )
+        .unwrap();
+
+        let replaced = replace_line(
+            todo_file.to_str().unwrap(),
+            "t2",
+            "Task 2 due:2024-03-01 id:t2",
+        )
+        .unwrap();
+
+        assert!(replaced);
+        let content = fs::read_to_string(&todo_file).unwrap();
+        assert_eq!(
+            content,
+            "Task one id:t1\nTask 2 due:2024-03-01 id:t2\n\nTask three id:t3\n"
+        );
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn test_replace_line_without_trailing_newline() {
+        let temp_dir = std::env::temp_dir().join("torudo_test_replace_line_no_nl");
+        fs::create_dir_all(&temp_dir).unwrap();
+        let todo_file = temp_dir.join("todo.txt");
+        fs::write(&todo_file, "Task one id:t1\nTask two id:t2").unwrap();
+
+        replace_line(todo_file.to_str().unwrap(), "t1", "Task 1 id:t1").unwrap();
+
+        let content = fs::read_to_string(&todo_file).unwrap();
+        assert_eq!(content, "Task 1 id:t1\nTask two id:t2");
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn test_replace_line_rejects_dropped_or_changed_id() {
+        let temp_dir = std::env::temp_dir().join("torudo_test_replace_line_reject");
+        fs::create_dir_all(&temp_dir).unwrap();
+        let todo_file = temp_dir.join("todo.txt");
+        let initial = "Task one id:t1\n";
+        fs::write(&todo_file, initial).unwrap();
+        let path = todo_file.to_str().unwrap();
+
+        assert!(replace_line(path, "t1", "Task one").is_err());
+        assert!(replace_line(path, "t1", "Task one id:other").is_err());
+        assert!(replace_line(path, "t1", "").is_err());
+        assert_eq!(fs::read_to_string(&todo_file).unwrap(), initial);
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn test_replace_line_missing_id_returns_false() {
+        let temp_dir = std::env::temp_dir().join("torudo_test_replace_line_missing");
+        fs::create_dir_all(&temp_dir).unwrap();
+        let todo_file = temp_dir.join("todo.txt");
+        fs::write(&todo_file, "Task one id:t1\n").unwrap();
+
+        let replaced = replace_line(todo_file.to_str().unwrap(), "t9", "Task id:t9").unwrap();
+
+        assert!(!replaced);
+        assert_eq!(fs::read_to_string(&todo_file).unwrap(), "Task one id:t1\n");
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+
     #[test]
     fn test_set_priority_adds_to_unprioritized() {
         let temp_dir = std::env::temp_dir().join("torudo_test_set_priority_add");
@@ -1491,4 +1786,245 @@ Learn Rust +learning @coding id:task-003";
         let today = NaiveDate::from_ymd_opt(2026, 4, 14).unwrap();
         assert!(!item.is_overdue(today));
     }
+
+    // Property tests: random files mixing LF/CRLF, blank lines, comments,
+    // completed items and a missing trailing newline go through each edit,
+    // and every line the edit did not target must come out byte-identical.
+    // Expectations are built from the generated byte slices, not by parsing.
+
+    type Lines = Vec<(String, &'static str)>;
+
+    fn join_lines(lines: &[(String, &'static str)]) -> String {
+        lines.iter().fold(String::new(), |mut out, (text, ending)| {
+            out.push_str(text);
+            out.push_str(ending);
+            out
+        })
+    }
+
+    /// The file with line `index` replaced by `text`, terminator kept.
+    fn replaced(lines: &Lines, index: usize, text: &str) -> String {
+        format!(
+            "{}{text}{}{}",
+            join_lines(&lines[..index]),
+            lines[index].1,
+            join_lines(&lines[index + 1..])
+        )
+    }
+
+    /// The file without line `index`. When that was an unterminated last
+    /// line, the file still has no trailing newline afterwards.
+    fn removed(lines: &Lines, index: usize) -> String {
+        let before = join_lines(&lines[..index]);
+        if index + 1 == lines.len() && lines[index].1.is_empty() {
+            let before = before
+                .strip_suffix("\r\n")
+                .or_else(|| before.strip_suffix('\n'))
+                .unwrap_or(&before);
+            return before.to_string();
+        }
+        before + &join_lines(&lines[index + 1..])
+    }
+
+    /// `content` with `text` added as a last line, terminated like the first
+    /// line of the file (LF when it has none). A file without a trailing
+    /// newline keeps lacking one.
+    fn appended(content: &str, text: &str) -> String {
+        let eol = match content.find('\n') {
+            Some(i) if content[..i].ends_with('\r') => "\r\n",
+            _ => "\n",
+        };
+        if content.is_empty() || content.ends_with('\n') {
+            format!("{content}{text}{eol}")
+        } else {
+            format!("{content}{eol}{text}")
+        }
+    }
+
+    /// A random file and the indices of its open items, which all have ids.
+    fn random_file(rng: &mut fastrand::Rng, with_missing_ids: bool) -> (Lines, Vec<usize>) {
+        let mut lines = Lines::new();
+        let mut open = Vec::new();
+        for n in 0..rng.usize(0..12) {
+            let text = match rng.u8(0..6) {
+                0 => String::new(),
+                1 => "  ".to_string(),
+                2 => format!("# note {n} id:c{n}"),
+                3 => format!("x 2026-01-0{} Done {n} +p id:d{n}", rng.u8(1..9)),
+                4 if with_missing_ids => format!("Bare {n} @ctx"),
+                _ => {
+                    open.push(lines.len());
+                    let priority = ["", "(A) ", "(B) "][rng.usize(0..3)];
+                    format!("{priority}Task {n}  with  spaces id:t{n}")
+                }
+            };
+            let ending = if rng.bool() { "\n" } else { "\r\n" };
+            lines.push((text, ending));
+        }
+        if rng.bool()
+            && let Some(last) = lines.last_mut()
+            && !last.0.is_empty()
+        {
+            last.1 = "";
+        }
+        (lines, open)
+    }
+
+    fn id_of(lines: &Lines, index: usize) -> String {
+        Item::parse(&lines[index].0, index + 1).id.unwrap()
+    }
+
+    #[test]
+    fn test_expectation_helpers_on_fixed_files() {
+        let lines: Lines = vec![
+            ("a".into(), "\r\n"),
+            (String::new(), "\n"),
+            ("b".into(), ""),
+        ];
+        assert_eq!(replaced(&lines, 2, "c"), "a\r\n\nc");
+        assert_eq!(removed(&lines, 2), "a\r\n");
+        assert_eq!(removed(&lines, 0), "\nb");
+        assert_eq!(appended("a\r\n\nb", "c"), "a\r\n\nb\r\nc");
+        assert_eq!(appended("a\nb\r\n", "c"), "a\nb\r\nc\n");
+        assert_eq!(appended("", "c"), "c\n");
+    }
+
+    #[test]
+    fn test_round_trip_of_random_files_is_lossless() {
+        let mut rng = fastrand::Rng::with_seed(7);
+        for _ in 0..200 {
+            let (lines, _) = random_file(&mut rng, true);
+            let content = join_lines(&lines);
+            let doc = TodoFile::parse(&content);
+            assert_eq!(doc.to_string(), content);
+            let items: Vec<&str> = doc.items().map(|(_, text)| text).collect();
+            let expected: Vec<&str> = lines
+                .iter()
+                .map(|(text, _)| text.as_str())
+                .filter(|text| !text.trim().is_empty() && !text.starts_with('#'))
+                .collect();
+            assert_eq!(items, expected, "{content:?}");
+        }
+    }
+
+    #[test]
+    fn test_edits_leave_untouched_lines_byte_identical() {
+        let today = chrono::Local::now().date_naive().to_string();
+        let mut rng = fastrand::Rng::with_seed(42);
+        for case in 0..300 {
+            let dir = tempfile::tempdir().unwrap();
+            let todo = dir.path().join("todo.txt");
+            let dest = dir.path().join("ref.txt");
+            let done = dir.path().join(&crate::config::get().files.done);
+            let todo_path = todo.to_str().unwrap();
+
+            let (lines, open) = random_file(&mut rng, false);
+            let (dest_lines, _) = random_file(&mut rng, false);
+            let (done_lines, _) = random_file(&mut rng, false);
+            let mut expected_todo = join_lines(&lines);
+            let mut expected_dest = join_lines(&dest_lines);
+            let mut expected_done = join_lines(&done_lines);
+            fs::write(&todo, &expected_todo).unwrap();
+            fs::write(&dest, &expected_dest).unwrap();
+            fs::write(&done, &expected_done).unwrap();
+
+            let target = (!open.is_empty()).then(|| open[rng.usize(0..open.len())]);
+            let op = if target.is_some() { rng.u8(0..6) } else { 5 };
+            match (op, target) {
+                (0, Some(index)) => {
+                    let id = id_of(&lines, index);
+                    let priority = [None, Some('A'), Some('C')][rng.usize(0..3)];
+                    set_priority(todo_path, &id, priority).unwrap();
+                    let (_, rest) = split_priority_prefix(&lines[index].0);
+                    let line =
+                        priority.map_or_else(|| rest.to_string(), |c| format!("({c}) {rest}"));
+                    expected_todo = replaced(&lines, index, &line);
+                }
+                (1, Some(index)) => {
+                    let id = id_of(&lines, index);
+                    let new_line = format!("Edited {case} id:{id}");
+                    assert!(replace_line(todo_path, &id, &new_line).unwrap());
+                    expected_todo = replaced(&lines, index, &new_line);
+                }
+                (2, Some(index)) => {
+                    let id = id_of(&lines, index);
+                    assert!(delete_todo(todo_path, &id).unwrap());
+                    expected_todo = removed(&lines, index);
+                }
+                (3, Some(index)) => {
+                    let id = id_of(&lines, index);
+                    move_to_file(todo_path, dest.to_str().unwrap(), &id).unwrap();
+                    expected_todo = removed(&lines, index);
+                    expected_dest = appended(&expected_dest, &lines[index].0);
+                }
+                (4, Some(index)) => {
+                    let id = id_of(&lines, index);
+                    mark_complete(todo_path, &id, "next").unwrap();
+                    expected_todo = removed(&lines, index);
+                    let completed = format!(
+                        "x {} from:todo",
+                        insert_date_after_priority(&lines[index].0, &today)
+                    );
+                    expected_done = appended(&expected_done, &completed);
+                }
+                _ => {
+                    let line = format!("Appended {case} id:a{case}");
+                    append_todo(todo_path, &line).unwrap();
+                    expected_todo = appended(&expected_todo, &line);
+                }
+            }
+
+            let context = format!("case {case}, op {op}");
+            assert_eq!(
+                fs::read_to_string(&todo).unwrap(),
+                expected_todo,
+                "{context}"
+            );
+            assert_eq!(
+                fs::read_to_string(&dest).unwrap(),
+                expected_dest,
+                "{context}"
+            );
+            assert_eq!(
+                fs::read_to_string(&done).unwrap(),
+                expected_done,
+                "{context}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_add_missing_ids_only_touches_lines_without_id() {
+        let mut rng = fastrand::Rng::with_seed(3);
+        for case in 0..200 {
+            let dir = tempfile::tempdir().unwrap();
+            let todo = dir.path().join("todo.txt");
+            let (lines, _) = random_file(&mut rng, true);
+            fs::write(&todo, join_lines(&lines)).unwrap();
+
+            add_missing_ids(todo.to_str().unwrap()).unwrap();
+
+            // Walk the output along the original lines: each must be there
+            // byte for byte, bare items followed by " id:<uuid>"
+            let after = fs::read_to_string(&todo).unwrap();
+            let mut rest = after.as_str();
+            for (text, ending) in &lines {
+                rest = rest
+                    .strip_prefix(text.as_str())
+                    .unwrap_or_else(|| panic!("case {case}: {text:?} changed in {after:?}"));
+                if text.starts_with("Bare ") {
+                    let (id, tail) = rest
+                        .strip_prefix(" id:")
+                        .and_then(|r| r.split_at_checked(36))
+                        .unwrap_or_else(|| panic!("case {case}: no id after {text:?}"));
+                    assert!(Uuid::parse_str(id).is_ok(), "case {case}: {id}");
+                    rest = tail;
+                }
+                rest = rest
+                    .strip_prefix(ending)
+                    .unwrap_or_else(|| panic!("case {case}: terminator of {text:?} changed"));
+            }
+            assert_eq!(rest, "", "case {case}");
+        }
+    }
 }
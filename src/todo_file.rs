@@ -0,0 +1,244 @@
+use crate::todo::Item;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+
+/// One physical line and the terminator it was read with: `"\n"`,
+/// `"\r\n"`, or `""` for a last line without a trailing newline.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Line {
+    text: String,
+    ending: &'static str,
+}
+
+/// A todo.txt file held byte for byte: line endings (LF or CRLF, even mixed),
+/// blank lines, comments and whether the file ends in a newline all survive
+/// an edit, so writing it back only changes the lines that were edited.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TodoFile {
+    lines: Vec<Line>,
+    /// Terminator for added lines: the first one in the file, LF if none
+    eol: Option<&'static str>,
+}
+
+impl TodoFile {
+    pub fn parse(content: &str) -> Self {
+        let lines = content
+            .split_inclusive('\n')
+            .map(|chunk| {
+                let (text, ending) = chunk
+                    .strip_suffix("\r\n")
+                    .map(|text| (text, "\r\n"))
+                    .or_else(|| chunk.strip_suffix('\n').map(|text| (text, "\n")))
+                    .unwrap_or((chunk, ""));
+                Line {
+                    text: text.to_string(),
+                    ending,
+                }
+            })
+            .collect::<Vec<_>>();
+        let eol = lines.iter().map(|l| l.ending).find(|e| !e.is_empty());
+        Self { lines, eol }
+    }
+
+    pub fn read(path: &str) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// Like `read`, but a missing file is an empty document.
+    pub fn read_or_empty(path: &str) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(Self::parse(&content)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub const fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn line(&self, index: usize) -> &str {
+        &self.lines[index].text
+    }
+
+    /// Index and text of every item line, skipping blank lines and `#`
+    /// comments.
+    pub fn items(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| {
+                let text = l.text.trim_start();
+                !text.is_empty() && !text.starts_with('#')
+            })
+            .map(|(i, l)| (i, l.text.as_str()))
+    }
+
+    /// First line carrying `id:{id}`, completed or not.
+    pub fn find(&self, id: &str) -> Option<usize> {
+        self.items()
+            .find(|(i, text)| Item::parse(text, i + 1).id.as_deref() == Some(id))
+            .map(|(i, _)| i)
+    }
+
+    /// First open (not `x `-completed) line carrying `id:{id}`.
+    pub fn find_open(&self, id: &str) -> Option<usize> {
+        self.items()
+            .filter(|(_, text)| !text.starts_with("x "))
+            .find(|(i, text)| Item::parse(text, i + 1).id.as_deref() == Some(id))
+            .map(|(i, _)| i)
+    }
+
+    /// Replace the text of line `index`, keeping its terminator.
+    pub fn set_line(&mut self, index: usize, text: String) {
+        self.lines[index].text = text;
+    }
+
+    /// Remove line `index` and return its text. When it was the unterminated
+    /// last line, the new last line loses its terminator instead, so the
+    /// file still does not end in a newline.
+    pub fn remove_line(&mut self, index: usize) -> String {
+        let removed = self.lines.remove(index);
+        if removed.ending.is_empty()
+            && index == self.lines.len()
+            && let Some(last) = self.lines.last_mut()
+        {
+            last.ending = "";
+        }
+        removed.text
+    }
+
+    /// Add `text` as the last line. A file that did not end in a newline
+    /// still does not; an empty one gets a terminated line.
+    pub fn push_line(&mut self, text: String) {
+        let eol = self.eol.unwrap_or("\n");
+        let ending = match self.lines.last_mut() {
+            Some(last) if last.ending.is_empty() => {
+                last.ending = eol;
+                ""
+            }
+            _ => eol,
+        };
+        self.lines.push(Line { text, ending });
+    }
+}
+
+/// Add `text` as the last line of the file at `path`, creating it if
+/// needed, without reading the whole file: only the first line (for the
+/// line ending) and the last byte are looked at. The result is the same as
+/// `push_line` on the parsed file.
+pub fn append_line(path: &str, text: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(path)?;
+    let mut first = Vec::new();
+    BufReader::new(&file).read_until(b'\n', &mut first)?;
+    let eol = if first.ends_with(b"\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut last = [b'\n'];
+    if file.metadata()?.len() > 0 {
+        file.seek(SeekFrom::End(-1))?;
+        file.read_exact(&mut last)?;
+    }
+    let out = if last[0] == b'\n' {
+        format!("{text}{eol}")
+    } else {
+        // Still no trailing newline afterwards, like the file had
+        format!("{eol}{text}")
+    };
+    file.write_all(out.as_bytes())?;
+    file.sync_data()
+}
+
+impl fmt::Display for TodoFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            f.write_str(&line.text)?;
+            f.write_str(line.ending)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_is_byte_identical() {
+        for content in [
+            "",
+            "\n",
+            "a id:1",
+            "a id:1\n",
+            "a id:1\r\nb id:2\r\n",
+            "# comment\n\n  \na id:1\r\n\nb id:2",
+            "mixed\r\nendings\nhere\r\n",
+        ] {
+            assert_eq!(TodoFile::parse(content).to_string(), content);
+        }
+    }
+
+    #[test]
+    fn test_find_skips_blanks_comments_and_completed_lines() {
+        let doc = TodoFile::parse("\n# note id:a\nx done id:a\nopen id:a\n");
+        assert_eq!(doc.find("a"), Some(2));
+        assert_eq!(doc.find_open("a"), Some(3));
+        assert_eq!(doc.find("zzz"), None);
+    }
+
+    #[test]
+    fn test_remove_and_push_keep_trailing_newline_state() {
+        let mut doc = TodoFile::parse("a\r\nb");
+        assert_eq!(doc.remove_line(1), "b");
+        assert_eq!(doc.to_string(), "a");
+        doc.push_line("c".to_string());
+        assert_eq!(doc.to_string(), "a\r\nc");
+
+        let mut doc = TodoFile::parse("a\nb\n");
+        doc.remove_line(0);
+        doc.push_line("c".to_string());
+        assert_eq!(doc.to_string(), "b\nc\n");
+
+        let mut doc = TodoFile::default();
+        doc.push_line("first".to_string());
+        assert_eq!(doc.to_string(), "first\n");
+    }
+
+    #[test]
+    fn test_append_line_matches_push_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("done.txt");
+        let path = path.to_str().unwrap();
+        for content in [
+            None,
+            Some(""),
+            Some("a\n"),
+            Some("a"),
+            Some("a\r\nb\r\n"),
+            Some("a\r\nb"),
+            Some("\n\n"),
+            Some("a\nb\r\n"),
+        ] {
+            let _ = fs::remove_file(path);
+            if let Some(content) = content {
+                fs::write(path, content).unwrap();
+            }
+            let mut doc = TodoFile::parse(content.unwrap_or_default());
+            doc.push_line("x new id:n".to_string());
+
+            append_line(path, "x new id:n").unwrap();
+            assert_eq!(
+                fs::read_to_string(path).unwrap(),
+                doc.to_string(),
+                "{content:?}"
+            );
+        }
+    }
+}
@@ -1,11 +1,15 @@
-use crate::app_state::{AppState, TemplateState, ViewMode};
+use crate::app_state::{AppState, InputState, TemplateState, View, ViewMode};
 use crate::help;
+use crate::keymap::{self, Action};
+use crate::line_editor::LineEditor;
 use crate::md_preview::format_elapsed;
+use crate::mouse::{self, TabTarget};
+use crate::theme::{self, Theme};
 use crate::todo::Item;
 use crate::url::strip_urls;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Flex, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Clear, Paragraph, Tabs},
 };
@@ -13,58 +17,54 @@ use std::time::SystemTime;
 use unicode_width::UnicodeWidthChar;
 
 const SELECTED_ICON: &str = "> ";
-const PENDING_FG: Color = Color::Rgb(120, 120, 120);
-const MD_META_FG: Color = Color::Rgb(170, 170, 170);
 
 fn selected_icon_span() -> Span<'static> {
     Span::styled(
         SELECTED_ICON,
-        Style::default()
-            .fg(Color::Yellow)
-            .add_modifier(Modifier::BOLD),
+        theme::active().selection.add_modifier(Modifier::BOLD),
     )
 }
 
 pub fn create_todo_spans(todo: &Item) -> Vec<Span<'static>> {
+    let theme = theme::active();
     let mut spans = Vec::new();
     if todo.completed {
-        spans.push(Span::styled("✓ ", Style::default().fg(Color::Green)));
+        spans.push(Span::styled("✓ ", theme.done_badge));
     }
     if let Some(priority) = todo.priority {
-        let color = match priority {
-            'A' => Color::Red,
-            'B' => Color::Yellow,
-            'C' => Color::Blue,
-            _ => Color::White,
+        let style = match priority {
+            'A' => theme.priority_a,
+            'B' => theme.priority_b,
+            'C' => theme.priority_c,
+            _ => theme.priority_other,
         };
-        spans.push(Span::styled(
-            format!("({priority}) "),
-            Style::default().fg(color).add_modifier(Modifier::BOLD),
-        ));
+        spans.push(Span::styled(format!("({priority}) "), style));
     }
     let (display_text, has_urls) = strip_urls(&todo.description);
     if has_urls {
-        spans.push(Span::styled("🔗 ", Style::default().fg(Color::Blue)));
+        spans.push(Span::styled("🔗 ", theme.url_badge));
     }
     spans.push(Span::raw(display_text));
     for context in &todo.contexts {
-        spans.push(Span::styled(
-            format!(" @{context}"),
-            Style::default().fg(Color::Cyan),
-        ));
+        spans.push(Span::styled(format!(" @{context}"), theme.context));
     }
     spans
 }
 
-pub fn get_todo_border_style(is_selected: bool, is_overdue: bool, is_dimmed: bool) -> Style {
+pub const fn get_todo_border_style(
+    theme: &Theme,
+    is_selected: bool,
+    is_overdue: bool,
+    is_dimmed: bool,
+) -> Style {
     if is_selected {
-        Style::default().fg(Color::Yellow)
+        theme.selection
     } else if is_overdue {
-        Style::default().fg(Color::Red)
+        theme.overdue
     } else if is_dimmed {
-        Style::default().fg(Color::DarkGray)
+        theme.dimmed
     } else {
-        Style::default().fg(Color::White)
+        theme.border
     }
 }
 
@@ -120,13 +120,7 @@ fn calc_todo_height(todo: &Item, available_width: u16) -> u16 {
 }
 
 fn hint_label_span(label: &str) -> Span<'static> {
-    Span::styled(
-        format!(" {label} "),
-        Style::default()
-            .fg(Color::Black)
-            .bg(Color::Yellow)
-            .add_modifier(Modifier::BOLD),
-    )
+    Span::styled(format!(" {label} "), theme::active().hint_label)
 }
 
 struct ColumnLayout {
@@ -202,6 +196,8 @@ const fn column_params(state: &AppState, col_idx: usize) -> (bool, usize, usize)
     (is_active, selected, scroll)
 }
 
+/// Draw one board column. Returns the scroll offset used and the area of
+/// each drawn card, the first being row `offset`.
 #[allow(clippy::too_many_arguments)]
 pub fn draw_project_column(
     f: &mut ratatui::Frame,
@@ -215,11 +211,12 @@ pub fn draw_project_column(
     now: SystemTime,
     col_idx: usize,
     hint: Option<&crate::app_state::HintState>,
-) -> usize {
+) -> (usize, Vec<Rect>) {
+    let theme = theme::active();
     let border_style = if is_active_column {
-        Style::default().fg(Color::Yellow)
+        theme.selection
     } else {
-        Style::default().fg(Color::White)
+        theme.border
     };
 
     let title_text = format!("{project_name} ({})", project_todos.len());
@@ -245,7 +242,7 @@ pub fn draw_project_column(
     f.render_widget(project_block, column_area);
 
     if project_todos.is_empty() {
-        return scroll_offset;
+        return (scroll_offset, Vec::new());
     }
 
     let visible_todos = &project_todos[layout.offset..layout.visible_end];
@@ -268,9 +265,9 @@ pub fn draw_project_column(
         let spans = create_todo_spans(todo);
         let text: String = spans.iter().map(|s| s.content.as_ref()).collect();
         let is_selected = is_active_column && actual_idx == selected_in_column;
-        let is_overdue = todo.is_overdue(today);
+        let is_overdue = !todo.completed && todo.is_overdue(today);
         let border_style =
-            get_todo_border_style(is_selected, is_overdue, todo.completed || is_pending);
+            get_todo_border_style(theme, is_selected, is_overdue, todo.completed || is_pending);
 
         let effective_width = usize::from(todo_layout[i].width.saturating_sub(2));
         let mut wrapped_lines: Vec<Line<'_>> = wrap_text(&text, effective_width)
@@ -281,7 +278,7 @@ pub fn draw_project_column(
             for preview_text in &meta.preview {
                 wrapped_lines.push(Line::from(Span::styled(
                     format!("☐ {preview_text}"),
-                    Style::default().fg(MD_META_FG),
+                    theme.meta,
                 )));
             }
         }
@@ -296,19 +293,17 @@ pub fn draw_project_column(
             block = block.title(Line::from(hint_label_span(label)).right_aligned());
         }
         if let Some(label) = meta_label(todo, now) {
-            block = block.title_bottom(
-                Line::from(Span::styled(label, Style::default().fg(MD_META_FG))).right_aligned(),
-            );
+            block = block.title_bottom(Line::from(Span::styled(label, theme.meta)).right_aligned());
         }
         let mut todo_paragraph = Paragraph::new(wrapped_lines).block(block);
         if is_pending {
-            todo_paragraph = todo_paragraph.style(Style::default().fg(PENDING_FG));
+            todo_paragraph = todo_paragraph.style(theme.pending);
         }
 
         f.render_widget(todo_paragraph, todo_layout[i]);
     }
 
-    layout.offset
+    (layout.offset, todo_layout[..visible_todos.len()].to_vec())
 }
 
 fn draw_project_columns(f: &mut ratatui::Frame, state: &mut AppState, area: Rect, now: SystemTime) {
@@ -317,7 +312,7 @@ fn draw_project_columns(f: &mut ratatui::Frame, state: &mut AppState, area: Rect
     if num_columns == 0 {
         let paragraph = Paragraph::new("No items")
             .alignment(Alignment::Center)
-            .style(Style::default().fg(Color::DarkGray));
+            .style(theme::active().dimmed);
         f.render_widget(paragraph, area);
         return;
     }
@@ -356,7 +351,8 @@ fn draw_project_columns(f: &mut ratatui::Frame, state: &mut AppState, area: Rect
     for (col_idx, project_name) in visible_projects.iter().enumerate() {
         if let Some(project_todos) = state.grouped_todos.get(project_name) {
             let (is_active, selected, scroll) = column_params(state, col_idx);
-            let new_scroll = draw_project_column(
+            state.click_map.add_column(columns[col_idx], col_idx);
+            let (new_scroll, cards) = draw_project_column(
                 f,
                 project_todos,
                 project_name,
@@ -372,23 +368,44 @@ fn draw_project_columns(f: &mut ratatui::Frame, state: &mut AppState, area: Rect
             if is_active {
                 state.scroll_offset = new_scroll;
             }
+            for (i, card) in cards.into_iter().enumerate() {
+                state.click_map.add_card(card, col_idx, new_scroll + i);
+            }
         }
     }
 }
 
-fn draw_tab_bar(f: &mut ratatui::Frame, state: &AppState, area: Rect) {
-    let tab_titles: Vec<String> = ViewMode::ALL
+fn draw_tab_bar(f: &mut ratatui::Frame, state: &mut AppState, area: Rect) {
+    let mut tab_titles: Vec<String> = ViewMode::ALL
         .iter()
         .enumerate()
         .map(|(i, m)| format!("{} ({})", m.label(), state.mode_counts[i]))
         .collect();
+    // The agenda and the done archive are not a mode, so while open they
+    // get their own highlighted tab
+    let extra = match state.view {
+        View::Board => None,
+        View::Agenda => Some(TabTarget::Agenda),
+        View::Done => Some(TabTarget::Done),
+    };
+    let selected = if extra.is_some() {
+        tab_titles.push(state.view.label().to_string());
+        tab_titles.len() - 1
+    } else {
+        state.current_mode_index()
+    };
+    let targets = ViewMode::ALL
+        .iter()
+        .map(|m| TabTarget::Mode(*m))
+        .chain(extra);
+    for (tab_area, target) in mouse::tab_areas(area, &tab_titles).into_iter().zip(targets) {
+        state.click_map.add_tab(tab_area, target);
+    }
+    let theme = theme::active();
     let tabs = Tabs::new(tab_titles)
-        .select(state.current_mode_index())
-        .highlight_style(
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        );
+        .select(selected)
+        .style(theme.tab)
+        .highlight_style(theme.tab_active);
     f.render_widget(tabs, area);
 }
 
@@ -399,6 +416,7 @@ pub fn draw_ui(f: &mut ratatui::Frame, state: &mut AppState) {
     }
     let now = SystemTime::now();
     let size = f.area();
+    state.click_map.clear();
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
@@ -416,33 +434,11 @@ pub fn draw_ui(f: &mut ratatui::Frame, state: &mut AppState) {
 
     draw_project_columns(f, state, chunks[1], now);
 
-    let version = env!("CARGO_PKG_VERSION");
-    let footer_spans: Vec<Span<'_>> = if let Some(ref msg) = state.status_message {
-        vec![Span::styled(msg.clone(), Style::default().fg(Color::Green))]
+    if let Some(input) = state.input.as_ref() {
+        draw_input_line(f, input, state.status_message.as_deref(), chunks[2]);
     } else {
-        let mut spans = vec![Span::raw(format!("torudo v{version}"))];
-        if let Some(ref v) = state.update_available {
-            spans.push(Span::styled(
-                format!(" ({v} available! Run: torudo update)"),
-                Style::default().fg(Color::Yellow),
-            ));
-        }
-        let is_todo = state.view_mode == ViewMode::Todo;
-        let is_waiting = state.view_mode == ViewMode::Waiting;
-        let has_claude = state.crmux_available() || state.claude_available();
-        let footer_str = help::footer_entries(is_todo, is_waiting, has_claude)
-            .iter()
-            .map(|(key, desc)| format!("{key}:{desc}"))
-            .collect::<Vec<_>>()
-            .join(" │ ");
-        spans.push(Span::raw(format!(" │ {footer_str}")));
-        spans
-    };
-    let footer = Paragraph::new(Line::from(footer_spans))
-        .block(Block::default().borders(Borders::ALL))
-        .alignment(Alignment::Center);
-
-    f.render_widget(footer, chunks[2]);
+        draw_footer(f, state, chunks[2]);
+    }
 
     // Draw plan modal overlay if open
     if let Some(modal) = &state.plan_modal {
@@ -460,6 +456,111 @@ pub fn draw_ui(f: &mut ratatui::Frame, state: &mut AppState) {
     }
 }
 
+fn footer_key_spans(state: &AppState) -> Vec<Span<'static>> {
+    let version = env!("CARGO_PKG_VERSION");
+    let theme = theme::active();
+    let mut spans = Vec::new();
+    if let Some(ref filter) = state.filter {
+        spans.push(Span::styled(
+            format!("F:{} │ ", filter.source()),
+            theme.accent,
+        ));
+    }
+    if let Some(ref query) = state.search {
+        spans.push(Span::styled(
+            format!("/{query} (Esc: clear) │ "),
+            theme.search,
+        ));
+    }
+    if state.view == View::Board && !state.columns.hidden.is_empty() {
+        spans.push(Span::styled(
+            match keymap::active().display(Action::UnhideColumns) {
+                key if key.is_empty() => format!("{} hidden │ ", state.columns.hidden.len()),
+                key => format!("{} hidden ({key}: show) │ ", state.columns.hidden.len()),
+            },
+            theme.dimmed,
+        ));
+    }
+    spans.push(Span::raw(format!("torudo v{version}")));
+    if let Some(ref v) = state.update_available {
+        spans.push(Span::styled(
+            format!(" ({v} available! Run: torudo update)"),
+            theme.notice,
+        ));
+    }
+    let is_todo = state.view_mode == ViewMode::Todo;
+    let is_waiting = state.view_mode == ViewMode::Waiting;
+    let has_claude = state.crmux_available() || state.claude_available();
+    let footer_str = help::footer_entries(keymap::active(), is_todo, is_waiting, has_claude)
+        .iter()
+        .map(|(key, desc)| format!("{key}:{desc}"))
+        .collect::<Vec<_>>()
+        .join(" │ ");
+    spans.push(Span::raw(format!(" │ {footer_str}")));
+    spans
+}
+
+fn draw_footer(f: &mut ratatui::Frame, state: &AppState, area: Rect) {
+    let footer_spans = state.status_message.as_ref().map_or_else(
+        || footer_key_spans(state),
+        |msg| vec![Span::styled(msg.clone(), theme::active().status)],
+    );
+    let footer = Paragraph::new(Line::from(footer_spans))
+        .block(Block::default().borders(Borders::ALL))
+        .alignment(Alignment::Center);
+
+    f.render_widget(footer, area);
+}
+
+/// Slice of the editor text that fits in `width` cells while keeping the
+/// cursor visible, plus the cursor column within that slice.
+fn input_window(editor: &LineEditor, width: usize) -> (String, usize) {
+    let chars: Vec<char> = editor.text().chars().collect();
+    let cursor = editor.cursor();
+    let mut start = 0;
+    let mut cursor_col = editor.cursor_width();
+    // Reserve one cell so the cursor can sit after the last character.
+    while cursor_col >= width && start < cursor {
+        cursor_col -= chars[start].width().unwrap_or(0);
+        start += 1;
+    }
+    let mut visible = String::new();
+    let mut used = 0;
+    for &c in &chars[start..] {
+        let w = c.width().unwrap_or(0);
+        if used + w > width {
+            break;
+        }
+        visible.push(c);
+        used += w;
+    }
+    (visible, cursor_col)
+}
+
+fn draw_input_line(f: &mut ratatui::Frame, input: &InputState, error: Option<&str>, area: Rect) {
+    let prompt = input.kind.prompt();
+    // A rejected submit leaves the prompt open; show why in the title.
+    let title = error.map_or_else(
+        || Span::raw(" Enter: Save │ Esc: Cancel "),
+        |msg| Span::styled(format!(" {msg} "), theme::active().error),
+    );
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme::active().prompt)
+        .title(title);
+    let inner = block.inner(area);
+    let prompt_width = prompt.chars().count();
+    let avail = (inner.width as usize).saturating_sub(prompt_width);
+    let (visible, cursor_col) = input_window(&input.editor, avail);
+    let line = Line::from(vec![
+        Span::styled(prompt, theme::active().prompt.add_modifier(Modifier::BOLD)),
+        Span::raw(visible),
+    ]);
+    f.render_widget(Paragraph::new(line).block(block), area);
+    let x = inner.x + u16::try_from(prompt_width + cursor_col).unwrap_or(inner.width);
+    f.set_cursor_position((x.min(inner.right().saturating_sub(1)), inner.y));
+}
+
 fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
@@ -484,10 +585,11 @@ fn draw_plan_modal(f: &mut ratatui::Frame, modal: &crate::app_state::PlanModal,
     let modal_area = centered_rect(60, 60, area);
     f.render_widget(Clear, modal_area);
 
+    let theme = theme::active();
     let block = Block::default()
         .title("Get Plans")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Cyan));
+        .border_style(theme.accent);
 
     let inner = block.inner(modal_area);
     f.render_widget(block, modal_area);
@@ -513,11 +615,9 @@ fn draw_plan_modal(f: &mut ratatui::Frame, modal: &crate::app_state::PlanModal,
             let checkbox = if modal.checked[i] { "[x] " } else { "[ ] " };
             let text = format!("{checkbox}{}: {}", plan.project_name, plan.title);
             let style = if i == modal.selected {
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD)
+                theme.selection.add_modifier(Modifier::BOLD)
             } else {
-                Style::default().fg(Color::White)
+                theme.text
             };
             Line::from(Span::styled(text, style))
         })
@@ -536,10 +636,11 @@ fn draw_help_overlay(f: &mut ratatui::Frame, area: Rect, view_mode: ViewMode, ha
     let modal_area = centered_rect(50, 60, area);
     f.render_widget(Clear, modal_area);
 
+    let theme = theme::active();
     let block = Block::default()
         .title("Keyboard Controls")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Cyan));
+        .border_style(theme.accent);
 
     let inner = block.inner(modal_area);
     f.render_widget(block, modal_area);
@@ -551,7 +652,7 @@ fn draw_help_overlay(f: &mut ratatui::Frame, area: Rect, view_mode: ViewMode, ha
 
     let is_todo = view_mode == ViewMode::Todo;
     let is_waiting = view_mode == ViewMode::Waiting;
-    let entries = help::visible_entries(is_todo, is_waiting, has_claude);
+    let entries = help::visible_entries(keymap::active(), is_todo, is_waiting, has_claude);
 
     let max_key_width = entries.iter().map(|e| e.key.len()).max().unwrap_or(0);
 
@@ -561,22 +662,18 @@ fn draw_help_overlay(f: &mut ratatui::Frame, area: Rect, view_mode: ViewMode, ha
             let mut spans = Vec::new();
             if e.indent {
                 // Use a non-whitespace-only span to prevent trim from eating indent
-                spans.push(Span::styled("  ", Style::default().fg(Color::DarkGray)));
+                spans.push(Span::styled("  ", theme.dimmed));
                 spans.push(Span::styled(
                     format!("{:<width$}  ", e.key, width = max_key_width),
-                    Style::default()
-                        .fg(Color::Yellow)
-                        .add_modifier(Modifier::BOLD),
+                    theme.key,
                 ));
             } else {
                 spans.push(Span::styled(
                     format!("{:<width$}    ", e.key, width = max_key_width),
-                    Style::default()
-                        .fg(Color::Yellow)
-                        .add_modifier(Modifier::BOLD),
+                    theme.key,
                 ));
             }
-            spans.push(Span::styled(e.desc, Style::default().fg(Color::White)));
+            spans.push(Span::styled(e.desc, theme.text));
             Line::from(spans)
         })
         .collect();
@@ -584,8 +681,15 @@ fn draw_help_overlay(f: &mut ratatui::Frame, area: Rect, view_mode: ViewMode, ha
     let list = Paragraph::new(lines);
     f.render_widget(list, inner_chunks[0]);
 
-    let footer = Paragraph::new("Press ? or q or Esc to close")
-        .style(Style::default().fg(Color::DarkGray))
+    let keymap = keymap::active();
+    let close_keys: Vec<String> = [Action::Help, Action::Quit]
+        .iter()
+        .map(|a| keymap.display(*a))
+        .filter(|k| !k.is_empty())
+        .chain(std::iter::once("Esc".to_string()))
+        .collect();
+    let footer = Paragraph::new(format!("Press {} to close", close_keys.join(" or ")))
+        .style(theme.dimmed)
         .alignment(Alignment::Center);
     f.render_widget(footer, inner_chunks[1]);
 }
@@ -596,10 +700,11 @@ fn draw_template_overlay(f: &mut ratatui::Frame, area: Rect, tstate: &TemplateSt
     let modal_area = centered_rect(70, 70, area);
     f.render_widget(Clear, modal_area);
 
+    let theme = theme::active();
     let block = Block::default()
         .title("Insert Template")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Cyan));
+        .border_style(theme.accent);
     let inner = block.inner(modal_area);
     f.render_widget(block, modal_area);
 
@@ -619,12 +724,9 @@ fn draw_template_overlay(f: &mut ratatui::Frame, area: Rect, tstate: &TemplateSt
         .enumerate()
         .map(|(i, entry)| {
             let style = if i == tstate.focused {
-                Style::default()
-                    .fg(Color::Black)
-                    .bg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD)
+                theme.focus
             } else {
-                Style::default().fg(Color::White)
+                theme.text
             };
             Line::from(Span::styled(format!(" {} ", entry.name), style))
         })
@@ -633,7 +735,7 @@ fn draw_template_overlay(f: &mut ratatui::Frame, area: Rect, tstate: &TemplateSt
     let list = Paragraph::new(list_lines).block(
         Block::default()
             .borders(Borders::RIGHT)
-            .border_style(Style::default().fg(Color::DarkGray)),
+            .border_style(theme.dimmed),
     );
     f.render_widget(list, panes[0]);
 
@@ -654,12 +756,12 @@ fn draw_template_overlay(f: &mut ratatui::Frame, area: Rect, tstate: &TemplateSt
         })
         .unwrap_or_default();
     let preview = Paragraph::new(preview_text)
-        .style(Style::default().fg(Color::White))
+        .style(theme.text)
         .wrap(ratatui::widgets::Wrap { trim: false });
     f.render_widget(preview, panes[1]);
 
     let footer = Paragraph::new("j/k: move | Enter: insert | Esc/q: cancel")
-        .style(Style::default().fg(Color::DarkGray))
+        .style(theme.dimmed)
         .alignment(Alignment::Center);
     f.render_widget(footer, body_and_footer[1]);
 }
@@ -669,6 +771,7 @@ mod tests {
     use super::*;
     use crate::md_preview::MdMeta;
     use crate::todo::Item;
+    use ratatui::style::Color;
     use std::collections::HashMap;
 
     fn make_item(description: &str) -> Item {
@@ -832,25 +935,25 @@ mod tests {
 
     #[test]
     fn get_todo_border_style_dimmed_is_darkgray() {
-        let style = get_todo_border_style(false, false, true);
+        let style = get_todo_border_style(&Theme::dark(), false, false, true);
         assert_eq!(style, Style::default().fg(Color::DarkGray));
     }
 
     #[test]
     fn get_todo_border_style_overdue_is_red() {
-        let style = get_todo_border_style(false, true, false);
+        let style = get_todo_border_style(&Theme::dark(), false, true, false);
         assert_eq!(style, Style::default().fg(Color::Red));
     }
 
     #[test]
     fn get_todo_border_style_selected_trumps_overdue() {
-        let style = get_todo_border_style(true, true, false);
+        let style = get_todo_border_style(&Theme::dark(), true, true, false);
         assert_eq!(style, Style::default().fg(Color::Yellow));
     }
 
     #[test]
     fn get_todo_border_style_overdue_trumps_dimmed() {
-        let style = get_todo_border_style(false, true, true);
+        let style = get_todo_border_style(&Theme::dark(), false, true, true);
         assert_eq!(style, Style::default().fg(Color::Red));
     }
 
@@ -989,4 +1092,36 @@ mod tests {
             "all 3 visible todos should receive a hint label"
         );
     }
+
+    #[test]
+    fn input_window_scrolls_to_keep_cursor_visible() {
+        let editor = LineEditor::new("abcdefghij");
+        let (visible, col) = input_window(&editor, 5);
+        assert_eq!(visible, "ghij");
+        assert_eq!(col, 4);
+
+        let editor = LineEditor::with_cursor("abcdefghij", 0);
+        let (visible, col) = input_window(&editor, 5);
+        assert_eq!(visible, "abcde");
+        assert_eq!(col, 0);
+    }
+
+    #[test]
+    fn draw_ui_renders_add_prompt_in_footer() {
+        use ratatui::backend::TestBackend;
+
+        let todos = vec![make_item_with_id("task a", "a", "p1")];
+        let mut state = AppState::new(todos, String::new(), String::new());
+        state.start_add_item(true);
+
+        let backend = TestBackend::new(80, 24);
+        let mut terminal = ratatui::Terminal::new(backend).unwrap();
+        terminal.draw(|f| draw_ui(f, &mut state)).unwrap();
+
+        let buf = terminal.backend().buffer().clone();
+        let footer_row: String = (0..buf.area.width)
+            .map(|x| buf[(x, buf.area.height - 3)].symbol().to_string())
+            .collect();
+        assert!(footer_row.contains("Add:  +p1"), "got: {footer_row}");
+    }
 }
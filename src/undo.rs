@@ -0,0 +1,462 @@
+use crate::store;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Maximum number of entries kept on the undo side of the journal.
+const MAX_ENTRIES: usize = 50;
+
+/// Content of one file at a point in time. `None` means the file did not exist.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FileImage {
+    pub path: String,
+    pub content: Option<String>,
+}
+
+impl FileImage {
+    fn capture(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            content: fs::read_to_string(path).ok(),
+        }
+    }
+
+    fn restore(&self) -> Result<(), Box<dyn Error>> {
+        match &self.content {
+            Some(content) => store::write_atomic(&self.path, content)?,
+            None => {
+                if let Err(e) = fs::remove_file(&self.path)
+                    && e.kind() != std::io::ErrorKind::NotFound
+                {
+                    return Err(e.into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn matches_disk(&self) -> bool {
+        fs::read_to_string(&self.path).ok() == self.content
+    }
+}
+
+/// Length of an append-only file (done.txt) before a mutation, so the
+/// journal can keep just what the mutation added instead of the whole file.
+#[derive(Clone, Debug)]
+pub struct EndMark {
+    path: String,
+    len: Option<u64>,
+}
+
+/// Bytes a mutation added to the end of a file.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Appended {
+    pub path: String,
+    /// Length before the append; `None` when the append created the file
+    pub len: Option<u64>,
+    pub text: String,
+}
+
+impl Appended {
+    /// What was added to `mark.path` since the mark was taken, reading only
+    /// the new bytes. `None` when nothing was added.
+    fn capture(mark: EndMark) -> Option<Self> {
+        let mut file = File::open(&mark.path).ok()?;
+        let start = mark.len.unwrap_or(0);
+        if file.metadata().ok()?.len() <= start {
+            return None;
+        }
+        file.seek(SeekFrom::Start(start)).ok()?;
+        let mut text = String::new();
+        file.read_to_string(&mut text).ok()?;
+        Some(Self {
+            path: mark.path,
+            len: mark.len,
+            text,
+        })
+    }
+
+    fn matches_before(&self) -> bool {
+        fs::metadata(&self.path).ok().map(|m| m.len()) == self.len
+    }
+
+    /// The file still ends with exactly the appended bytes.
+    fn matches_after(&self) -> bool {
+        let start = self.len.unwrap_or(0);
+        let Ok(mut file) = File::open(&self.path) else {
+            return false;
+        };
+        let mut tail = String::new();
+        file.metadata()
+            .is_ok_and(|m| m.len() == start + self.text.len() as u64)
+            && file.seek(SeekFrom::Start(start)).is_ok()
+            && file.read_to_string(&mut tail).is_ok()
+            && tail == self.text
+    }
+
+    fn remove(&self) -> Result<(), Box<dyn Error>> {
+        match self.len {
+            Some(len) => OpenOptions::new()
+                .write(true)
+                .open(&self.path)?
+                .set_len(len)?,
+            None => fs::remove_file(&self.path)?,
+        }
+        Ok(())
+    }
+
+    fn reapply(&self) -> Result<(), Box<dyn Error>> {
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&self.path)?;
+        file.write_all(self.text.as_bytes())?;
+        file.sync_data()?;
+        Ok(())
+    }
+}
+
+/// One reversible mutation: every affected file before and after it ran,
+/// plus what it appended to append-only files.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UndoEntry {
+    pub label: String,
+    pub before: Vec<FileImage>,
+    pub after: Vec<FileImage>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub appended: Vec<Appended>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+struct Journal {
+    undo: Vec<UndoEntry>,
+    redo: Vec<UndoEntry>,
+}
+
+/// Capture the current content of `paths` before a mutation.
+pub fn snapshot(paths: &[String]) -> Vec<FileImage> {
+    paths.iter().map(|p| FileImage::capture(p)).collect()
+}
+
+/// Note the length of `paths` before a mutation that only appends to them.
+pub fn mark_ends(paths: &[String]) -> Vec<EndMark> {
+    paths
+        .iter()
+        .map(|p| EndMark {
+            path: p.clone(),
+            len: fs::metadata(p).ok().map(|m| m.len()),
+        })
+        .collect()
+}
+
+/// Undo/redo stacks persisted in `{todotxt_dir}/.torudo/undo.json`.
+///
+/// Every operation loads and saves the journal, so the TUI and
+/// `torudo undo` always see the same history.
+pub struct UndoStack {
+    dir: PathBuf,
+    path: PathBuf,
+}
+
+impl UndoStack {
+    pub fn new(todotxt_dir: &str) -> Self {
+        Self {
+            dir: PathBuf::from(todotxt_dir),
+            path: Path::new(todotxt_dir).join(".torudo").join("undo.json"),
+        }
+    }
+
+    fn load(&self) -> Journal {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, journal: &Journal) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        store::write_atomic(&self.path, &serde_json::to_string(journal)?)?;
+        Ok(())
+    }
+
+    /// Record a finished mutation given the images captured before it ran.
+    /// Nothing is recorded when no file actually changed.
+    pub fn record(&self, label: &str, before: Vec<FileImage>) -> Result<(), Box<dyn Error>> {
+        self.record_appending(label, before, Vec::new())
+    }
+
+    /// Like `record`, for a mutation that also appended to the files marked
+    /// with [`mark_ends`].
+    pub fn record_appending(
+        &self,
+        label: &str,
+        before: Vec<FileImage>,
+        ends: Vec<EndMark>,
+    ) -> Result<(), Box<dyn Error>> {
+        let after: Vec<FileImage> = before.iter().map(|i| FileImage::capture(&i.path)).collect();
+        let appended: Vec<Appended> = ends.into_iter().filter_map(Appended::capture).collect();
+        if before == after && appended.is_empty() {
+            return Ok(());
+        }
+        let _lock = store::lock(&self.dir)?;
+        let mut journal = self.load();
+        journal.undo.push(UndoEntry {
+            label: label.to_string(),
+            before,
+            after,
+            appended,
+        });
+        if journal.undo.len() > MAX_ENTRIES {
+            let excess = journal.undo.len() - MAX_ENTRIES;
+            journal.undo.drain(..excess);
+        }
+        journal.redo.clear();
+        debug!("Recorded undo entry: {label}");
+        self.save(&journal)
+    }
+
+    /// Restore the before-images of the latest entry. Returns its label, or
+    /// `None` when there is nothing to undo.
+    pub fn undo(&self) -> Result<Option<String>, Box<dyn Error>> {
+        let _lock = store::lock(&self.dir)?;
+        let mut journal = self.load();
+        let Some(entry) = journal.undo.pop() else {
+            return Ok(None);
+        };
+        apply(&entry, Direction::Undo)?;
+        let label = entry.label.clone();
+        journal.redo.push(entry);
+        self.save(&journal)?;
+        Ok(Some(label))
+    }
+
+    /// Reapply the after-images of the latest undone entry.
+    pub fn redo(&self) -> Result<Option<String>, Box<dyn Error>> {
+        let _lock = store::lock(&self.dir)?;
+        let mut journal = self.load();
+        let Some(entry) = journal.redo.pop() else {
+            return Ok(None);
+        };
+        apply(&entry, Direction::Redo)?;
+        let label = entry.label.clone();
+        journal.undo.push(entry);
+        self.save(&journal)?;
+        Ok(Some(label))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Undo,
+    Redo,
+}
+
+/// Move the files of `entry` to its before (undo) or after (redo) state,
+/// but only if every file is still in the other state; otherwise an edit
+/// made since the entry was recorded would be clobbered.
+fn apply(entry: &UndoEntry, direction: Direction) -> Result<(), Box<dyn Error>> {
+    let (expected, target) = match direction {
+        Direction::Undo => (&entry.after, &entry.before),
+        Direction::Redo => (&entry.before, &entry.after),
+    };
+    let changed = expected
+        .iter()
+        .find(|i| !i.matches_disk())
+        .map(|i| &i.path)
+        .or_else(|| {
+            entry
+                .appended
+                .iter()
+                .find(|a| match direction {
+                    Direction::Undo => !a.matches_after(),
+                    Direction::Redo => !a.matches_before(),
+                })
+                .map(|a| &a.path)
+        });
+    if let Some(path) = changed {
+        return Err(format!("{path} changed since \"{}\"; not restoring", entry.label).into());
+    }
+    for image in target {
+        image.restore()?;
+    }
+    for appended in &entry.appended {
+        match direction {
+            Direction::Undo => appended.remove()?,
+            Direction::Redo => appended.reapply()?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path_in(dir: &tempfile::TempDir, name: &str) -> String {
+        dir.path().join(name).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_undo_redo_restores_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let stack = UndoStack::new(dir.path().to_str().unwrap());
+        let todo = path_in(&dir, "todo.txt");
+        fs::write(&todo, "a\n").unwrap();
+
+        let before = snapshot(std::slice::from_ref(&todo));
+        fs::write(&todo, "b\n").unwrap();
+        stack.record("Edit", before).unwrap();
+
+        assert_eq!(stack.undo().unwrap().as_deref(), Some("Edit"));
+        assert_eq!(fs::read_to_string(&todo).unwrap(), "a\n");
+        assert_eq!(stack.redo().unwrap().as_deref(), Some("Edit"));
+        assert_eq!(fs::read_to_string(&todo).unwrap(), "b\n");
+    }
+
+    #[test]
+    fn test_undo_recreates_deleted_file_and_removes_created_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let stack = UndoStack::new(dir.path().to_str().unwrap());
+        let md = path_in(&dir, "gone.md");
+        let created = path_in(&dir, "new.txt");
+        fs::write(&md, "# detail\n").unwrap();
+
+        let before = snapshot(&[md.clone(), created.clone()]);
+        fs::remove_file(&md).unwrap();
+        fs::write(&created, "x\n").unwrap();
+        stack.record("Delete", before).unwrap();
+
+        stack.undo().unwrap();
+        assert_eq!(fs::read_to_string(&md).unwrap(), "# detail\n");
+        assert!(!Path::new(&created).exists());
+    }
+
+    #[test]
+    fn test_appends_are_journaled_without_the_whole_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let stack = UndoStack::new(dir.path().to_str().unwrap());
+        let done = path_in(&dir, "done.txt");
+        let created = path_in(&dir, "new.txt");
+        let archive = "x 2026-01-01 Old id:o1\r\n".repeat(1000);
+        fs::write(&done, &archive).unwrap();
+
+        let ends = mark_ends(&[done.clone(), created.clone()]);
+        for path in [&done, &created] {
+            crate::todo_file::append_line(path, "x 2026-10-16 New id:n1").unwrap();
+        }
+        stack
+            .record_appending("Complete", Vec::new(), ends)
+            .unwrap();
+        let journal = fs::read_to_string(dir.path().join(".torudo/undo.json")).unwrap();
+        assert!(journal.len() < 500, "{journal}");
+
+        assert_eq!(stack.undo().unwrap().as_deref(), Some("Complete"));
+        assert_eq!(fs::read_to_string(&done).unwrap(), archive);
+        assert!(!Path::new(&created).exists());
+
+        stack.redo().unwrap();
+        assert!(
+            fs::read_to_string(&done)
+                .unwrap()
+                .ends_with("\r\nx 2026-10-16 New id:n1\r\n")
+        );
+        assert_eq!(
+            fs::read_to_string(&created).unwrap(),
+            "x 2026-10-16 New id:n1\n"
+        );
+
+        // Appended to again since the redo: undo must not cut it off
+        crate::todo_file::append_line(&done, "x 2026-10-17 Later id:n2").unwrap();
+        assert!(stack.undo().is_err());
+        assert!(
+            fs::read_to_string(&done)
+                .unwrap()
+                .ends_with("Later id:n2\r\n")
+        );
+    }
+
+    #[test]
+    fn test_journal_is_shared_between_instances() {
+        let dir = tempfile::tempdir().unwrap();
+        let todo = path_in(&dir, "todo.txt");
+        fs::write(&todo, "a\n").unwrap();
+
+        let before = snapshot(std::slice::from_ref(&todo));
+        fs::write(&todo, "b\n").unwrap();
+        UndoStack::new(dir.path().to_str().unwrap())
+            .record("Edit", before)
+            .unwrap();
+
+        let other = UndoStack::new(dir.path().to_str().unwrap());
+        assert_eq!(other.undo().unwrap().as_deref(), Some("Edit"));
+        assert_eq!(fs::read_to_string(&todo).unwrap(), "a\n");
+    }
+
+    #[test]
+    fn test_noop_mutation_is_not_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let stack = UndoStack::new(dir.path().to_str().unwrap());
+        let todo = path_in(&dir, "todo.txt");
+        fs::write(&todo, "a\n").unwrap();
+
+        stack.record("Noop", snapshot(&[todo])).unwrap();
+        assert_eq!(stack.undo().unwrap(), None);
+    }
+
+    #[test]
+    fn test_new_record_clears_redo() {
+        let dir = tempfile::tempdir().unwrap();
+        let stack = UndoStack::new(dir.path().to_str().unwrap());
+        let todo = path_in(&dir, "todo.txt");
+        fs::write(&todo, "a\n").unwrap();
+
+        let before = snapshot(std::slice::from_ref(&todo));
+        fs::write(&todo, "b\n").unwrap();
+        stack.record("First", before).unwrap();
+        stack.undo().unwrap();
+
+        let before = snapshot(std::slice::from_ref(&todo));
+        fs::write(&todo, "c\n").unwrap();
+        stack.record("Second", before).unwrap();
+        assert_eq!(stack.redo().unwrap(), None);
+    }
+
+    #[test]
+    fn test_undo_refuses_when_file_changed_externally() {
+        let dir = tempfile::tempdir().unwrap();
+        let stack = UndoStack::new(dir.path().to_str().unwrap());
+        let todo = path_in(&dir, "todo.txt");
+        fs::write(&todo, "a\n").unwrap();
+
+        let before = snapshot(std::slice::from_ref(&todo));
+        fs::write(&todo, "b\n").unwrap();
+        stack.record("Edit", before).unwrap();
+        fs::write(&todo, "edited elsewhere\n").unwrap();
+
+        assert!(stack.undo().is_err());
+        assert_eq!(fs::read_to_string(&todo).unwrap(), "edited elsewhere\n");
+    }
+
+    #[test]
+    fn test_undo_stack_is_bounded() {
+        let dir = tempfile::tempdir().unwrap();
+        let stack = UndoStack::new(dir.path().to_str().unwrap());
+        let todo = path_in(&dir, "todo.txt");
+        for i in 0..=MAX_ENTRIES {
+            let before = snapshot(std::slice::from_ref(&todo));
+            fs::write(&todo, format!("{i}\n")).unwrap();
+            stack.record("Edit", before).unwrap();
+        }
+        let mut undone = 0;
+        while stack.undo().unwrap().is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, MAX_ENTRIES);
+        assert_eq!(fs::read_to_string(&todo).unwrap(), "0\n");
+    }
+}
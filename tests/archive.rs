@@ -0,0 +1,74 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+const fn bin() -> &'static str {
+    env!("CARGO_BIN_EXE_torudo")
+}
+
+fn fresh_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn archive_rotate_by_year_moves_old_completions() {
+    let dir = fresh_dir("torudo_it_archive_rotate");
+    fs::write(dir.join("todo.txt"), "").unwrap();
+    fs::write(
+        dir.join("done.txt"),
+        "x 2020-05-01 Old id:a from:todo\nx 2021-01-02 Older id:b\nx Undated id:c\n",
+    )
+    .unwrap();
+
+    let output = Command::new(bin())
+        .args([
+            "--todotxt-dir",
+            dir.to_str().unwrap(),
+            "archive",
+            "rotate",
+            "--by",
+            "year",
+        ])
+        .output()
+        .expect("failed to run torudo");
+
+    assert!(
+        output.status.success(),
+        "non-zero exit: stderr={}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Moved 1 item(s) to done-2020.txt\nMoved 1 item(s) to done-2021.txt\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("done.txt")).unwrap(),
+        "x Undated id:c\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("done-2020.txt")).unwrap(),
+        "x 2020-05-01 Old id:a from:todo\n"
+    );
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn archive_rotate_without_period_reports_error() {
+    let dir = fresh_dir("torudo_it_archive_rotate_off");
+    fs::write(dir.join("todo.txt"), "").unwrap();
+
+    let output = Command::new(bin())
+        .env("XDG_CONFIG_HOME", &dir)
+        .args(["--todotxt-dir", dir.to_str().unwrap(), "archive", "rotate"])
+        .output()
+        .expect("failed to run torudo");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("rotation is off"));
+
+    fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,114 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+const fn bin() -> &'static str {
+    env!("CARGO_BIN_EXE_torudo")
+}
+
+fn fresh_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn torudo(xdg: &PathBuf, args: &[&str]) -> std::process::Output {
+    Command::new(bin())
+        .env("XDG_CONFIG_HOME", xdg)
+        .env_remove("TODOTXT_DIR")
+        .env_remove("NVIM_LISTEN_ADDRESS")
+        .args(args)
+        .output()
+        .expect("failed to run torudo")
+}
+
+#[test]
+fn config_show_merges_files_and_flags() {
+    let root = fresh_dir("torudo_it_config_show");
+    let xdg = root.join("xdg");
+    let todo_dir = root.join("todotxt");
+    fs::create_dir_all(xdg.join("torudo")).unwrap();
+    fs::create_dir_all(todo_dir.join(".torudo")).unwrap();
+    fs::write(
+        xdg.join("torudo").join("config.toml"),
+        format!(
+            "todotxt_dir = {:?}\n[files]\ninbox = \"in.txt\"\n[keys]\nquit = \"Q\"\n",
+            todo_dir.to_str().unwrap()
+        ),
+    )
+    .unwrap();
+    fs::write(
+        todo_dir.join(".torudo").join("config.toml"),
+        "[preview]\nmax_items = 7\n",
+    )
+    .unwrap();
+
+    let output = torudo(&xdg, &["--view", "agenda", "config", "show"]);
+    assert!(
+        output.status.success(),
+        "non-zero exit: stderr={}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("(loaded)"), "{stdout}");
+    assert!(stdout.contains("view = \"agenda\""), "{stdout}");
+    assert!(stdout.contains("inbox = \"in.txt\""), "{stdout}");
+    assert!(stdout.contains("max_items = 7"), "{stdout}");
+    assert!(stdout.contains("quit = \"Q\""), "{stdout}");
+    assert!(stdout.contains("undo = \"u\""), "{stdout}");
+
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn inbox_add_uses_configured_file_name() {
+    let root = fresh_dir("torudo_it_config_inbox_name");
+    let xdg = root.join("xdg");
+    fs::create_dir_all(xdg.join("torudo")).unwrap();
+    fs::write(
+        xdg.join("torudo").join("config.toml"),
+        "[files]\ninbox = \"capture.txt\"\n",
+    )
+    .unwrap();
+
+    let output = torudo(
+        &xdg,
+        &[
+            "--todotxt-dir",
+            root.to_str().unwrap(),
+            "inbox",
+            "add",
+            "Buy milk",
+        ],
+    );
+    assert!(output.status.success());
+    let capture = fs::read_to_string(root.join("capture.txt")).unwrap();
+    assert!(capture.contains("Buy milk"));
+    assert!(!root.join("inbox.txt").exists());
+
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn invalid_config_exits_with_message() {
+    let root = fresh_dir("torudo_it_config_invalid");
+    let xdg = root.join("xdg");
+    fs::create_dir_all(xdg.join("torudo")).unwrap();
+    fs::write(
+        xdg.join("torudo").join("config.toml"),
+        "[watch]\ndebounce_ms = \"fast\"\n",
+    )
+    .unwrap();
+
+    let output = torudo(
+        &xdg,
+        &["--todotxt-dir", root.to_str().unwrap(), "config", "show"],
+    );
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("config.toml"), "{stderr}");
+    assert!(stderr.contains("debounce_ms"), "{stderr}");
+
+    fs::remove_dir_all(&root).ok();
+}
@@ -1,6 +1,6 @@
 use std::fs;
 use std::path::PathBuf;
-use std::process::Command;
+use std::process::{Command, Stdio};
 
 const fn bin() -> &'static str {
     env!("CARGO_BIN_EXE_torudo")
@@ -18,6 +18,7 @@ fn inbox_add_happy_path() {
     let dir = fresh_dir("torudo_it_inbox_add_happy");
 
     let output = Command::new(bin())
+        .env("XDG_CONFIG_HOME", &dir)
         .args([
             "--todotxt-dir",
             dir.to_str().unwrap(),
@@ -56,6 +57,7 @@ fn inbox_add_preserves_existing_id() {
     let dir = fresh_dir("torudo_it_inbox_add_keep_id");
 
     let output = Command::new(bin())
+        .env("XDG_CONFIG_HOME", &dir)
         .args([
             "--todotxt-dir",
             dir.to_str().unwrap(),
@@ -84,6 +86,7 @@ fn inbox_add_accepts_unquoted_multi_word_text() {
     let dir = fresh_dir("torudo_it_inbox_add_unquoted_multi");
 
     let output = Command::new(bin())
+        .env("XDG_CONFIG_HOME", &dir)
         .args([
             "--todotxt-dir",
             dir.to_str().unwrap(),
@@ -126,6 +129,7 @@ fn inbox_add_accepts_unquoted_priority_and_projects() {
     let dir = fresh_dir("torudo_it_inbox_add_unquoted_priority");
 
     let output = Command::new(bin())
+        .env("XDG_CONFIG_HOME", &dir)
         .args([
             "--todotxt-dir",
             dir.to_str().unwrap(),
@@ -160,6 +164,7 @@ fn inbox_add_successive_calls_produce_distinct_uuids() {
 
     let run = |text: &str| -> serde_json::Value {
         let output = Command::new(bin())
+            .env("XDG_CONFIG_HOME", &dir)
             .args(["--todotxt-dir", dir.to_str().unwrap(), "inbox", "add", text])
             .output()
             .expect("failed to run torudo");
@@ -186,3 +191,40 @@ fn inbox_add_successive_calls_produce_distinct_uuids() {
 
     fs::remove_dir_all(&dir).ok();
 }
+
+#[test]
+fn inbox_add_concurrent_calls_keep_every_item() {
+    let dir = fresh_dir("torudo_it_inbox_add_concurrent");
+
+    // Start every process before waiting for any of them
+    let children: Vec<_> = (0..8)
+        .map(|i| {
+            Command::new(bin())
+                .env("XDG_CONFIG_HOME", &dir)
+                .args([
+                    "--todotxt-dir",
+                    dir.to_str().unwrap(),
+                    "inbox",
+                    "add",
+                    &format!("Task {i}"),
+                ])
+                .stdout(Stdio::null())
+                .spawn()
+                .expect("failed to run torudo")
+        })
+        .collect();
+    for mut child in children {
+        assert!(child.wait().unwrap().success());
+    }
+
+    let inbox = fs::read_to_string(dir.join("inbox.txt")).unwrap();
+    for i in 0..8 {
+        assert!(
+            inbox.contains(&format!("Task {i} ")),
+            "Task {i} lost:\n{inbox}"
+        );
+    }
+    assert_eq!(inbox.lines().count(), 8);
+
+    fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,111 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+const fn bin() -> &'static str {
+    env!("CARGO_BIN_EXE_torudo")
+}
+
+fn fresh_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn list_filter_prints_matching_items_as_json_array() {
+    let dir = fresh_dir("torudo_it_list_filter");
+    fs::write(
+        dir.join("todo.txt"),
+        "(A) Call Bob @phone due:2026-10-20 id:a\n(B) Call Ann @phone +someday id:b\n(C) Email Carl @mail id:c\n",
+    )
+    .unwrap();
+
+    let output = Command::new(bin())
+        .env("XDG_CONFIG_HOME", &dir)
+        .args([
+            "--todotxt-dir",
+            dir.to_str().unwrap(),
+            "list",
+            "--filter",
+            "@phone pri:A-B -+someday due<2026-11-01",
+        ])
+        .output()
+        .expect("failed to run torudo");
+
+    assert!(
+        output.status.success(),
+        "non-zero exit: stderr={}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("stdout is JSON");
+    let items = json.as_array().expect("array");
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["id"], "a");
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn list_with_invalid_filter_reports_error() {
+    let dir = fresh_dir("torudo_it_list_bad_filter");
+    fs::write(dir.join("todo.txt"), "Task id:a\n").unwrap();
+
+    let output = Command::new(bin())
+        .env("XDG_CONFIG_HOME", &dir)
+        .args([
+            "--todotxt-dir",
+            dir.to_str().unwrap(),
+            "list",
+            "--filter",
+            "has:nothing",
+        ])
+        .output()
+        .expect("failed to run torudo");
+
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("unknown has:nothing"), "stderr={stderr}");
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn list_mode_project_and_formats() {
+    let dir = fresh_dir("torudo_it_list_modes");
+    fs::write(
+        dir.join("waiting.txt"),
+        "Reply from Ann +work id:w1\nParcel +home id:w2\n",
+    )
+    .unwrap();
+    fs::write(dir.join("done.txt"), "x 2026-10-01 Shipped +work id:d1\n").unwrap();
+
+    let run = |args: &[&str]| {
+        let output = Command::new(bin())
+            .env("XDG_CONFIG_HOME", &dir)
+            .args(["--todotxt-dir", dir.to_str().unwrap(), "list"])
+            .args(args)
+            .output()
+            .expect("failed to run torudo");
+        assert!(
+            output.status.success(),
+            "non-zero exit: stderr={}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let txt = run(&["--mode", "waiting", "--project", "work", "--format", "txt"]);
+    assert_eq!(txt, "Reply from Ann +work id:w1\n");
+
+    let jsonl = run(&["--mode", "done", "--format", "jsonl"]);
+    let item: serde_json::Value = serde_json::from_str(jsonl.trim()).unwrap();
+    assert_eq!(item["id"], "d1");
+    assert_eq!(item["completed"], true);
+
+    let empty = run(&["--mode", "someday"]);
+    assert_eq!(empty.trim(), "[]");
+
+    fs::remove_dir_all(&dir).ok();
+}
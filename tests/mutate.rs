@@ -0,0 +1,151 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const fn bin() -> &'static str {
+    env!("CARGO_BIN_EXE_torudo")
+}
+
+fn fresh_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    Command::new(bin())
+        .env("XDG_CONFIG_HOME", dir)
+        .args(["--todotxt-dir", dir.to_str().unwrap()])
+        .args(args)
+        .output()
+        .expect("failed to run torudo")
+}
+
+fn json(output: &Output) -> serde_json::Value {
+    assert!(
+        output.status.success(),
+        "non-zero exit: stderr={}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn pri_mv_done_print_resulting_item() {
+    let dir = fresh_dir("torudo_it_mutate_flow");
+    fs::write(dir.join("inbox.txt"), "Plan trip +travel id:m1\n").unwrap();
+
+    let item = json(&run(&dir, &["pri", "m1", "b"]));
+    assert_eq!(item["priority"], "B");
+
+    let item = json(&run(&dir, &["mv", "m1", "todo"]));
+    assert_eq!(item["id"], "m1");
+    assert!(
+        fs::read_to_string(dir.join("todo.txt"))
+            .unwrap()
+            .contains("(B) Plan trip +travel id:m1")
+    );
+
+    let item = json(&run(&dir, &["done", "m1"]));
+    assert_eq!(item["completed"], true);
+    assert!(
+        fs::read_to_string(dir.join("done.txt"))
+            .unwrap()
+            .contains("id:m1")
+    );
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn rm_prints_removed_item_and_is_undoable() {
+    let dir = fresh_dir("torudo_it_mutate_rm");
+    fs::write(dir.join("todo.txt"), "Old chore id:r1\n").unwrap();
+
+    let item = json(&run(&dir, &["rm", "r1"]));
+    assert_eq!(item["title"], "Old chore");
+    assert!(
+        !fs::read_to_string(dir.join("todo.txt"))
+            .unwrap()
+            .contains("id:r1")
+    );
+
+    assert!(run(&dir, &["undo"]).status.success());
+    assert_eq!(
+        fs::read_to_string(dir.join("todo.txt")).unwrap(),
+        "Old chore id:r1\n"
+    );
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn distinct_exit_codes_for_missing_id_and_io_errors() {
+    let dir = fresh_dir("torudo_it_mutate_errors");
+    fs::write(dir.join("todo.txt"), "Task id:e1\n").unwrap();
+
+    let output = run(&dir, &["done", "missing"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("id:missing"));
+
+    // A directory where done.txt should be makes the write fail.
+    fs::create_dir(dir.join("done.txt")).unwrap();
+    let output = run(&dir, &["done", "e1"]);
+    assert_eq!(output.status.code(), Some(4));
+
+    let output = run(&dir, &["pri", "e1", "AB"]);
+    assert_eq!(
+        output.status.code(),
+        Some(2),
+        "invalid priority is a usage error"
+    );
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn interrupted_move_is_finished_on_next_start() {
+    let dir = fresh_dir("torudo_it_mutate_recover");
+    let inbox = dir.join("inbox.txt");
+    let todo = dir.join("todo.txt");
+    // State left by a crash between writing todo.txt and rewriting inbox.txt
+    fs::write(&inbox, "Keep +home id:k1\nMoved +work id:m1\n").unwrap();
+    fs::write(&todo, "Moved +work id:m1\n").unwrap();
+    fs::create_dir_all(dir.join(".torudo")).unwrap();
+    fs::write(
+        dir.join(".torudo/pending.json"),
+        serde_json::json!({
+            "id": "m1",
+            "from": inbox.to_str().unwrap(),
+            "to": todo.to_str().unwrap(),
+        })
+        .to_string(),
+    )
+    .unwrap();
+    // Added by another writer after the crash; recovery must not drop it
+    fs::OpenOptions::new()
+        .append(true)
+        .open(&inbox)
+        .unwrap()
+        .write_all(b"Later +home id:l1\n")
+        .unwrap();
+
+    let output = run(&dir, &["list", "--mode", "inbox", "--format", "txt"]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("interrupted move of id:m1"), "{stderr}");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Keep +home id:k1\nLater +home id:l1\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&inbox).unwrap(),
+        "Keep +home id:k1\nLater +home id:l1\n"
+    );
+    assert_eq!(fs::read_to_string(&todo).unwrap(), "Moved +work id:m1\n");
+    assert!(!dir.join(".torudo/pending.json").exists());
+
+    fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,69 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const fn bin() -> &'static str {
+    env!("CARGO_BIN_EXE_torudo")
+}
+
+fn fresh_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn run(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new(bin())
+        .env("XDG_CONFIG_HOME", dir)
+        .args(["--todotxt-dir", dir.to_str().unwrap()])
+        .args(args)
+        .output()
+        .expect("failed to run torudo");
+    assert!(
+        output.status.success(),
+        "non-zero exit: stderr={}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn undo_and_redo_inbox_add_via_journal() {
+    let dir = fresh_dir("torudo_it_undo_inbox_add");
+    fs::write(dir.join("inbox.txt"), "Existing id:keep\n").unwrap();
+
+    run(&dir, &["inbox", "add", "Captured idea"]);
+    assert!(
+        fs::read_to_string(dir.join("inbox.txt"))
+            .unwrap()
+            .contains("Captured idea")
+    );
+
+    let stdout = run(&dir, &["undo"]);
+    assert_eq!(stdout.trim(), "Undid: Add");
+    assert_eq!(
+        fs::read_to_string(dir.join("inbox.txt")).unwrap(),
+        "Existing id:keep\n"
+    );
+
+    let stdout = run(&dir, &["redo"]);
+    assert_eq!(stdout.trim(), "Redid: Add");
+    assert!(
+        fs::read_to_string(dir.join("inbox.txt"))
+            .unwrap()
+            .contains("Captured idea")
+    );
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn undo_with_empty_journal() {
+    let dir = fresh_dir("torudo_it_undo_empty");
+
+    let stdout = run(&dir, &["undo"]);
+    assert_eq!(stdout.trim(), "Nothing to undo");
+
+    fs::remove_dir_all(&dir).ok();
+}